@@ -0,0 +1,4 @@
+-- Existing rows are stamped with the time of the migration, as their real creation time is unknown.
+ALTER TABLE contacts ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+ALTER TABLE contacts ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+UPDATE contacts SET created_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP;
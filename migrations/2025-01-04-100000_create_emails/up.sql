@@ -0,0 +1,8 @@
+CREATE TABLE emails
+(
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id INTEGER NOT NULL REFERENCES contacts (id),
+    label      TEXT    NOT NULL,
+    address    TEXT    NOT NULL
+);
+CREATE INDEX emails_contact_id ON emails (contact_id);
@@ -0,0 +1,9 @@
+CREATE TABLE relationships
+(
+    id                 INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id         INTEGER NOT NULL REFERENCES contacts (id),
+    related_contact_id INTEGER NOT NULL REFERENCES contacts (id),
+    label              TEXT    NOT NULL
+);
+CREATE INDEX relationships_contact_id ON relationships (contact_id);
+CREATE INDEX relationships_related_contact_id ON relationships (related_contact_id);
@@ -0,0 +1,5 @@
+CREATE TABLE settings
+(
+    key   TEXT PRIMARY KEY NOT NULL,
+    value TEXT NOT NULL
+);
@@ -0,0 +1,8 @@
+CREATE TABLE tags
+(
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id INTEGER NOT NULL REFERENCES contacts (id),
+    name       TEXT    NOT NULL
+);
+CREATE INDEX tags_contact_id ON tags (contact_id);
+CREATE INDEX tags_name ON tags (name);
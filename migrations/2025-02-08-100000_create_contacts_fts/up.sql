@@ -0,0 +1,4 @@
+-- The full-text index needs SQLite's FTS5 module, which not every SQLite build has, so it is
+-- created when a connection is opened, once FTS5 is known to be available. See
+-- `migrations::create_search_index`.
+DROP TABLE IF EXISTS contacts_fts;
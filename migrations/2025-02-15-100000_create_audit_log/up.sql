@@ -0,0 +1,8 @@
+CREATE TABLE audit_log
+(
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at TIMESTAMP NOT NULL,
+    operation  TEXT      NOT NULL,
+    contact_id INTEGER
+);
+CREATE INDEX audit_log_contact_id ON audit_log (contact_id);
@@ -0,0 +1,3 @@
+-- JSON snapshots of the contact before and after the change, NULL when it didn't exist.
+ALTER TABLE audit_log ADD COLUMN before TEXT;
+ALTER TABLE audit_log ADD COLUMN after TEXT;
@@ -0,0 +1,52 @@
+-- The contacts update trigger reads emails, so it can't exist while that table is being replaced.
+DROP TRIGGER IF EXISTS contacts_fts_update;
+
+CREATE TABLE emails_new
+(
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id INTEGER NOT NULL REFERENCES contacts (id),
+    label      TEXT    NOT NULL,
+    address    TEXT    NOT NULL
+);
+INSERT INTO emails_new (id, contact_id, label, address)
+SELECT id, contact_id, label, address
+FROM emails
+WHERE contact_id IN (SELECT id FROM contacts);
+DROP TABLE emails;
+ALTER TABLE emails_new RENAME TO emails;
+CREATE INDEX emails_contact_id ON emails (contact_id);
+
+CREATE TABLE tags_new
+(
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id INTEGER NOT NULL REFERENCES contacts (id),
+    name       TEXT    NOT NULL
+);
+INSERT INTO tags_new (id, contact_id, name)
+SELECT id, contact_id, name
+FROM tags
+WHERE contact_id IN (SELECT id FROM contacts);
+DROP TABLE tags;
+ALTER TABLE tags_new RENAME TO tags;
+CREATE INDEX tags_contact_id ON tags (contact_id);
+CREATE INDEX tags_name ON tags (name);
+
+CREATE TABLE relationships_new
+(
+    id                 INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id         INTEGER NOT NULL REFERENCES contacts (id),
+    related_contact_id INTEGER NOT NULL REFERENCES contacts (id),
+    label              TEXT    NOT NULL
+);
+INSERT INTO relationships_new (id, contact_id, related_contact_id, label)
+SELECT id, contact_id, related_contact_id, label
+FROM relationships
+WHERE contact_id IN (SELECT id FROM contacts)
+  AND related_contact_id IN (SELECT id FROM contacts);
+DROP TABLE relationships;
+ALTER TABLE relationships_new RENAME TO relationships;
+CREATE INDEX relationships_contact_id ON relationships (contact_id);
+CREATE INDEX relationships_related_contact_id ON relationships (related_contact_id);
+
+-- The full-text index triggers, including those dropped with the emails table, are created again
+-- when the database is next opened.
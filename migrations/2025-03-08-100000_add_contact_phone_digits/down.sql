@@ -0,0 +1,2 @@
+DROP INDEX contacts_phone_digits;
+ALTER TABLE contacts DROP COLUMN phone_digits;
@@ -0,0 +1,8 @@
+-- The phone number reduced to its digits, so a number can be looked up however it was typed. The
+-- column is virtual: it takes no space in the table, only in its index.
+ALTER TABLE contacts
+    ADD COLUMN phone_digits TEXT GENERATED ALWAYS AS (
+        REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(phone, '(', ''), ')', ''), '-', ''), ' ', ''), '.', ''), '+', '')
+    ) VIRTUAL;
+
+CREATE INDEX contacts_phone_digits ON contacts (phone_digits);
@@ -0,0 +1,7 @@
+-- Advanced search criteria saved under a name, which is matched ignoring case.
+CREATE TABLE saved_searches
+(
+    name       TEXT PRIMARY KEY NOT NULL COLLATE NOCASE,
+    criteria   TEXT      NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,2 @@
+-- Kept apart from the phone number, so standardizing the number can't mangle it.
+ALTER TABLE contacts ADD COLUMN extension TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,2 @@
+-- The day the user last got in touch with the contact, NULL if never recorded.
+ALTER TABLE contacts ADD COLUMN last_contacted DATE;
@@ -0,0 +1,9 @@
+DROP TRIGGER IF EXISTS emails_fts_delete;
+DROP TRIGGER IF EXISTS emails_fts_update;
+DROP TRIGGER IF EXISTS emails_fts_insert;
+DROP TRIGGER IF EXISTS contacts_fts_delete;
+DROP TRIGGER IF EXISTS contacts_fts_update;
+DROP TRIGGER IF EXISTS contacts_fts_insert;
+DROP TABLE IF EXISTS contacts_fts;
+
+ALTER TABLE contacts DROP COLUMN company;
@@ -0,0 +1,12 @@
+-- The organization a contact works for, exported as the vCard ORG property.
+ALTER TABLE contacts ADD COLUMN company TEXT NOT NULL DEFAULT '';
+
+-- FTS5 tables can't gain a column, so the full-text index is dropped, to be rebuilt with the
+-- company when the database is next opened.
+DROP TRIGGER IF EXISTS emails_fts_delete;
+DROP TRIGGER IF EXISTS emails_fts_update;
+DROP TRIGGER IF EXISTS emails_fts_insert;
+DROP TRIGGER IF EXISTS contacts_fts_delete;
+DROP TRIGGER IF EXISTS contacts_fts_update;
+DROP TRIGGER IF EXISTS contacts_fts_insert;
+DROP TABLE IF EXISTS contacts_fts;
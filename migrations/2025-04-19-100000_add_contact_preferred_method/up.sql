@@ -0,0 +1,2 @@
+-- How the contact prefers to be reached: phone, email or text, empty if unknown.
+ALTER TABLE contacts ADD COLUMN preferred_method TEXT NOT NULL DEFAULT '';
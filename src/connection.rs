@@ -1,26 +1,218 @@
+use diesel::connection::{
+    set_default_instrumentation, Instrumentation, InstrumentationEvent, SimpleConnection,
+};
 use diesel::prelude::*;
 use diesel::sqlite::SqliteConnection;
 use dotenv::dotenv;
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::env;
+use std::sync::Mutex;
 
-use crate::migrations::run_migrations;
+use crate::migrations::{create_search_index, run_migrations};
+use crate::phone_book::info::is_in_memory;
+use crate::settings;
+
+/// Database given with `--database`, used instead of the `DATABASE_URL` environment variable.
+static DATABASE_URL: Mutex<Option<String>> = Mutex::new(None);
+
+/// Databases, by path, already migrated and given a search index by this process.
+static PREPARED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+thread_local! {
+    /// Database of the phone book opened with `PhoneBook::open` last used on this thread, so
+    /// each phone book works on its own file whatever was opened after it.
+    static ACTIVE_DATABASE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Makes every connection opened from now on use the given database instead of the one named by
+/// the `DATABASE_URL` environment variable.
+///
+/// # Arguments
+///
+/// * `database_url` - The path of the SQLite database.
+pub fn use_database(database_url: &str) {
+    let mut url = DATABASE_URL.lock().unwrap_or_else(|err| err.into_inner());
+    *url = Some(database_url.to_string());
+}
+
+/// Makes the connections this thread opens from now on use the database of a phone book opened
+/// with `PhoneBook::open`, before each of its methods runs.
+///
+/// # Arguments
+///
+/// * `database_url` - The path of the SQLite database.
+pub(crate) fn activate_database(database_url: &str) {
+    ACTIVE_DATABASE.with(|active| *active.borrow_mut() = Some(database_url.to_string()));
+}
+
+/// Returns the path of the database in use: the one of the phone book opened with
+/// `PhoneBook::open` last used on this thread, or else the one given with `--database`, or else
+/// the one named by the `DATABASE_URL` environment variable.
+///
+/// # Returns
+///
+/// The path, or `None` if no database is configured.
+pub fn database_url() -> Option<String> {
+    if let Some(active) = ACTIVE_DATABASE.with(|active| active.borrow().clone()) {
+        return Some(active);
+    }
+    dotenv().ok();
+    let given = DATABASE_URL
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone();
+    given.or_else(|| env::var("DATABASE_URL").ok())
+}
 
 /// Establishes a connection to the SQLite database using the `DATABASE_URL` environment variable.
 ///
+/// The pragmas from `settings::connection_pragmas` are applied first: a 5 second busy timeout,
+/// write-ahead logging, `synchronous=NORMAL` and enforced foreign keys by default. If they are
+/// misconfigured the defaults are used, and `settings::validate` reports the problem at startup.
+/// The first connection to each database then runs the migrations and creates the search index.
+///
 /// # Panics
 ///
-/// This function will panic if the `DATABASE_URL` environment variable is not set.
+/// This function will panic if the `DATABASE_URL` environment variable is not set, or if the
+/// database can't be opened, configured or migrated.
 ///
 /// # Returns
 ///
 /// Returns a `SqliteConnection` instance representing the established connection to the database.
 pub fn establish_connection() -> SqliteConnection {
-    dotenv().ok();
+    try_establish_connection().unwrap_or_else(|err| panic!("{}", err))
+}
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+/// Establishes a connection like `establish_connection`, but reports failures instead of
+/// panicking.
+///
+/// # Returns
+///
+/// The connection, or an error message if the database isn't configured or can't be opened,
+/// configured or migrated.
+pub fn try_establish_connection() -> Result<SqliteConnection, String> {
+    let database_url = database_url().ok_or_else(|| "DATABASE_URL must be set".to_string())?;
     let mut connection = SqliteConnection::establish(&database_url)
-        .unwrap_or_else(|_| panic!("Error connecting to {}", database_url));
-    // Run migrations
-    run_migrations(&mut connection).expect("Error running migrations");
-    connection
+        .map_err(|err| format!("Error connecting to {}: {}", database_url, err))?;
+    let pragmas = settings::connection_pragmas().unwrap_or_else(|_| {
+        settings::CONNECTION_PRAGMAS
+            .iter()
+            .map(|&(pragma, default, _)| (pragma, default.to_string()))
+            .collect()
+    });
+    for (pragma, value) in pragmas {
+        connection
+            .batch_execute(&format!("PRAGMA {} = {};", pragma, value))
+            .map_err(|err| format!("Error setting {} on {}: {}", pragma, database_url, err))?;
+    }
+    prepare_database(&mut connection, &database_url)?;
+    Ok(connection)
+}
+
+/// Runs the migrations and creates the search index on a database, unless this process already
+/// did so. An in-memory database is new on every connection, so it is always prepared.
+///
+/// # Arguments
+///
+/// * `connection` - A connection to the database.
+/// * `database_url` - The path of the database.
+///
+/// # Returns
+///
+/// `Ok(())` if the database is ready, or an error message.
+fn prepare_database(connection: &mut SqliteConnection, database_url: &str) -> Result<(), String> {
+    // Held while preparing, so concurrent first connections don't migrate at the same time
+    let mut prepared = PREPARED.lock().unwrap_or_else(|err| err.into_inner());
+    let prepared = prepared.get_or_insert_with(HashSet::new);
+    if prepared.contains(database_url) {
+        return Ok(());
+    }
+    run_migrations(connection).map_err(|err| format!("Error running migrations: {}", err))?;
+    create_search_index(connection)
+        .map_err(|err| format!("Error creating the search index: {}", err))?;
+    if !is_in_memory(database_url) {
+        prepared.insert(database_url.to_string());
+    }
+    Ok(())
+}
+
+/// Makes every connection opened from now on print the SQL it runs to stderr, for the
+/// `--verbose` option.
+///
+/// # Returns
+///
+/// `Ok(())` if query logging was turned on.
+pub fn log_queries() -> QueryResult<()> {
+    set_default_instrumentation(|| {
+        let logger: Box<dyn Instrumentation> = Box::new(|event: InstrumentationEvent<'_>| {
+            if let InstrumentationEvent::StartQuery { query, .. } = event {
+                eprintln!("[SQL] {}", query);
+            }
+        });
+        Some(logger)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use diesel::dsl::sql;
+    use diesel::sql_types::{Integer, Text};
+
+    use super::*;
+    use crate::test_support::open_book;
+
+    #[test]
+    fn pragmas_are_set_on_new_connections() {
+        let _book = open_book();
+        let mut connection = establish_connection();
+        let foreign_keys = diesel::select(sql::<Integer>(
+            "(SELECT foreign_keys FROM pragma_foreign_keys())",
+        ))
+        .get_result::<i32>(&mut connection)
+        .unwrap();
+        assert_eq!(foreign_keys, 1);
+        let journal_mode = diesel::select(sql::<Text>(
+            "(SELECT journal_mode FROM pragma_journal_mode())",
+        ))
+        .get_result::<String>(&mut connection)
+        .unwrap();
+        assert_eq!(journal_mode, "wal");
+    }
+
+    #[test]
+    fn busy_timeout_is_set_to_the_configured_value() {
+        let _book = open_book();
+        let busy_timeout = || {
+            diesel::select(sql::<Integer>(
+                "(SELECT timeout FROM pragma_busy_timeout())",
+            ))
+            .get_result::<i32>(&mut establish_connection())
+            .unwrap()
+        };
+        assert_eq!(busy_timeout(), 5000);
+        // Connections are only opened by tests holding the database lock, as this one does
+        env::set_var("SQLITE_BUSY_TIMEOUT", "1234");
+        let configured = busy_timeout();
+        env::remove_var("SQLITE_BUSY_TIMEOUT");
+        assert_eq!(configured, 1234);
+    }
+
+    #[test]
+    fn databases_are_only_migrated_by_the_first_connection() {
+        let _book = open_book();
+        let mut connection = establish_connection();
+        connection
+            .batch_execute("DROP TABLE __diesel_schema_migrations;")
+            .unwrap();
+        drop(connection);
+        let migrations_table = diesel::select(sql::<Integer>(
+            "(SELECT COUNT(*) FROM sqlite_master WHERE name = '__diesel_schema_migrations')",
+        ))
+        .get_result::<i32>(&mut establish_connection())
+        .unwrap();
+        assert_eq!(migrations_table, 0);
+    }
 }
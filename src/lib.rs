@@ -0,0 +1,8 @@
+pub mod connection;
+mod migrations;
+pub mod phone_book;
+mod schema;
+pub mod settings;
+mod shutdown;
+#[cfg(test)]
+mod test_support;
@@ -1,10 +1,14 @@
-mod connection;
-mod migrations;
-mod phone_book;
-mod schema;
+use std::env;
+use std::process;
 
-use phone_book::phone_book::PhoneBook;
+use phone_book_management_cli_rust::phone_book::{Options, PhoneBook};
 
 fn main() {
-    PhoneBook::start();
+    match Options::from_args(env::args().skip(1)) {
+        Ok(options) => PhoneBook::start(options),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    }
 }
@@ -1,11 +1,64 @@
+use diesel::connection::SimpleConnection;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
 use diesel::sqlite::SqliteConnection;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 
+type MigrationError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
 pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
 
-pub fn run_migrations(
-    connection: &mut SqliteConnection,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+/// The `contacts_fts` full-text index and the triggers keeping it in sync with the contacts.
+const SEARCH_INDEX: &str = include_str!("search_index.sql");
+
+/// Copies the existing contacts into a newly created `contacts_fts` index.
+const FILL_SEARCH_INDEX: &str = "
+INSERT INTO contacts_fts (rowid, first_name, last_name, email, address, phone, phone_digits, website, company, emails)
+SELECT id,
+       first_name,
+       last_name,
+       email,
+       address,
+       phone,
+       REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(phone, '(', ''), ')', ''), '-', ''), ' ', ''), '.', ''), '+', ''),
+       website,
+       company,
+       COALESCE((SELECT GROUP_CONCAT(address, ' ') FROM emails WHERE emails.contact_id = contacts.id), '')
+FROM contacts;
+";
+
+pub fn run_migrations(connection: &mut SqliteConnection) -> Result<(), MigrationError> {
     connection.run_pending_migrations(MIGRATIONS)?;
     Ok(())
 }
+
+/// Returns the version of the latest migration applied to the database, e.g. `20250419100000`.
+pub fn schema_version(connection: &mut SqliteConnection) -> Result<Option<String>, MigrationError> {
+    let applied = connection.applied_migrations()?;
+    Ok(applied.iter().map(|version| version.to_string()).max())
+}
+
+/// Creates the `contacts_fts` full-text index, filled with the existing contacts, and the triggers
+/// keeping it up to date, unless they already exist.
+///
+/// Not every SQLite build has the FTS5 module, so nothing is created without it, and searches fall
+/// back to `LIKE` scans.
+pub fn create_search_index(connection: &mut SqliteConnection) -> QueryResult<()> {
+    let fts5 = diesel::select(sql::<Bool>("sqlite_compileoption_used('ENABLE_FTS5')"))
+        .get_result::<bool>(connection)?;
+    if !fts5 {
+        return Ok(());
+    }
+    connection.transaction(|connection| {
+        let exists = diesel::select(sql::<Bool>(
+            "EXISTS (SELECT 1 FROM sqlite_master WHERE name = 'contacts_fts')",
+        ))
+        .get_result::<bool>(connection)?;
+        connection.batch_execute(SEARCH_INDEX)?;
+        if !exists {
+            connection.batch_execute(FILL_SEARCH_INDEX)?;
+        }
+        Ok(())
+    })
+}
@@ -0,0 +1,612 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use comfy_table::Cell;
+use csv::{QuoteStyle, WriterBuilder};
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::connection::establish_connection;
+use crate::phone_book::contact::{format_timestamp, styled_table, Contact};
+use crate::phone_book::contact_email::ContactEmail;
+use crate::phone_book::export::write_export;
+use crate::phone_book::operations::Cancelled;
+use crate::phone_book::phone_book::PhoneBook;
+use crate::phone_book::tag::Tag;
+use crate::schema::{audit_log, contacts};
+use crate::settings;
+use crate::shutdown::BusyGuard;
+
+/// Number of audit log entries shown by `show_audit`.
+const AUDIT_ENTRIES_SHOWN: i64 = 20;
+
+/// Header of audit log exports.
+const AUDIT_CSV_HEADER: [&str; 6] = [
+    "entry",
+    "timestamp",
+    "operation",
+    "contact_id",
+    "before",
+    "after",
+];
+
+/// Define an AuditEntry struct recording one change made to the phone book.
+#[derive(Insertable, Queryable, Clone)]
+#[diesel(table_name = audit_log)]
+pub struct AuditEntry {
+    pub(crate) id: Option<i32>, // Diesel uses Option for nullable integer fields.
+    pub(crate) created_at: NaiveDateTime, // Stored in UTC.
+    pub(crate) operation: String,
+    pub(crate) contact_id: Option<i32>,
+    pub(crate) before: Option<String>, // JSON `ContactSnapshot`, `None` if the contact didn't exist.
+    pub(crate) after: Option<String>,  // JSON `ContactSnapshot`, `None` if the contact was deleted.
+}
+
+/// Define a ContactSnapshot struct holding the full state of a contact at one point in time, so a
+/// change can be reverted.
+///
+/// Relationships aren't part of the snapshot, as they involve other contacts.
+#[derive(Serialize, Deserialize)]
+pub struct ContactSnapshot {
+    pub(crate) contact: Contact,
+    pub(crate) emails: Vec<(String, String)>,
+    pub(crate) tags: Vec<String>,
+}
+/// ContactSnapshot struct implementation.
+impl ContactSnapshot {
+    /// Captures the current state of a contact.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    /// * `contact_id`: The id of the contact.
+    ///
+    /// # Returns
+    ///
+    /// The snapshot, or `None` if there is no contact with that id.
+    pub fn load(
+        connection: &mut SqliteConnection,
+        contact_id: i32,
+    ) -> QueryResult<Option<ContactSnapshot>> {
+        let Some(contact) = contacts::table
+            .filter(contacts::id.eq(contact_id))
+            .first::<Contact>(connection)
+            .optional()?
+        else {
+            return Ok(None);
+        };
+        let emails = ContactEmail::load_for(connection, contact_id)?
+            .into_iter()
+            .map(|email| (email.label, email.address))
+            .collect();
+        let tags = Tag::load_for(connection, contact_id)?;
+        Ok(Some(ContactSnapshot {
+            contact,
+            emails,
+            tags,
+        }))
+    }
+    /// Puts the contact back in the state of the snapshot, re-creating it with its original id
+    /// if it has since been deleted.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the contact was restored.
+    pub fn restore(&self, connection: &mut SqliteConnection) -> QueryResult<()> {
+        let contact = &self.contact;
+        let Some(contact_id) = contact.id else {
+            return Err(diesel::result::Error::NotFound);
+        };
+        let updated = diesel::update(contacts::table.filter(contacts::id.eq(contact_id)))
+            .set((
+                contacts::first_name.eq(&contact.first_name),
+                contacts::last_name.eq(&contact.last_name),
+                contacts::email.eq(&contact.email),
+                contacts::address.eq(&contact.address),
+                contacts::phone.eq(&contact.phone),
+                contacts::website.eq(&contact.website),
+                contacts::extension.eq(&contact.extension),
+                contacts::company.eq(&contact.company),
+                contacts::preferred_method.eq(&contact.preferred_method),
+                contacts::last_contacted.eq(&contact.last_contacted),
+                contacts::created_at.eq(&contact.created_at),
+                contacts::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(connection)?;
+        if updated == 0 {
+            diesel::insert_into(contacts::table)
+                .values(contact)
+                .execute(connection)?;
+        }
+        ContactEmail::replace_for(connection, contact_id, &self.emails)?;
+        Tag::replace_for(connection, contact_id, &self.tags)
+    }
+    /// Summarizes a snapshot for audit log exports: the contact's name, phone number, email and
+    /// company, followed by its tags.
+    ///
+    /// # Returns
+    ///
+    /// The summary, e.g. `John Doe; (555) 123-4567; john@example.com; tags: work`.
+    fn summary(&self) -> String {
+        let contact = &self.contact;
+        let name = format!("{} {}", contact.first_name, contact.last_name);
+        let mut parts: Vec<String> = [name.trim().to_string(), contact.full_phone()]
+            .into_iter()
+            .chain([contact.email.clone(), contact.company.clone()])
+            .filter(|part| !part.is_empty())
+            .collect();
+        if !self.tags.is_empty() {
+            parts.push(format!("tags: {}", self.tags.join(", ")));
+        }
+        parts.join("; ")
+    }
+    /// Serializes a snapshot for the audit log.
+    fn to_json(snapshot: Option<&ContactSnapshot>) -> Option<String> {
+        snapshot.map(|snapshot| {
+            serde_json::to_string(snapshot).expect("snapshots only hold serializable fields")
+        })
+    }
+}
+/// AuditEntry struct implementation.
+impl AuditEntry {
+    /// Records a change in the audit log, along with the state of the contact before and after
+    /// it.
+    ///
+    /// Callers should run this in the same transaction as the change, right after it, so the log
+    /// never records a change that was rolled back.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    /// * `operation`: What was done, e.g. "create" or "delete".
+    /// * `contact_id`: The id of the affected contact, if any.
+    /// * `before`: The contact as it was before the change, `None` if it didn't exist.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the entry was saved.
+    pub fn record(
+        connection: &mut SqliteConnection,
+        operation: &str,
+        contact_id: Option<i32>,
+        before: Option<&ContactSnapshot>,
+    ) -> QueryResult<()> {
+        let after = match contact_id {
+            Some(contact_id) => ContactSnapshot::load(connection, contact_id)?,
+            None => None,
+        };
+        diesel::insert_into(audit_log::table)
+            .values(&AuditEntry {
+                id: None,
+                created_at: Utc::now().naive_utc(),
+                operation: operation.to_string(),
+                contact_id,
+                before: ContactSnapshot::to_json(before),
+                after: ContactSnapshot::to_json(after.as_ref()),
+            })
+            .execute(connection)?;
+        Ok(())
+    }
+    /// Undoes a logged change by putting the contact back in its state before the change: a
+    /// created contact is deleted, and an updated or deleted one is restored. The revert is
+    /// itself recorded in the audit log, so it can be reverted too.
+    ///
+    /// # Parameters
+    ///
+    /// * `entry_id`: The id of the audit log entry to revert.
+    ///
+    /// # Returns
+    ///
+    /// The id of the affected contact, or a message explaining why the entry can't be reverted.
+    pub fn revert(entry_id: i32) -> Result<i32, String> {
+        let mut connection = establish_connection();
+        let entry = audit_log::table
+            .filter(audit_log::id.eq(entry_id))
+            .first::<AuditEntry>(&mut connection)
+            .optional()
+            .map_err(|err| format!("Error fetching the audit log: {}", err))?
+            .ok_or_else(|| format!("No audit log entry {}.", entry_id))?;
+        let (Some(contact_id), true) = (
+            entry.contact_id,
+            entry.before.is_some() || entry.after.is_some(),
+        ) else {
+            return Err(format!(
+                "Audit log entry {} has no snapshot of the contact and can't be reverted.",
+                entry_id
+            ));
+        };
+        let before = entry
+            .before
+            .as_deref()
+            .map(serde_json::from_str::<ContactSnapshot>)
+            .transpose()
+            .map_err(|err| format!("Invalid snapshot in audit log entry {}: {}", entry_id, err))?;
+        let _busy = BusyGuard::enter();
+        connection
+            .transaction(|connection| {
+                let current = ContactSnapshot::load(connection, contact_id)?;
+                match &before {
+                    Some(snapshot) => snapshot.restore(connection)?,
+                    None => {
+                        PhoneBook::delete_contact_rows(connection, contact_id)?;
+                    }
+                }
+                let operation = format!("revert {}", entry_id);
+                AuditEntry::record(connection, &operation, Some(contact_id), current.as_ref())
+            })
+            .map_err(|err| format!("Error reverting audit log entry {}: {}", entry_id, err))?;
+        Ok(contact_id)
+    }
+    /// Loads the most recent audit log entries.
+    ///
+    /// # Parameters
+    ///
+    /// * `limit`: The maximum number of entries to load.
+    ///
+    /// # Returns
+    ///
+    /// The entries, newest first.
+    pub fn recent(limit: i64) -> QueryResult<Vec<AuditEntry>> {
+        let mut connection = establish_connection();
+        audit_log::table
+            .order(audit_log::id.desc())
+            .limit(limit)
+            .load::<AuditEntry>(&mut connection)
+    }
+    /// Loads the audit log entries recorded between two dates.
+    ///
+    /// # Parameters
+    ///
+    /// * `from`: The first day, in local time, `None` to start with the first entry.
+    /// * `to`: The last day, in local time, `None` to end with the last entry.
+    ///
+    /// # Returns
+    ///
+    /// The entries, oldest first.
+    pub fn between(from: Option<NaiveDate>, to: Option<NaiveDate>) -> QueryResult<Vec<AuditEntry>> {
+        // Entries are stored in UTC, so the local days are converted to UTC bounds
+        let start_of = |date: NaiveDate| {
+            Local
+                .from_local_datetime(&date.and_time(Default::default()))
+                .earliest()
+                .map(|start| start.naive_utc())
+        };
+        let mut query = audit_log::table.order(audit_log::id.asc()).into_boxed();
+        if let Some(start) = from.and_then(start_of) {
+            query = query.filter(audit_log::created_at.ge(start));
+        }
+        if let Some(end) = to.and_then(|to| to.succ_opt()).and_then(start_of) {
+            query = query.filter(audit_log::created_at.lt(end));
+        }
+        let mut connection = establish_connection();
+        query.load::<AuditEntry>(&mut connection)
+    }
+    /// Serializes audit log entries as CSV text, with the snapshots before and after each change
+    /// summarized rather than written as JSON. The delimiter and quoting follow the
+    /// `CSV_DELIMITER` and `CSV_QUOTING` settings.
+    ///
+    /// # Parameters
+    ///
+    /// * `entries`: The entries to serialize.
+    ///
+    /// # Returns
+    ///
+    /// The CSV text, with a header row and UTC timestamps in RFC 3339 format, e.g.
+    /// `2025-04-19T10:00:00Z`.
+    pub fn to_csv(entries: &[AuditEntry]) -> Result<String, csv::Error> {
+        let summary = |snapshot: &Option<String>| match snapshot.as_deref() {
+            None => String::new(),
+            Some(json) => serde_json::from_str::<ContactSnapshot>(json).map_or_else(
+                |_| "(unreadable snapshot)".to_string(),
+                |snapshot| snapshot.summary(),
+            ),
+        };
+        let mut writer = WriterBuilder::new()
+            .delimiter(settings::csv_delimiter().unwrap_or(b','))
+            .quote_style(settings::csv_quoting().unwrap_or(QuoteStyle::Necessary))
+            .from_writer(Vec::new());
+        writer.write_record(AUDIT_CSV_HEADER)?;
+        for entry in entries {
+            writer.write_record([
+                entry.id.unwrap_or_default().to_string(),
+                entry
+                    .created_at
+                    .and_utc()
+                    .to_rfc3339_opts(SecondsFormat::Secs, true),
+                entry.operation.clone(),
+                entry
+                    .contact_id
+                    .map(|id| id.to_string())
+                    .unwrap_or_default(),
+                summary(&entry.before),
+                summary(&entry.after),
+            ])?;
+        }
+        let bytes = writer.into_inner().map_err(|err| err.into_error())?;
+        Ok(String::from_utf8(bytes).expect("CSV is written from UTF-8 strings"))
+    }
+}
+
+impl PhoneBook {
+    /// Exports the audit log to a CSV file for review or retention outside the phone book,
+    /// optionally only the entries recorded between two dates.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn export_audit_csv(&mut self) {
+        Self::print_cancel_hint();
+        let Ok(file_name) = Self::get_input_or_cancel(
+            "Enter the name of the CSV file to export the audit log to (.gz to compress): ",
+        ) else {
+            println!("Export cancelled.");
+            return;
+        };
+        let file_name = match Self::expand_path(&file_name) {
+            Ok(file_name) => file_name,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let Ok(from) = Self::prompt_date("Enter the first day (YYYY-MM-DD, leave empty for all): ")
+        else {
+            println!("Export cancelled.");
+            return;
+        };
+        let Ok(to) = Self::prompt_date("Enter the last day (YYYY-MM-DD, leave empty for all): ")
+        else {
+            println!("Export cancelled.");
+            return;
+        };
+        let entries = match AuditEntry::between(from, to) {
+            Ok(entries) => entries,
+            Err(err) => {
+                println!("Error fetching the audit log: {}", err);
+                return;
+            }
+        };
+        let csv = match AuditEntry::to_csv(&entries) {
+            Ok(csv) => csv,
+            Err(err) => {
+                println!("Error generating CSV: {}", err);
+                return;
+            }
+        };
+        match write_export(&file_name, &csv) {
+            Ok(()) => println!(
+                "Exported {} audit log entries to {}.",
+                entries.len(),
+                file_name
+            ),
+            Err(err) => println!("Error writing file: {}", err),
+        }
+    }
+    /// Prompts for an optional date, asking again until the input is a date or empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The prompt to display.
+    ///
+    /// # Returns
+    ///
+    /// The date, `None` if the input was left empty, or `Err(Cancelled)` if the user typed the
+    /// cancel token.
+    fn prompt_date(prompt: &str) -> Result<Option<NaiveDate>, Cancelled> {
+        loop {
+            let input = Self::get_input_or_cancel(prompt)?;
+            if input.is_empty() {
+                return Ok(None);
+            }
+            match input.parse::<NaiveDate>() {
+                Ok(date) => return Ok(Some(date)),
+                Err(_) => println!("Invalid date '{}'. Use the YYYY-MM-DD format.", input),
+            }
+        }
+    }
+    /// Displays the most recent changes made to the phone book, newest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the audit log to the console.
+    pub(crate) fn show_audit(&mut self) {
+        let entries = match AuditEntry::recent(AUDIT_ENTRIES_SHOWN) {
+            Ok(entries) => entries,
+            Err(err) => {
+                println!("Error fetching the audit log: {}", err);
+                return;
+            }
+        };
+        if entries.is_empty() {
+            println!("The audit log is empty.");
+            return;
+        }
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("Entry").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("When").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Operation").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Contact").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        for entry in &entries {
+            table.add_row(vec![
+                Cell::new(entry.id.unwrap_or_default()),
+                Cell::new(format_timestamp(&entry.created_at)),
+                Cell::new(&entry.operation),
+                Cell::new(
+                    entry
+                        .contact_id
+                        .map(|contact_id| format!("#{}", contact_id))
+                        .unwrap_or_default(),
+                ),
+            ]);
+        }
+        println!("{}", table);
+    }
+    /// Reverts a change from the audit log, chosen by its entry number, after backing up the
+    /// database unless `AUTO_BACKUP` is off.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn revert_audit_entry(&mut self) {
+        self.show_audit();
+        Self::print_cancel_hint();
+        let Ok(input) = Self::get_input_or_cancel("Enter the audit log entry to revert: ") else {
+            println!("Revert cancelled.");
+            return;
+        };
+        let Ok(entry_id) = input.parse::<i32>() else {
+            println!("Invalid audit log entry!");
+            return;
+        };
+        let confirm = Self::get_input(&format!(
+            "Revert audit log entry {}? Later changes to the contact are overwritten. (y/n): ",
+            entry_id
+        ));
+        if confirm.to_lowercase() != "y" {
+            println!("Revert cancelled.");
+            return;
+        }
+        if !Self::backup_before("revert") {
+            return;
+        }
+        match AuditEntry::revert(entry_id) {
+            Ok(contact_id) => {
+                // The last listing may show the contact in its reverted state
+                self.contacts.clear();
+                println!(
+                    "Reverted audit log entry {} (contact #{}).",
+                    entry_id, contact_id
+                );
+            }
+            Err(err) => println!("{}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contact, open_book};
+
+    #[test]
+    fn create_is_recorded_with_the_saved_contact() {
+        let _book = open_book();
+        let contact_id = PhoneBook::insert_contact_with(
+            &contact("John", "Doe", "5551234567", "john@example.com"),
+            &[("work".to_string(), "john@work.example".to_string())],
+            &["family".to_string()],
+            "create",
+        )
+        .unwrap();
+        let entries = AuditEntry::recent(10).unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.operation, "create");
+        assert_eq!(entry.contact_id, Some(contact_id));
+        assert!(entry.before.is_none());
+        let after: ContactSnapshot = serde_json::from_str(entry.after.as_deref().unwrap()).unwrap();
+        assert_eq!(after.contact.first_name, "John");
+        assert_eq!(
+            after.emails,
+            [("work".to_string(), "john@work.example".to_string())]
+        );
+        assert_eq!(after.tags, ["family"]);
+    }
+
+    #[test]
+    fn reverting_a_delete_restores_the_contact() {
+        let mut book = open_book();
+        let contact_id = PhoneBook::insert_contact_with(
+            &contact("John", "Doe", "5551234567", "john@example.com"),
+            &[],
+            &["family".to_string()],
+            "create",
+        )
+        .unwrap();
+        assert!(book.delete(contact_id).unwrap());
+        assert!(book.get(contact_id).unwrap().is_none());
+        let entry = AuditEntry::recent(1).unwrap().remove(0);
+        assert_eq!(entry.operation, "delete");
+        assert_eq!(AuditEntry::revert(entry.id.unwrap()), Ok(contact_id));
+        let restored = book.get(contact_id).unwrap().unwrap();
+        assert_eq!(restored.first_name, "John");
+        assert_eq!(restored.email, "john@example.com");
+        let mut connection = establish_connection();
+        assert_eq!(
+            Tag::load_for(&mut connection, contact_id).unwrap(),
+            ["family"]
+        );
+    }
+
+    #[test]
+    fn audit_export_holds_the_entries_within_the_range() {
+        let mut book = open_book();
+        let contact_id = book
+            .add(contact("John", "Doe", "5551234567", "john@example.com"))
+            .unwrap();
+        let mut changed = contact("John", "Doe", "5559876543", "john@example.com");
+        changed.set_field("company", "Acme".to_string());
+        PhoneBook::save_contact_update(contact_id, &changed, None, None).unwrap();
+        assert!(book.delete(contact_id).unwrap());
+        let old_id = book
+            .add(contact("Mary", "Major", "5550001111", ""))
+            .unwrap();
+        let mut connection = establish_connection();
+        let old_time = NaiveDate::from_ymd_opt(2020, 6, 15)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        diesel::update(audit_log::table.filter(audit_log::contact_id.eq(old_id)))
+            .set(audit_log::created_at.eq(old_time))
+            .execute(&mut connection)
+            .unwrap();
+
+        let today = Local::now().date_naive();
+        let entries = AuditEntry::between(Some(today), Some(today)).unwrap();
+        let csv = AuditEntry::to_csv(&entries).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "entry,timestamp,operation,contact_id,before,after"
+        );
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].ends_with(&format!(
+            ",create,{},,John Doe; (555) 123-4567; john@example.com",
+            contact_id
+        )));
+        assert!(lines[2].ends_with(&format!(
+            ",update,{},John Doe; (555) 123-4567; john@example.com,\
+             John Doe; (555) 987-6543; john@example.com; Acme",
+            contact_id
+        )));
+        assert!(lines[3].ends_with(&format!(
+            ",delete,{},John Doe; (555) 987-6543; john@example.com; Acme,",
+            contact_id
+        )));
+        assert!(!csv.contains("Mary"));
+
+        let june = |day| NaiveDate::from_ymd_opt(2020, 6, day);
+        let entries = AuditEntry::between(june(1), june(30)).unwrap();
+        let csv = AuditEntry::to_csv(&entries).unwrap();
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.contains(",2020-06-15T12:00:00Z,create,"));
+        assert!(csv.contains("Mary Major"));
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use chrono::Local;
+use diesel::connection::SimpleConnection;
+
+use crate::connection::{self, establish_connection};
+use crate::phone_book::phone_book::PhoneBook;
+use crate::settings;
+
+impl PhoneBook {
+    /// Copies the database to a timestamped file next to it before an operation that changes or
+    /// removes many contacts at once, unless the `AUTO_BACKUP` setting is off.
+    ///
+    /// The copy is made with `VACUUM INTO`, which produces a consistent, compact database even
+    /// while write-ahead logging is on.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - What is about to happen, e.g. "deduplication", used in the file name.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the operation may go ahead: the backup was made or isn't wanted. `false` if the
+    /// backup failed, which is reported to the user.
+    pub(crate) fn backup_before(operation: &str) -> bool {
+        match settings::auto_backup() {
+            Ok(false) => return true,
+            Ok(true) => {}
+            Err(err) => {
+                println!("{}", err);
+                return false;
+            }
+        }
+        let Some(database_url) = connection::database_url() else {
+            println!("Backup failed: DATABASE_URL must be set. Nothing was changed.");
+            return false;
+        };
+        let path = backup_path(&database_url, operation);
+        let mut connection = establish_connection();
+        match connection.batch_execute(&format!("VACUUM INTO '{}';", path.replace('\'', "''"))) {
+            Ok(()) => {
+                println!("Backed up the phone book to {}.", path);
+                true
+            }
+            Err(err) => {
+                println!("Backup failed: {}. Nothing was changed.", err);
+                false
+            }
+        }
+    }
+}
+
+/// Builds the path of a backup: the database's own path with the operation and the local time
+/// appended, e.g. `db-before-deduplication-20250405-143000.sqlite`.
+///
+/// # Arguments
+///
+/// * `database_url` - The path of the database.
+/// * `operation` - The operation about to run.
+///
+/// # Returns
+///
+/// The path of the backup file.
+fn backup_path(database_url: &str, operation: &str) -> String {
+    let path = Path::new(database_url);
+    let stem = path
+        .file_stem()
+        .map_or("phone_book".into(), |stem| stem.to_string_lossy());
+    let file_name = format!(
+        "{}-before-{}-{}.sqlite",
+        stem,
+        operation.replace(' ', "-"),
+        Local::now().format("%Y%m%d-%H%M%S")
+    );
+    path.with_file_name(file_name)
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,518 @@
+use chrono::Utc;
+use dialoguer::MultiSelect;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+use diesel::sqlite::SqliteConnection;
+use diesel::update;
+
+use crate::connection::establish_connection;
+use crate::phone_book::audit::{AuditEntry, ContactSnapshot};
+use crate::phone_book::contact::{sanitize_text, Contact};
+use crate::phone_book::operations::Cancelled;
+use crate::phone_book::phone_book::PhoneBook;
+use crate::phone_book::tag::Tag;
+use crate::schema::{contacts, tags};
+use crate::settings;
+use crate::shutdown::BusyGuard;
+
+/// Define a change applied to many contacts at once.
+pub enum BulkChange {
+    /// Tags every contact that doesn't carry the tag yet.
+    AddTag(String),
+    /// Replaces the beginning of the phone numbers starting with `old` by `new`, e.g. a dialing
+    /// prefix or an area code written the same way in every number.
+    ReplacePhonePrefix { old: String, new: String },
+    /// Rewrites the US area code `old` of the phone numbers to `new`, whatever their formatting,
+    /// e.g. when an area code is split.
+    ReplaceAreaCode { old: String, new: String },
+}
+
+impl BulkChange {
+    /// Selects the contacts the change actually affects.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    /// * `contacts`: The contacts matching the user's filter.
+    ///
+    /// # Returns
+    ///
+    /// The ids of the contacts that would change.
+    fn affected_ids(
+        &self,
+        connection: &mut SqliteConnection,
+        contacts: &[Contact],
+    ) -> QueryResult<Vec<i32>> {
+        match self {
+            BulkChange::AddTag(name) => {
+                let tagged: Vec<i32> = tags::table
+                    .filter(tags::name.eq(name))
+                    .select(tags::contact_id)
+                    .load(connection)?;
+                Ok(contacts
+                    .iter()
+                    .filter_map(|contact| contact.id)
+                    .filter(|id| !tagged.contains(id))
+                    .collect())
+            }
+            BulkChange::ReplacePhonePrefix { .. } | BulkChange::ReplaceAreaCode { .. } => {
+                Ok(contacts
+                    .iter()
+                    .filter(|contact| self.new_phone(contact).is_some())
+                    .filter_map(|contact| contact.id)
+                    .collect())
+            }
+        }
+    }
+    /// Computes the phone number a contact would get from a change of phone numbers.
+    ///
+    /// # Parameters
+    ///
+    /// * `contact`: The contact.
+    ///
+    /// # Returns
+    ///
+    /// The new phone number, or `None` if the change doesn't affect the contact's number.
+    fn new_phone(&self, contact: &Contact) -> Option<String> {
+        match self {
+            BulkChange::AddTag(_) => None,
+            BulkChange::ReplacePhonePrefix { old, new } => contact
+                .phone
+                .strip_prefix(old.as_str())
+                .map(|rest| format!("{}{}", new, rest)),
+            BulkChange::ReplaceAreaCode { old, new } => contact
+                .area_code()
+                .filter(|area_code| area_code == old)
+                .and_then(|_| contact.with_area_code(new)),
+        }
+    }
+    /// Describes the change for the confirmation prompt.
+    fn describe(&self) -> String {
+        match self {
+            BulkChange::AddTag(name) => format!("Add the tag '{}' to", name),
+            BulkChange::ReplacePhonePrefix { old, new } => {
+                format!("Replace the phone prefix '{}' with '{}' on", old, new)
+            }
+            BulkChange::ReplaceAreaCode { old, new } => {
+                format!("Change the area code {} to {} on", old, new)
+            }
+        }
+    }
+}
+
+impl PhoneBook {
+    /// Applies the same change to every contact matching a search, e.g. to tag everyone living in
+    /// a city, to rewrite a dialing prefix or to change an area code.
+    ///
+    /// The matching contacts are listed, with the new phone numbers of a phone change, and
+    /// nothing is changed until the user confirms. The
+    /// database is backed up, unless `AUTO_BACKUP` is off, and all the contacts are changed in a
+    /// single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn bulk_update_contacts(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        Self::print_cancel_hint();
+        let Ok(query) = Self::get_input_or_cancel(
+            "Enter a search query selecting the contacts to change (leave empty for all): ",
+        ) else {
+            println!("Bulk update cancelled.");
+            return;
+        };
+        let found_contacts = match Self::search_contacts(&query) {
+            Ok(found_contacts) => found_contacts,
+            Err(err) => {
+                println!("Error searching for contacts: {}", err);
+                return;
+            }
+        };
+        if found_contacts.is_empty() {
+            println!("No contacts found matching the search query.");
+            return;
+        }
+        self.print_contacts(&found_contacts);
+        let change = match Self::prompt_bulk_change() {
+            Ok(Some(change)) => change,
+            Ok(None) => return,
+            Err(_) => {
+                println!("Bulk update cancelled.");
+                return;
+            }
+        };
+        let mut connection = establish_connection();
+        let affected = match change.affected_ids(&mut connection, &found_contacts) {
+            Ok(affected) => affected,
+            Err(err) => {
+                println!("Error preparing the update: {}", err);
+                return;
+            }
+        };
+        if affected.is_empty() {
+            println!("None of the matching contacts would change.");
+            return;
+        }
+        // Phone changes are previewed, so rewrites going wrong can be spotted before confirming
+        for contact in &found_contacts {
+            if let Some(phone) = change.new_phone(contact) {
+                let name = format!("{} {}", contact.first_name, contact.last_name);
+                println!(
+                    "#{} {}: {} -> {}",
+                    contact.id.unwrap_or_default(),
+                    name.trim(),
+                    contact.phone,
+                    phone
+                );
+            }
+        }
+        let confirm = Self::get_input(&format!(
+            "{} {} of the {} matching contact(s)? (y/n): ",
+            change.describe(),
+            affected.len(),
+            found_contacts.len()
+        ));
+        if confirm.to_lowercase() != "y" {
+            println!("Bulk update cancelled.");
+            return;
+        }
+        if !Self::backup_before("bulk update") {
+            return;
+        }
+        match Self::bulk_update(&affected, &change) {
+            Ok(updated) => {
+                // The last listing may show the contacts as they were
+                self.contacts.clear();
+                println!("Updated {} contact(s).", updated);
+            }
+            Err(err) => println!("Error updating contacts: {}", err),
+        }
+    }
+    /// Asks which change to apply to the matching contacts.
+    ///
+    /// # Returns
+    ///
+    /// The change, `Ok(None)` if the input was invalid, which is reported to the user, or
+    /// `Err(Cancelled)` if the user typed the cancel token.
+    fn prompt_bulk_change() -> Result<Option<BulkChange>, Cancelled> {
+        match Self::get_input_or_cancel(
+            "Change to apply: (1) add a tag, (2) replace a phone prefix or (3) change an area code: ",
+        )?
+        .as_str()
+        {
+            "1" => {
+                let mut connection = establish_connection();
+                let existing = Tag::all_names(&mut connection).unwrap_or_default();
+                if !existing.is_empty() {
+                    println!("Existing tags: {}", existing.join(", "));
+                }
+                let typed = Self::get_input_or_cancel("Enter the tag to add: ")?;
+                if typed.trim().is_empty() {
+                    println!("The tag can't be empty!");
+                    return Ok(None);
+                }
+                Ok(Some(BulkChange::AddTag(Tag::resolve(
+                    typed.trim(),
+                    &existing,
+                ))))
+            }
+            "2" => {
+                let old = Self::get_input_or_cancel("Enter the phone prefix to replace: ")?;
+                if old.is_empty() {
+                    println!("The prefix to replace can't be empty!");
+                    return Ok(None);
+                }
+                let new =
+                    sanitize_text(&Self::get_input_or_cancel("Enter the new phone prefix: ")?);
+                Ok(Some(BulkChange::ReplacePhonePrefix { old, new }))
+            }
+            "3" => {
+                let is_area_code =
+                    |code: &str| code.len() == 3 && code.chars().all(|c| c.is_ascii_digit());
+                let old = Self::get_input_or_cancel("Enter the area code to change: ")?;
+                let new = Self::get_input_or_cancel("Enter the new area code: ")?;
+                if !is_area_code(&old) || !is_area_code(&new) {
+                    println!("Area codes must be three digits!");
+                    return Ok(None);
+                }
+                Ok(Some(BulkChange::ReplaceAreaCode { old, new }))
+            }
+            _ => {
+                println!("Invalid choice!");
+                Ok(None)
+            }
+        }
+    }
+    /// Applies a change to the given contacts in a single transaction, recording each changed
+    /// contact in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact_ids` - The ids of the contacts to change, as selected by the change itself.
+    /// * `change` - The change to apply.
+    ///
+    /// # Returns
+    ///
+    /// The number of updated contacts.
+    pub fn bulk_update(contact_ids: &[i32], change: &BulkChange) -> QueryResult<usize> {
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            let mut snapshots = Vec::with_capacity(contact_ids.len());
+            for &contact_id in contact_ids {
+                snapshots.push((contact_id, ContactSnapshot::load(connection, contact_id)?));
+            }
+            let now = Utc::now().naive_utc();
+            let selected = contacts::table.filter(contacts::id.eq_any(contact_ids));
+            let updated = match change {
+                BulkChange::AddTag(name) => {
+                    let rows: Vec<Tag> = contact_ids
+                        .iter()
+                        .map(|&contact_id| Tag::new(contact_id, name.clone()))
+                        .collect();
+                    diesel::insert_into(tags::table)
+                        .values(&rows)
+                        .execute(connection)?;
+                    update(selected)
+                        .set(contacts::updated_at.eq(now))
+                        .execute(connection)?
+                }
+                BulkChange::ReplacePhonePrefix { old, new } => {
+                    // SUBSTR counts characters from 1, so this keeps what follows the prefix
+                    let rest = sql::<Text>(&format!("SUBSTR(phone, {})", old.chars().count() + 1));
+                    update(selected)
+                        .set((
+                            contacts::phone.eq(new.clone().into_sql::<Text>().concat(rest)),
+                            contacts::updated_at.eq(now),
+                        ))
+                        .execute(connection)?
+                }
+                BulkChange::ReplaceAreaCode { .. } => {
+                    // The area code sits at a different place in each formatting, so each
+                    // number is rewritten on its own
+                    let mut updated = 0;
+                    for contact in selected.load::<Contact>(connection)? {
+                        let Some(phone) = change.new_phone(&contact) else {
+                            continue;
+                        };
+                        updated += update(contacts::table.filter(contacts::id.eq(contact.id)))
+                            .set((contacts::phone.eq(phone), contacts::updated_at.eq(now)))
+                            .execute(connection)?;
+                    }
+                    updated
+                }
+            };
+            for (contact_id, before) in &snapshots {
+                AuditEntry::record(
+                    connection,
+                    "bulk update",
+                    Some(*contact_id),
+                    before.as_ref(),
+                )?;
+            }
+            Ok(updated)
+        })
+    }
+    /// Lets the user tick several contacts of the last listing in a checklist and deletes them
+    /// all at once, after confirmation and a backup.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance, whose `contacts` are the
+    ///   last listing.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn delete_selected_contacts(&mut self) {
+        let items: Vec<String> = self
+            .contacts
+            .iter()
+            .enumerate()
+            .map(|(index, contact)| {
+                format!(
+                    "#{} {} {} ({})",
+                    index + 1,
+                    contact.first_name,
+                    contact.last_name,
+                    contact.full_phone()
+                )
+            })
+            .collect();
+        let selection = MultiSelect::new()
+            .with_prompt(
+                "Select the contacts to delete (Space to select, Enter to confirm, Esc to cancel)",
+            )
+            .items(&items)
+            .max_length(settings::page_size().unwrap_or(settings::DEFAULT_PAGE_SIZE))
+            .interact_opt();
+        let mut indexes = match selection {
+            Ok(Some(indexes)) if !indexes.is_empty() => indexes,
+            Ok(_) => {
+                println!("Contact deletion cancelled.");
+                return;
+            }
+            Err(err) => {
+                println!("Error reading the selection: {}", err);
+                return;
+            }
+        };
+        for &index in &indexes {
+            println!("{}", items[index]);
+        }
+        let confirm = Self::get_input(&format!(
+            "Are you sure you want to delete these {} contact(s)? (y/n): ",
+            indexes.len()
+        ));
+        if confirm.to_lowercase() != "y" {
+            println!("Contact deletion cancelled.");
+            return;
+        }
+        if indexes.len() > 1 && !Self::backup_before("delete") {
+            return;
+        }
+        let contact_ids: Vec<i32> = indexes
+            .iter()
+            .filter_map(|&index| self.contacts[index].id)
+            .collect();
+        match Self::delete_contacts_by_id(&contact_ids) {
+            Ok(deleted) => {
+                // Remove from the end so the remaining indexes stay valid
+                indexes.sort_unstable();
+                for &index in indexes.iter().rev() {
+                    self.contacts.remove(index);
+                }
+                println!("{} contact(s) deleted successfully.", deleted);
+            }
+            Err(err) => println!("Error deleting contacts from database: {}", err),
+        }
+    }
+    /// Deletes several contacts in a single transaction, recording each deletion in the audit
+    /// log: either all of them are deleted or none is.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact_ids` - The ids of the contacts to delete.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted contacts.
+    pub fn delete_contacts_by_id(contact_ids: &[i32]) -> QueryResult<usize> {
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            let mut deleted = 0;
+            for &contact_id in contact_ids {
+                let before = ContactSnapshot::load(connection, contact_id)?;
+                if Self::delete_contact_rows(connection, contact_id)? > 0 {
+                    AuditEntry::record(connection, "delete", Some(contact_id), before.as_ref())?;
+                    deleted += 1;
+                }
+            }
+            Ok(deleted)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contact, open_book};
+
+    #[test]
+    fn tag_is_added_to_every_contact_in_a_city() {
+        let mut book = open_book();
+        for (first_name, address) in [
+            ("John", "1 Pike St, Seattle"),
+            ("Jane", "2 Pine St, Seattle"),
+            ("Mary", "3 Main St, Portland"),
+        ] {
+            let mut new_contact = contact(first_name, "Doe", "5551234567", "");
+            new_contact.set_field("address", address.to_string());
+            book.add(new_contact).unwrap();
+        }
+        let matching = PhoneBook::search_contacts("Seattle").unwrap();
+        let change = BulkChange::AddTag("seattle".to_string());
+        let mut connection = establish_connection();
+        let contact_ids = change.affected_ids(&mut connection, &matching).unwrap();
+        assert_eq!(PhoneBook::bulk_update(&contact_ids, &change), Ok(2));
+        let tagged: Vec<String> = contacts::table
+            .inner_join(tags::table)
+            .filter(tags::name.eq("seattle"))
+            .select(contacts::first_name)
+            .order(contacts::id.asc())
+            .load(&mut connection)
+            .unwrap();
+        assert_eq!(tagged, ["John", "Jane"]);
+        // Contacts already carrying the tag aren't affected again
+        assert!(change
+            .affected_ids(&mut connection, &matching)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn several_contacts_are_deleted_in_one_batch() {
+        let mut book = open_book();
+        let ids: Vec<i32> = ["John", "Jane", "Mary"]
+            .into_iter()
+            .map(|first_name| {
+                book.add(contact(first_name, "Doe", "5551234567", ""))
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(PhoneBook::delete_contacts_by_id(&[ids[0], ids[2]]), Ok(2));
+        let names: Vec<String> = book
+            .list(None)
+            .unwrap()
+            .into_iter()
+            .map(|contact| contact.first_name)
+            .collect();
+        assert_eq!(names, ["Jane"]);
+    }
+
+    #[test]
+    fn area_code_is_rewritten_on_the_matching_contacts() {
+        let mut book = open_book();
+        let mut jane = contact("Jane", "Doe", "", "");
+        jane.set_field("phone", "+1 533 164 0000".to_string());
+        for new_contact in [
+            contact("John", "Doe", "5331646251", ""),
+            jane,
+            contact("Mary", "Doe", "5551234567", ""),
+            contact("Bob", "Roe", "5330000000", ""),
+        ] {
+            book.add(new_contact).unwrap();
+        }
+        let matching = PhoneBook::search_contacts("Doe").unwrap();
+        let change = BulkChange::ReplaceAreaCode {
+            old: "533".to_string(),
+            new: "534".to_string(),
+        };
+        let mut connection = establish_connection();
+        let contact_ids = change.affected_ids(&mut connection, &matching).unwrap();
+        assert_eq!(PhoneBook::bulk_update(&contact_ids, &change), Ok(2));
+        let phones: Vec<String> = book
+            .list(None)
+            .unwrap()
+            .into_iter()
+            .map(|contact| contact.phone)
+            .collect();
+        assert_eq!(
+            phones,
+            [
+                "(534) 164-6251",
+                "+1 534 164 0000",
+                "(555) 123-4567",
+                "(533) 000-0000"
+            ]
+        );
+    }
+}
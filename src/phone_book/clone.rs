@@ -0,0 +1,73 @@
+use crate::phone_book::contact::FIELDS;
+use crate::phone_book::operations::field_position;
+use crate::phone_book::phone_book::PhoneBook;
+use crate::settings;
+
+impl PhoneBook {
+    /// Creates a contact from a copy of an existing one, such as a colleague at the same
+    /// company: the create prompts are pre-filled with the existing contact's details, each kept
+    /// by leaving the input empty.
+    ///
+    /// Only the details are copied; the new contact starts without additional emails, tags or
+    /// relationships.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn clone_contact(&mut self) {
+        let required_fields = match settings::required_fields() {
+            Ok(required_fields) => required_fields,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let prompt_order = match settings::prompt_order() {
+            Ok(prompt_order) => prompt_order,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let Some(original) =
+            self.prompt_listed_contact("Enter the index of the contact to clone: ")
+        else {
+            return;
+        };
+        let mut values: Vec<String> = FIELDS
+            .iter()
+            .map(|&(field, _)| original.field(field).to_string())
+            .collect();
+        for field in &prompt_order {
+            let (position, label) = field_position(field);
+            let Ok(value) =
+                Self::prompt_field("Enter", field, label, &required_fields, &values[position])
+            else {
+                println!("Contact cloning cancelled.");
+                return;
+            };
+            if value.is_empty() && required_fields.contains(field) {
+                println!("{} is required. Contact cloning cancelled.", label);
+                return;
+            }
+            values[position] = value;
+        }
+        let new_contact = Self::contact_from_values(values);
+        if let Err(err) = new_contact.validate() {
+            println!("{} Contact cloning cancelled.", err);
+            return;
+        }
+        new_contact.print_contact();
+        match Self::insert_contact(&new_contact, "clone") {
+            Ok(_) => println!(
+                "Contact created successfully from a copy of {} {}!",
+                original.first_name, original.last_name
+            ),
+            Err(err) => println!("Error saving contact: {}", err),
+        }
+    }
+}
@@ -1,13 +1,100 @@
+use std::io::{self, IsTerminal};
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use comfy_table::modifiers::UTF8_ROUND_CORNERS;
 use comfy_table::presets::UTF8_FULL;
-use comfy_table::{Cell, Table};
+use comfy_table::{Cell, ContentArrangement, Table};
+use crossterm::terminal;
 use diesel::{Insertable, Queryable};
+use email_address::{EmailAddress, Options};
+use serde::{Deserialize, Serialize};
 
 use crate::schema::contacts;
+use crate::settings;
+
+/// Define the contact fields, as `(column name, label)` pairs in the order they are prompted for.
+pub const FIELDS: &[(&str, &str)] = &[
+    ("first_name", "First name"),
+    ("last_name", "Last name"),
+    ("company", "Company"),
+    ("phone", "Phone number"),
+    ("extension", "Phone extension"),
+    ("email", "Email"),
+    ("address", "Address"),
+    ("website", "Website"),
+    ("preferred_method", "Preferred contact method"),
+];
+
+/// Define the ways a contact may prefer to be reached, stored by key in the `preferred_method`
+/// column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContactMethod {
+    /// A phone call.
+    Phone,
+    /// An email.
+    Email,
+    /// A text message.
+    Text,
+}
+
+impl ContactMethod {
+    /// Every method, in the order they are offered.
+    pub const ALL: [ContactMethod; 3] = [
+        ContactMethod::Phone,
+        ContactMethod::Email,
+        ContactMethod::Text,
+    ];
+
+    /// Returns the key the method is stored and entered as.
+    ///
+    /// # Returns
+    ///
+    /// The key, e.g. `email`.
+    pub fn key(self) -> &'static str {
+        match self {
+            ContactMethod::Phone => "phone",
+            ContactMethod::Email => "email",
+            ContactMethod::Text => "text",
+        }
+    }
+    /// Reads a method from its key, ignoring case and surrounding whitespace.
+    ///
+    /// # Parameters
+    ///
+    /// * `value`: The key, e.g. `Email`.
+    ///
+    /// # Returns
+    ///
+    /// The method, or `None` if the key isn't one of `ALL`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        Self::ALL
+            .into_iter()
+            .find(|method| method.key().eq_ignore_ascii_case(value))
+    }
+    /// Lists the keys of every method, for prompts and error messages.
+    ///
+    /// # Returns
+    ///
+    /// The keys separated by slashes, e.g. `phone/email/text`.
+    pub fn keys() -> String {
+        Self::ALL.map(ContactMethod::key).join("/")
+    }
+}
 
-/// Define a Contact struct with fields for first name, last name, email, address, and phone number.
-#[derive(Insertable, Queryable)]
-#[table_name = "contacts"]
+/// Width, in characters, contact tables are fitted to when the terminal width can't be detected,
+/// e.g. when the output is piped.
+const FALLBACK_TABLE_WIDTH: u16 = 120;
+
+/// Fields checked against the `PLACEHOLDER_VALUES` setting. Names are left alone, as a
+/// placeholder-looking name such as "Na" may be real.
+const PLACEHOLDER_FIELDS: &[&str] = &["phone", "email", "address", "website"];
+
+/// Define a Contact struct with fields for first name, last name, email, address, phone number,
+/// website, phone extension, company and preferred contact method, along with when the contact
+/// was created, last updated and last contacted.
+#[derive(Insertable, Queryable, Serialize, Deserialize)]
+#[diesel(table_name = contacts)]
 #[derive(Clone)]
 pub struct Contact {
     pub(crate) id: Option<i32>, // Diesel uses Option for nullable integer fields.
@@ -16,6 +103,18 @@ pub struct Contact {
     pub(crate) email: String,
     pub(crate) address: String,
     pub(crate) phone: String,
+    pub(crate) created_at: NaiveDateTime, // Stored in UTC.
+    pub(crate) updated_at: NaiveDateTime, // Stored in UTC.
+    pub(crate) website: String,
+    #[serde(default)] // Missing from audit log snapshots taken before extensions existed.
+    pub(crate) extension: String,
+    #[serde(default)] // Missing from audit log snapshots taken before it existed.
+    pub(crate) last_contacted: Option<NaiveDate>,
+    #[serde(default)] // Missing from audit log snapshots taken before it existed.
+    pub(crate) company: String,
+    /// The key of a `ContactMethod`, or empty if the contact has no preference.
+    #[serde(default)] // Missing from audit log snapshots taken before it existed.
+    pub(crate) preferred_method: String,
 }
 /// Contact struct implementation.
 impl Contact {
@@ -31,8 +130,10 @@ impl Contact {
     ///
     /// # Returns
     ///
-    /// A new `Contact` instance with the provided details. The phone number is standardized using the
-    /// `standardize_phone_number` method.
+    /// A new `Contact` instance with the provided details, cleaned of control characters by
+    /// `sanitize_text`. Unless the `STANDARDIZE_PHONE` setting is off, the phone number is
+    /// standardized using the `standardize_phone_number` method, and the names are title-cased
+    /// if the `TITLE_CASE_NAMES` setting is on.
     pub fn new(
         first_name: String,
         last_name: String,
@@ -40,14 +141,31 @@ impl Contact {
         address: String,
         phone_number: String,
     ) -> Self {
-        let standard_phone_number = Self::standardize_phone_number(&phone_number);
-        Self {
-            first_name,
-            last_name,
-            email,
-            address,
-            phone: standard_phone_number,
+        let phone_number = sanitize_text(&phone_number);
+        // The setting is validated at startup, so fall back to the default if it has since changed.
+        let phone = if settings::standardize_phone().unwrap_or(true) {
+            Self::standardize_phone_number(&phone_number)
+        } else {
+            phone_number
+        };
+        let mut contact = Self {
+            first_name: sanitize_text(&first_name),
+            last_name: sanitize_text(&last_name),
+            email: sanitize_text(&email),
+            address: sanitize_text(&address),
+            phone,
             ..Default::default()
+        };
+        contact.normalize_name_case();
+        contact
+    }
+    /// Title-cases the first and last names with `title_case_name` if the `TITLE_CASE_NAMES`
+    /// setting is on, so names typed or imported in all caps or all lowercase are stored alike.
+    pub(crate) fn normalize_name_case(&mut self) {
+        // The setting is validated at startup, so fall back to the default if it has since changed.
+        if settings::title_case_names().unwrap_or(false) {
+            self.first_name = title_case_name(&self.first_name);
+            self.last_name = title_case_name(&self.last_name);
         }
     }
     /// Prints the contact information in a table format.
@@ -63,28 +181,291 @@ impl Contact {
     ///
     /// This function does not return any value. It prints the contact information to the console.
     pub fn print_contact(&self) {
-        let mut table = Table::new();
-        table
-            .load_preset(UTF8_FULL)
-            .apply_modifier(UTF8_ROUND_CORNERS)
-            .set_header(vec![
-                Cell::new("First Name").add_attribute(comfy_table::Attribute::Bold),
-                Cell::new("Last Name").add_attribute(comfy_table::Attribute::Bold),
-                Cell::new("Phone Number").add_attribute(comfy_table::Attribute::Bold),
-                Cell::new("Email").add_attribute(comfy_table::Attribute::Bold),
-                Cell::new("Address").add_attribute(comfy_table::Attribute::Bold),
-            ]);
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("First Name").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Last Name").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Phone Number").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Email").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Address").add_attribute(comfy_table::Attribute::Bold),
+        ]);
         // Add contact details to the table
         table.add_row(vec![
             &self.first_name,
             &self.last_name,
-            &self.phone,
+            &self.full_phone(),
             &self.email,
             &self.address,
         ]);
+        fit_to_terminal(&mut table);
         // Print the contact information
         println!("{}", table);
     }
+    /// Validates the contact against the configured rules.
+    ///
+    /// Each field must be no longer than its maximum length from the `MAX_FIELD_LENGTHS` setting,
+    /// which keeps tables readable and the database tidy. The phone number, email, address and
+    /// website must not be placeholders such as `N/A` from the `PLACEHOLDER_VALUES` setting. A
+    /// website, if any, must be an http or https URL, a phone extension must be numeric, and a
+    /// preferred contact method must be the key of a `ContactMethod`. An email, if any, must be
+    /// well-formed, and a phone number must have at least the digits of `MIN_PHONE_DIGITS` or of
+    /// the `PHONE_REGION` setting's numbers.
+    ///
+    /// This is checked before every write, whether the contact was typed, imported or added
+    /// through the library.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the contact is valid, otherwise a message describing the first problem found.
+    pub fn validate(&self) -> Result<(), String> {
+        let max_lengths = settings::max_field_lengths()?;
+        for &(field, label) in FIELDS {
+            let length = self.field(field).chars().count();
+            let max_length = max_lengths
+                .iter()
+                .find(|(name, _)| name == field)
+                .map_or(usize::MAX, |&(_, max_length)| max_length);
+            if length > max_length {
+                return Err(format!(
+                    "{} is too long ({} characters, maximum {}).",
+                    label, length, max_length
+                ));
+            }
+        }
+        let placeholders = settings::placeholder_values();
+        if let Some(&(field, label)) = FIELDS
+            .iter()
+            .find(|&&(field, _)| is_placeholder(field, self.field(field), &placeholders))
+        {
+            return Err(format!(
+                "{} '{}' is a placeholder; leave it empty instead.",
+                label,
+                self.field(field)
+            ));
+        }
+        if !self.website.is_empty() && !is_valid_url(&self.website) {
+            return Err(format!(
+                "Website must start with http:// or https:// ({}).",
+                self.website
+            ));
+        }
+        if !self.preferred_method.is_empty()
+            && ContactMethod::parse(&self.preferred_method).map(ContactMethod::key)
+                != Some(self.preferred_method.as_str())
+        {
+            return Err(format!(
+                "Preferred contact method must be one of {} ({}).",
+                ContactMethod::keys(),
+                self.preferred_method
+            ));
+        }
+        if !self.extension.chars().all(|c| c.is_ascii_digit()) {
+            return Err(format!(
+                "Phone extension must only contain digits ({}).",
+                self.extension
+            ));
+        }
+        match self.email_problem().or_else(|| self.phone_digits_problem()) {
+            Some(problem) => Err(problem),
+            None => Ok(()),
+        }
+    }
+    /// Lists every way the contact fails the current validation rules, unlike `validate` which
+    /// stops at the first length problem. Used to find bad data stored before a rule existed.
+    ///
+    /// # Parameters
+    ///
+    /// * `required_fields`: The fields that must not be empty, from the `REQUIRED_FIELDS` setting.
+    ///
+    /// # Returns
+    ///
+    /// A description of each problem, empty if the contact is valid.
+    pub(crate) fn problems(&self, required_fields: &[String]) -> Vec<String> {
+        let mut problems = Vec::new();
+        for &(field, label) in FIELDS {
+            if self.field(field).is_empty() && required_fields.iter().any(|name| name == field) {
+                problems.push(format!("{} is required.", label));
+            }
+        }
+        let first = self.validate().err();
+        problems.extend(first.clone());
+        // `validate` stops at the first problem, which may be one of these
+        for problem in [self.email_problem(), self.phone_digits_problem()]
+            .into_iter()
+            .flatten()
+        {
+            if Some(&problem) != first.as_ref() {
+                problems.push(problem);
+            }
+        }
+        problems
+    }
+    /// Checks that the contact's email, if any, is well-formed.
+    ///
+    /// # Returns
+    ///
+    /// A description of the problem, or `None` if the email is valid or empty.
+    fn email_problem(&self) -> Option<String> {
+        (!self.email.is_empty() && !is_valid_email(&self.email))
+            .then(|| format!("Invalid email ({}).", self.email))
+    }
+    /// Checks that the contact's phone number, if any, has at least the digits of
+    /// `MIN_PHONE_DIGITS` or of the `PHONE_REGION` setting's numbers.
+    ///
+    /// # Returns
+    ///
+    /// A description of the problem, or `None` if the number is long enough or empty.
+    fn phone_digits_problem(&self) -> Option<String> {
+        // The setting is validated at startup, so fall back to the default if it has since changed.
+        let min_digits = settings::min_phone_digits().unwrap_or(settings::DEFAULT_MIN_PHONE_DIGITS);
+        let digits = self.phone.chars().filter(|c| c.is_ascii_digit()).count();
+        (!self.phone.is_empty() && digits < min_digits).then(|| {
+            format!(
+                "Phone number is too short ({} digits, minimum {}).",
+                digits, min_digits
+            )
+        })
+    }
+    /// Lists every way a single field value fails the validation rules, with the same checks
+    /// `problems` runs on a whole contact, so a prompt can reject a bad value as soon as it is
+    /// entered.
+    ///
+    /// # Parameters
+    ///
+    /// * `field`: The column name of the field, e.g. `email`.
+    /// * `value`: The value entered for it.
+    ///
+    /// # Returns
+    ///
+    /// A description of each problem, empty if the value is valid.
+    pub(crate) fn field_problems(field: &str, value: &str) -> Vec<String> {
+        let mut contact = Contact::default();
+        contact.set_field(field, value.to_string());
+        contact.problems(&[])
+    }
+    /// Empties the fields holding placeholders such as `N/A` from the `PLACEHOLDER_VALUES`
+    /// setting, so imports don't fill the phone book with non-values.
+    pub(crate) fn clear_placeholders(&mut self) {
+        let placeholders = settings::placeholder_values();
+        for &field in PLACEHOLDER_FIELDS {
+            if is_placeholder(field, self.field(field), &placeholders) {
+                self.set_field(field, String::new());
+            }
+        }
+    }
+    /// Returns the id the database assigned to the contact.
+    ///
+    /// # Returns
+    ///
+    /// The id, or `None` if the contact hasn't been saved.
+    pub fn id(&self) -> Option<i32> {
+        self.id
+    }
+    /// Returns the value of a contact field by its column name.
+    ///
+    /// # Parameters
+    ///
+    /// * `field`: The column name of the field, as listed in `FIELDS`.
+    ///
+    /// # Returns
+    ///
+    /// The field's value, or an empty string if `field` isn't a contact field.
+    pub fn field(&self, field: &str) -> &str {
+        match field {
+            "first_name" => &self.first_name,
+            "last_name" => &self.last_name,
+            "phone" => &self.phone,
+            "email" => &self.email,
+            "address" => &self.address,
+            "website" => &self.website,
+            "extension" => &self.extension,
+            "company" => &self.company,
+            "preferred_method" => &self.preferred_method,
+            _ => "",
+        }
+    }
+    /// Sets the value of a contact field by its column name.
+    ///
+    /// # Parameters
+    ///
+    /// * `field`: The column name of the field, as listed in `FIELDS`.
+    /// * `value`: The new value, cleaned of control characters by `sanitize_text`. It is ignored
+    ///   if `field` isn't a contact field.
+    pub fn set_field(&mut self, field: &str, value: String) {
+        let value = sanitize_text(&value);
+        match field {
+            "first_name" => self.first_name = value,
+            "last_name" => self.last_name = value,
+            "phone" => self.phone = value,
+            "email" => self.email = value,
+            "address" => self.address = value,
+            "website" => self.website = value,
+            "extension" => self.extension = value,
+            "company" => self.company = value,
+            "preferred_method" => self.preferred_method = value,
+            _ => {}
+        }
+    }
+    /// Formats the phone number with its extension, if any, e.g. `(555) 123-4567 x123`.
+    ///
+    /// # Returns
+    ///
+    /// The phone number as shown in the detail view.
+    pub(crate) fn full_phone(&self) -> String {
+        if self.extension.is_empty() {
+            self.phone.clone()
+        } else {
+            format!("{} x{}", self.phone, self.extension)
+        }
+    }
+    /// Extracts the US area code from the contact's phone number.
+    ///
+    /// Only the digits of the phone number are considered, so both standardized numbers such as
+    /// `(533) 164-6251` and raw ones such as `+1 533 164 6251` are recognised.
+    ///
+    /// # Returns
+    ///
+    /// The three-digit area code, or `None` if the number isn't a 10-digit US number (optionally
+    /// prefixed with the country code `1`).
+    pub(crate) fn area_code(&self) -> Option<String> {
+        let digits: String = self.phone.chars().filter(|c| c.is_ascii_digit()).collect();
+        match digits.len() {
+            10 => Some(digits[0..3].to_string()),
+            11 if digits.starts_with('1') => Some(digits[1..4].to_string()),
+            _ => None,
+        }
+    }
+    /// Rewrites the US area code of the contact's phone number, keeping its formatting, e.g.
+    /// `(533) 164-6251` into `(534) 164-6251` or `+1 533 164 6251` into `+1 534 164 6251`.
+    ///
+    /// # Parameters
+    ///
+    /// * `area_code`: The new three-digit area code.
+    ///
+    /// # Returns
+    ///
+    /// The rewritten phone number, or `None` if the number has no area code, as for `area_code`.
+    pub(crate) fn with_area_code(&self, area_code: &str) -> Option<String> {
+        self.area_code()?;
+        // The country code 1 of an 11-digit number comes before the area code
+        let mut country_code = self.phone.chars().filter(char::is_ascii_digit).count() - 10;
+        let mut new_digits = area_code.chars();
+        Some(
+            self.phone
+                .chars()
+                .map(|c| {
+                    if !c.is_ascii_digit() {
+                        c
+                    } else if country_code > 0 {
+                        country_code -= 1;
+                        c
+                    } else {
+                        new_digits.next().unwrap_or(c)
+                    }
+                })
+                .collect(),
+        )
+    }
     /// Standardizes a phone number by removing non-numeric characters and formatting it as (XXX) XXX-XXXX.
     /// If the phone number does not have exactly 10 digits, it is returned as is.
     ///
@@ -96,9 +477,12 @@ impl Contact {
     ///
     /// A string representing the standardized phone number. If the input phone number does not have 10 digits,
     /// it is returned as is.
-    fn standardize_phone_number(phone_number: &str) -> String {
+    pub(crate) fn standardize_phone_number(phone_number: &str) -> String {
         // Remove non-numeric characters
-        let digits: String = phone_number.chars().filter(|c| c.is_digit(10)).collect();
+        let digits: String = phone_number
+            .chars()
+            .filter(|c| c.is_ascii_digit())
+            .collect();
         // Format the phone number
         if digits.len() == 10 {
             format!("({}) {}-{}", &digits[0..=2], &digits[3..=5], &digits[6..=9])
@@ -107,6 +491,253 @@ impl Contact {
         }
     }
 }
+/// Replaces control characters, such as pasted line breaks or tabs, with spaces, so they can't
+/// break table rendering or exported files.
+///
+/// A run of control characters becomes a single space, and leading and trailing whitespace is
+/// removed.
+///
+/// # Parameters
+///
+/// * `value`: The text to clean.
+///
+/// # Returns
+///
+/// The cleaned text.
+pub(crate) fn sanitize_text(value: &str) -> String {
+    let mut sanitized = String::with_capacity(value.len());
+    let mut in_control_run = false;
+    for c in value.chars() {
+        if c.is_control() {
+            if !in_control_run {
+                sanitized.push(' ');
+            }
+            in_control_run = true;
+        } else {
+            sanitized.push(c);
+            in_control_run = false;
+        }
+    }
+    sanitized.trim().to_string()
+}
+/// Name suffixes kept with the last name when splitting a full name, compared ignoring case and
+/// trailing dots.
+const NAME_SUFFIXES: &[&str] = &["jr", "sr", "ii", "iii", "iv", "v", "phd", "md", "esq"];
+/// Particles written in lowercase within a name when the `TITLE_CASE_NAMES` setting is on, e.g.
+/// the "van" of "Ludwig van Beethoven".
+const NAME_PARTICLES: &[&str] = &[
+    "da", "de", "del", "della", "den", "der", "di", "du", "la", "le", "ter", "van", "von",
+];
+/// Name suffixes written in uppercase when the `TITLE_CASE_NAMES` setting is on.
+const UPPERCASE_SUFFIXES: &[&str] = &["ii", "iii", "iv", "md"];
+/// Title-cases a first or last name typed in a single case, e.g. "john" or "MCDONALD", into
+/// "John" and "McDonald".
+///
+/// Each word is capitalized, including after a hyphen or the apostrophe of "O'Brien", and the
+/// word after a "Mc" prefix. Particles such as "van" and "de" stay lowercase unless they end the
+/// name, and suffixes such as "III" are uppercased. Words already in mixed case, such as
+/// "DeShawn", are kept as typed.
+///
+/// # Parameters
+///
+/// * `name`: The name.
+///
+/// # Returns
+///
+/// The title-cased name.
+pub(crate) fn title_case_name(name: &str) -> String {
+    let words: Vec<&str> = name.split(' ').collect();
+    let last = words.len().saturating_sub(1);
+    words
+        .iter()
+        .enumerate()
+        .map(|(position, &word)| {
+            let lowercase = word.to_lowercase();
+            if word != lowercase && word != word.to_uppercase() {
+                return word.to_string();
+            }
+            let bare = lowercase.trim_end_matches(['.', ',']);
+            if position < last && NAME_PARTICLES.contains(&bare) {
+                return lowercase;
+            }
+            if UPPERCASE_SUFFIXES.contains(&bare) {
+                return word.to_uppercase();
+            }
+            if bare == "phd" {
+                return lowercase.replacen("phd", "PhD", 1);
+            }
+            lowercase
+                .split('-')
+                .map(title_case_part)
+                .collect::<Vec<String>>()
+                .join("-")
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+/// Title-cases a lowercase part of a name between hyphens, e.g. "mcdonald" or "o'brien".
+///
+/// # Parameters
+///
+/// * `part`: The part of the name, in lowercase.
+///
+/// # Returns
+///
+/// The capitalized part, e.g. "McDonald" or "O'Brien".
+fn title_case_part(part: &str) -> String {
+    let capitalize = |text: &str| {
+        let mut chars = text.chars();
+        chars.next().map_or_else(String::new, |first| {
+            first.to_uppercase().chain(chars).collect()
+        })
+    };
+    if let Some(rest) = part.strip_prefix("mc").filter(|rest| !rest.is_empty()) {
+        return format!("Mc{}", capitalize(rest));
+    }
+    match part.split_once('\'') {
+        Some((prefix, rest)) if prefix.chars().count() == 1 => {
+            format!("{}'{}", capitalize(prefix), capitalize(rest))
+        }
+        _ => capitalize(part),
+    }
+}
+/// Splits a full name into a first and a last name: the last word is the last name and the rest
+/// the first name, e.g. "John Q. Public" into "John Q." and "Public". A suffix such as "Jr." stays
+/// with the last name, and "Public, John Q." is read as last name first.
+///
+/// # Parameters
+///
+/// * `full_name`: The full name.
+///
+/// # Returns
+///
+/// The first and last names. A single word is a first name, with an empty last name.
+pub(crate) fn split_full_name(full_name: &str) -> (String, String) {
+    let is_suffix =
+        |word: &str| NAME_SUFFIXES.contains(&word.trim_end_matches('.').to_lowercase().as_str());
+    let full_name = sanitize_text(full_name);
+    if let Some((last, first)) = full_name.split_once(',') {
+        if !first.trim().is_empty() && !is_suffix(first.trim()) {
+            return (first.trim().to_string(), last.trim().to_string());
+        }
+    }
+    let words: Vec<&str> = full_name.split_whitespace().collect();
+    // The suffix and the word before it form the last name, unless that leaves no first name
+    let last_start = match words.len() {
+        0 | 1 => return (words.join(" "), String::new()),
+        2 => 1,
+        length if is_suffix(words[length - 1].trim_end_matches(',')) => length - 2,
+        length => length - 1,
+    };
+    (words[..last_start].join(" "), words[last_start..].join(" "))
+}
+/// Checks that a string is an email address, following the RFC 5322 syntax: a valid local part,
+/// an `@`, and a domain with a top-level domain, e.g. `first.last+tag@mail.example.com`. Display
+/// names such as `John <john@example.com>` and IP address domains are rejected, as they don't
+/// belong in a contact's email field.
+///
+/// # Parameters
+///
+/// * `email`: The string to check.
+///
+/// # Returns
+///
+/// `true` if the string is a valid email address.
+pub(crate) fn is_valid_email(email: &str) -> bool {
+    let options = Options::default()
+        .with_required_tld()
+        .without_display_text()
+        .without_domain_literal();
+    EmailAddress::parse_with_options(email, options).is_ok()
+}
+/// Checks that a string looks like a web address: `http://` or `https://` followed by a host,
+/// without whitespace.
+///
+/// # Parameters
+///
+/// * `url`: The string to check.
+///
+/// # Returns
+///
+/// `true` if the string looks like a web address.
+pub(crate) fn is_valid_url(url: &str) -> bool {
+    let lowercase = url.to_lowercase();
+    let rest = lowercase
+        .strip_prefix("https://")
+        .or_else(|| lowercase.strip_prefix("http://"));
+    rest.is_some_and(|rest| !rest.is_empty() && !rest.starts_with('/'))
+        && !url.contains(char::is_whitespace)
+}
+/// Shortens a value to a maximum number of characters for display, ending it with an ellipsis
+/// when it is cut.
+///
+/// # Parameters
+///
+/// * `value`: The value to display.
+/// * `width`: The maximum number of characters, including the ellipsis.
+///
+/// # Returns
+///
+/// The value, or its first `width - 1` characters followed by `…`.
+pub(crate) fn truncate_display(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        return value.to_string();
+    }
+    let kept: String = value.chars().take(width.saturating_sub(1)).collect();
+    format!("{}…", kept.trim_end())
+}
+/// Creates an empty table drawn in the style of the `TABLE_STYLE` setting.
+///
+/// # Returns
+///
+/// The table, with the borders of the style.
+pub(crate) fn styled_table() -> Table {
+    // The setting is validated at startup, so fall back to the default if it has since changed.
+    let (preset, modifier) =
+        settings::table_style().unwrap_or((UTF8_FULL, Some(UTF8_ROUND_CORNERS)));
+    let mut table = Table::new();
+    table.load_preset(preset);
+    if let Some(modifier) = modifier {
+        table.apply_modifier(modifier);
+    }
+    table
+}
+/// Makes a table fit the width of the terminal, wrapping the content of its widest columns
+/// rather than letting rows overflow and break the borders on small screens.
+///
+/// # Parameters
+///
+/// * `table`: The table to fit. Its width is the terminal's when standard output is a terminal,
+///   and `FALLBACK_TABLE_WIDTH` otherwise.
+pub(crate) fn fit_to_terminal(table: &mut Table) {
+    let width = if io::stdout().is_terminal() {
+        terminal::size().ok().map(|(columns, _)| columns)
+    } else {
+        None
+    };
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_width(width.unwrap_or(FALLBACK_TABLE_WIDTH));
+}
+/// Formats a UTC timestamp for display in the user's local timezone.
+///
+/// # Parameters
+///
+/// * `timestamp`: The timestamp to format, in UTC.
+///
+/// # Returns
+///
+/// The timestamp formatted in local time with the `DATE_FORMAT` setting, `YYYY-MM-DD HH:MM` by
+/// default.
+pub(crate) fn format_timestamp(timestamp: &NaiveDateTime) -> String {
+    // The setting is validated at startup, so fall back to the default if it has since changed.
+    let format =
+        settings::date_format().unwrap_or_else(|_| settings::DEFAULT_DATE_FORMAT.to_string());
+    Local
+        .from_utc_datetime(timestamp)
+        .format(&format)
+        .to_string()
+}
 /// Implements the `Default` trait for the `Contact` struct.
 ///
 /// The `Default` trait provides a way to create a default instance of a type.
@@ -122,7 +753,14 @@ impl Default for Contact {
     /// - `email`: An empty string.
     /// - `address`: An empty string.
     /// - `phone_number`: An empty string.
+    /// - `website`: An empty string.
+    /// - `extension`: An empty string.
+    /// - `last_contacted`: `None`.
+    /// - `company`: An empty string.
+    /// - `preferred_method`: An empty string.
+    /// - `created_at` and `updated_at`: The current time.
     fn default() -> Self {
+        let now = Utc::now().naive_utc();
         Contact {
             id: None, // Diesel uses Option for nullable integer fields.
             first_name: String::new(),
@@ -130,6 +768,244 @@ impl Default for Contact {
             email: String::new(),
             address: String::new(),
             phone: String::new(),
+            created_at: now,
+            updated_at: now,
+            website: String::new(),
+            extension: String::new(),
+            last_contacted: None,
+            company: String::new(),
+            preferred_method: String::new(),
+        }
+    }
+}
+
+/// Checks whether a field holds a placeholder rather than a real value. Values are compared
+/// ignoring case, and phone numbers by their digits, so `(000) 000-0000` matches `000-000-0000`.
+///
+/// # Parameters
+///
+/// * `field`: The column name of the field.
+/// * `value`: The value of the field.
+/// * `placeholders`: The lowercase placeholders, from the `PLACEHOLDER_VALUES` setting.
+///
+/// # Returns
+///
+/// `true` if the field is checked for placeholders and the value is one.
+fn is_placeholder(field: &str, value: &str, placeholders: &[String]) -> bool {
+    if value.is_empty() || !PLACEHOLDER_FIELDS.contains(&field) {
+        return false;
+    }
+    let value = value.trim().to_lowercase();
+    let digits = |text: &str| -> String { text.chars().filter(char::is_ascii_digit).collect() };
+    placeholders.iter().any(|placeholder| {
+        *placeholder == value
+            || (field == "phone"
+                && !digits(placeholder).is_empty()
+                && digits(placeholder) == digits(&value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{
+        format_timestamp, is_valid_email, split_full_name, title_case_name, ContactMethod,
+    };
+    use crate::phone_book::library::Error;
+    use crate::settings;
+    use crate::test_support::{contact, open_book, set};
+
+    #[test]
+    fn over_length_first_name_is_rejected() {
+        let mut book = open_book();
+        let long_name = "J".repeat(257);
+        let err = contact(&long_name, "Doe", "5551234567", "")
+            .validate()
+            .unwrap_err();
+        assert_eq!(err, "First name is too long (257 characters, maximum 256).");
+        assert_eq!(
+            book.add(contact(&long_name, "Doe", "5551234567", "")),
+            Err(Error::InvalidContact(err))
+        );
+        assert!(contact(&"J".repeat(256), "Doe", "5551234567", "")
+            .validate()
+            .is_ok());
+
+        set("MAX_FIELD_LENGTHS", "first_name=5");
+        assert!(contact("Johnny", "Doe", "5551234567", "")
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn phone_is_stored_verbatim_when_standardization_is_off() {
+        let mut book = open_book();
+        assert_eq!(
+            contact("John", "Doe", "555.123.4567", "").phone,
+            "(555) 123-4567"
+        );
+
+        set("STANDARDIZE_PHONE", "false");
+        let contact_id = book
+            .add(contact("John", "Doe", "555.123.4567", ""))
+            .unwrap();
+        let stored = book.get(contact_id).unwrap().unwrap();
+        assert_eq!(stored.phone, "555.123.4567");
+    }
+
+    #[test]
+    fn malformed_website_is_rejected() {
+        let _book = open_book();
+        let mut john = contact("John", "Doe", "5551234567", "");
+        for website in ["https://john.example", "http://john.example/about", ""] {
+            john.website = website.to_string();
+            assert!(john.validate().is_ok(), "{}", website);
+        }
+        for website in ["john.example", "ftp://john.example", "https://"] {
+            john.website = website.to_string();
+            assert!(john.validate().is_err(), "{}", website);
+        }
+    }
+
+    #[test]
+    fn control_characters_are_replaced_before_storage() {
+        let mut book = open_book();
+        let mut john = contact("John\nPaul", "Doe\r\n", "5551234567", "");
+        john.set_field("address", "1 Main St\t\tApt 2".to_string());
+        let contact_id = book.add(john).unwrap();
+        let stored = book.get(contact_id).unwrap().unwrap();
+        assert_eq!(stored.first_name, "John Paul");
+        assert_eq!(stored.last_name, "Doe");
+        assert_eq!(stored.address, "1 Main St Apt 2");
+    }
+
+    #[test]
+    fn timestamps_are_shown_in_the_configured_date_format() {
+        let _book = open_book();
+        // Noon UTC mid-month keeps the date the same in every timezone
+        let timestamp = NaiveDate::from_ymd_opt(2024, 6, 15)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        set("DATE_FORMAT", "%d/%m/%Y");
+        assert_eq!(format_timestamp(&timestamp), "15/06/2024");
+
+        set("DATE_FORMAT", "%Y-%Q");
+        assert_eq!(
+            settings::validate(),
+            Err("Invalid DATE_FORMAT setting: '%Y-%Q' is not a valid strftime format.".to_string())
+        );
+    }
+
+    #[test]
+    fn short_regional_number_passes_while_noise_fails() {
+        let _book = open_book();
+        let too_short = |phone: &str| {
+            contact("John", "Doe", phone, "")
+                .problems(&[])
+                .iter()
+                .any(|problem| problem.starts_with("Phone number is too short"))
+        };
+        assert!(too_short("4002"));
+        set("PHONE_REGION", "NU");
+        assert!(!too_short("4002"));
+        assert!(too_short("12"));
+
+        set("PHONE_REGION", "US");
+        assert!(too_short("555-1234"));
+        assert!(!too_short("5551234567"));
+    }
+
+    #[test]
+    fn full_names_are_split_into_first_and_last_names() {
+        let split = split_full_name;
+        assert_eq!(split("John Q. Public"), ("John Q.".into(), "Public".into()));
+        assert_eq!(split("Madonna"), ("Madonna".into(), String::new()));
+        assert_eq!(
+            split("Martin Luther King Jr."),
+            ("Martin Luther".into(), "King Jr.".into())
+        );
+        assert_eq!(split("Sammy Jr."), ("Sammy".into(), "Jr.".into()));
+        assert_eq!(
+            split("Public, John Q."),
+            ("John Q.".into(), "Public".into())
+        );
+        assert_eq!(split("  "), (String::new(), String::new()));
+    }
+
+    #[test]
+    fn tricky_but_valid_emails_pass_and_invalid_ones_fail() {
+        for email in [
+            "first.last+tag@mail.example.com",
+            "o'brien@example.ie",
+            "x@example.io",
+            "user_name-1@sub.domain.example.org",
+        ] {
+            assert!(is_valid_email(email), "{} should be valid", email);
+        }
+        for email in [
+            "plainaddress",
+            "@example.com",
+            "john@",
+            "john@example",
+            "john..doe@example.com",
+            "john doe@example.com",
+            "John <john@example.com>",
+            "john@[192.168.0.1]",
+        ] {
+            assert!(!is_valid_email(email), "{} should be invalid", email);
+        }
+    }
+
+    #[test]
+    fn only_allowed_contact_methods_are_accepted() {
+        let _book = open_book();
+        let with_method = |method: &str| {
+            let mut new_contact = contact("John", "Doe", "5551234567", "");
+            new_contact.set_field("preferred_method", method.to_string());
+            new_contact.validate()
+        };
+        for method in ["", "phone", "email", "text"] {
+            assert_eq!(with_method(method), Ok(()), "{}", method);
+        }
+        for method in ["fax", "Email", "phone/email"] {
+            assert_eq!(
+                with_method(method),
+                Err(format!(
+                    "Preferred contact method must be one of phone/email/text ({}).",
+                    method
+                ))
+            );
+        }
+        assert_eq!(ContactMethod::parse(" Text "), Some(ContactMethod::Text));
+        assert_eq!(ContactMethod::parse("pigeon"), None);
+    }
+
+    #[test]
+    fn names_are_title_cased_when_the_setting_is_on() {
+        let _book = open_book();
+        let typed = contact("john", "MCDONALD", "5551234567", "");
+        assert_eq!(
+            (typed.first_name.as_str(), typed.last_name.as_str()),
+            ("john", "MCDONALD")
+        );
+
+        set("TITLE_CASE_NAMES", "true");
+        let typed = contact("john", "MCDONALD", "5551234567", "");
+        assert_eq!(
+            (typed.first_name.as_str(), typed.last_name.as_str()),
+            ("John", "McDonald")
+        );
+        for (name, expected) in [
+            ("LUDWIG VAN BEETHOVEN", "Ludwig van Beethoven"),
+            ("maria de la cruz", "Maria de la Cruz"),
+            ("o'brien-smith", "O'Brien-Smith"),
+            ("john smith iii", "John Smith III"),
+            ("DeShawn", "DeShawn"),
+            ("van", "Van"),
+        ] {
+            assert_eq!(title_case_name(name), expected);
         }
     }
 }
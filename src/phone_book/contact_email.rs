@@ -0,0 +1,163 @@
+use comfy_table::Cell;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+
+use crate::phone_book::contact::styled_table;
+use crate::schema::emails;
+
+/// Define a ContactEmail struct for the additional, labeled email addresses of a contact.
+///
+/// The contact's primary email stays in `Contact::email`; these rows hold any others, such as a
+/// work or personal address.
+#[derive(Insertable, Queryable, Clone)]
+#[diesel(table_name = emails)]
+pub struct ContactEmail {
+    pub(crate) id: Option<i32>, // Diesel uses Option for nullable integer fields.
+    pub(crate) contact_id: i32,
+    pub(crate) label: String,
+    pub(crate) address: String,
+}
+/// ContactEmail struct implementation.
+impl ContactEmail {
+    /// Creates a new `ContactEmail` for the given contact.
+    ///
+    /// # Parameters
+    ///
+    /// * `contact_id`: The id of the contact the email belongs to.
+    /// * `label`: A short description of the email, e.g. "work".
+    /// * `address`: The email address.
+    ///
+    /// # Returns
+    ///
+    /// A new, not yet saved, `ContactEmail` instance.
+    pub fn new(contact_id: i32, label: String, address: String) -> Self {
+        Self {
+            id: None,
+            contact_id,
+            label,
+            address,
+        }
+    }
+    /// Loads the additional emails of a contact, in the order they were added.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    /// * `contact_id`: The id of the contact whose emails to load.
+    ///
+    /// # Returns
+    ///
+    /// The contact's additional emails.
+    pub fn load_for(
+        connection: &mut SqliteConnection,
+        contact_id: i32,
+    ) -> QueryResult<Vec<ContactEmail>> {
+        emails::table
+            .filter(emails::contact_id.eq(contact_id))
+            .order(emails::id.asc())
+            .load::<ContactEmail>(connection)
+    }
+    /// Replaces the additional emails of a contact with the given ones, in a transaction.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    /// * `contact_id`: The id of the contact whose emails to replace.
+    /// * `new_emails`: The new `(label, address)` pairs.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the emails were saved.
+    pub fn replace_for(
+        connection: &mut SqliteConnection,
+        contact_id: i32,
+        new_emails: &[(String, String)],
+    ) -> QueryResult<()> {
+        connection.transaction(|connection| {
+            diesel::delete(emails::table.filter(emails::contact_id.eq(contact_id)))
+                .execute(connection)?;
+            let rows: Vec<ContactEmail> = new_emails
+                .iter()
+                .map(|(label, address)| Self::new(contact_id, label.clone(), address.clone()))
+                .collect();
+            diesel::insert_into(emails::table)
+                .values(&rows)
+                .execute(connection)?;
+            Ok(())
+        })
+    }
+    /// Prints labeled emails in a table format.
+    ///
+    /// # Parameters
+    ///
+    /// * `emails`: The `(label, address)` pairs to print.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints nothing if there are no emails.
+    pub fn print_emails(emails: &[(String, String)]) {
+        if emails.is_empty() {
+            return;
+        }
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("Label").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Email").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        for (label, address) in emails {
+            table.add_row(vec![label, address]);
+        }
+        println!("{}", table);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::establish_connection;
+    use crate::phone_book::PhoneBook;
+    use crate::schema::{contacts, tags};
+    use crate::test_support::{contact, open_book};
+
+    #[test]
+    fn labeled_emails_are_read_back_in_order() {
+        let mut book = open_book();
+        let contact_id = book.add(contact("John", "Doe", "5551234567", "")).unwrap();
+        let other_id = book.add(contact("Jane", "Doe", "5551234568", "")).unwrap();
+        let emails = [
+            ("work".to_string(), "john@work.example".to_string()),
+            ("home".to_string(), "john@home.example".to_string()),
+        ];
+        let mut connection = establish_connection();
+        ContactEmail::replace_for(&mut connection, contact_id, &emails).unwrap();
+        let loaded: Vec<(String, String)> = ContactEmail::load_for(&mut connection, contact_id)
+            .unwrap()
+            .into_iter()
+            .map(|email| (email.label, email.address))
+            .collect();
+        assert_eq!(loaded, emails);
+        assert!(ContactEmail::load_for(&mut connection, other_id)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn deleting_a_contact_cascades_to_its_emails_and_tags() {
+        let _book = open_book();
+        let contact_id = PhoneBook::insert_contact_with(
+            &contact("John", "Doe", "5551234567", ""),
+            &[("work".to_string(), "john@work.example".to_string())],
+            &["family".to_string()],
+            "create",
+        )
+        .unwrap();
+        let mut connection = establish_connection();
+        // A plain delete, leaving the dependent rows to the foreign keys
+        diesel::delete(contacts::table.filter(contacts::id.eq(contact_id)))
+            .execute(&mut connection)
+            .unwrap();
+        let emails: i64 = emails::table.count().get_result(&mut connection).unwrap();
+        let tags: i64 = tags::table.count().get_result(&mut connection).unwrap();
+        assert_eq!((emails, tags), (0, 0));
+    }
+}
@@ -0,0 +1,468 @@
+use std::fs;
+
+use serde_json::Value;
+
+use crate::phone_book::contact::{is_valid_email, sanitize_text, split_full_name, Contact};
+use crate::phone_book::import_rules::{apply_import_rules, ImportRule};
+use crate::phone_book::phone_book::PhoneBook;
+use crate::shutdown::BusyGuard;
+
+/// Keys under which exports nest the list of contacts in a top-level object.
+const LIST_KEYS: &[&str] = &["contacts", "connections", "data", "items"];
+/// Keys of the structured name, as an object or a list of objects.
+const NAME_KEYS: &[&str] = &["name", "names", "structuredName"];
+/// Keys of the given name, in the name object or the contact itself.
+const GIVEN_NAME_KEYS: &[&str] = &["givenName", "given", "firstName", "first_name"];
+/// Keys of the family name, in the name object or the contact itself.
+const FAMILY_NAME_KEYS: &[&str] = &["familyName", "family", "lastName", "last_name"];
+/// Keys of the whole name, used when the given and family names are missing.
+const DISPLAY_NAME_KEYS: &[&str] = &["displayName", "formatted", "fullName", "display_name"];
+/// Keys of the list of phone numbers.
+const PHONE_KEYS: &[&str] = &["phoneNumbers", "phones", "phone_numbers"];
+/// Keys of the list of email addresses.
+const EMAIL_KEYS: &[&str] = &["emailAddresses", "emails", "email_addresses"];
+/// Keys of the list of postal addresses.
+const ADDRESS_KEYS: &[&str] = &["postalAddresses", "addresses", "postal_addresses"];
+/// Keys of the list of web addresses.
+const URL_KEYS: &[&str] = &["urlAddresses", "urls", "websites"];
+/// Keys of the company, as text or a list of organization objects.
+const ORGANIZATION_KEYS: &[&str] = &["organizationName", "organizations", "company"];
+/// Keys of the name in an organization object.
+const ORGANIZATION_NAME_KEYS: &[&str] = &["name", "company", "value"];
+/// Keys of the value of a phone number, email, address or URL entry.
+const VALUE_KEYS: &[&str] = &["value", "number", "address", "formattedValue", "url"];
+/// Keys of the label of a phone number or email entry, e.g. "work".
+const LABEL_KEYS: &[&str] = &["label", "type", "formattedType"];
+/// Keys of the parts of a postal address without a formatted value, in the order they are joined.
+const ADDRESS_PART_KEYS: &[&str] = &[
+    "street",
+    "streetAddress",
+    "city",
+    "region",
+    "state",
+    "postalCode",
+    "country",
+];
+
+/// Define a contact flattened from a phone's contacts export, with the details our contacts
+/// don't have a column for.
+pub(crate) struct DeviceContact {
+    /// The contact, with the first phone number, email, address, website and company of the entry.
+    pub(crate) contact: Contact,
+    /// The other emails of the entry, as `(label, address)` pairs.
+    pub(crate) emails: Vec<(String, String)>,
+    /// How many other phone numbers the entry had, which contacts have no room for.
+    pub(crate) dropped_phones: usize,
+}
+
+/// Looks up the first of several keys present in a JSON object.
+///
+/// # Parameters
+///
+/// * `value` - The JSON object.
+/// * `keys` - The keys to try, in order.
+///
+/// # Returns
+///
+/// The value of the first key present, or `None`.
+fn lookup<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter().find_map(|&key| value.get(key))
+}
+
+/// Reads a text value, or the value of an entry object such as `{"label": "work", "value": ...}`.
+///
+/// # Parameters
+///
+/// * `value` - A JSON string or entry object.
+///
+/// # Returns
+///
+/// The trimmed text, empty if there is none.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.trim().to_string(),
+        Value::Object(_) => lookup(value, VALUE_KEYS).map(text).unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Reads the label of an entry object, without the `_$!<...>!$_` wrapping iOS puts around its
+/// built-in labels.
+///
+/// # Parameters
+///
+/// * `entry` - The entry object.
+///
+/// # Returns
+///
+/// The label in lowercase, e.g. "work", or "other" if the entry has none.
+fn label(entry: &Value) -> String {
+    let label = lookup(entry, LABEL_KEYS)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .trim_start_matches("_$!<")
+        .trim_end_matches(">!$_")
+        .trim()
+        .to_lowercase();
+    if label.is_empty() {
+        "other".to_string()
+    } else {
+        label
+    }
+}
+
+/// Reads a list of entries, accepting a single entry in place of a list.
+///
+/// # Parameters
+///
+/// * `value` - The contact object.
+/// * `keys` - The keys of the list.
+///
+/// # Returns
+///
+/// The entries, empty if the contact has none.
+fn entries<'a>(value: &'a Value, keys: &[&str]) -> Vec<&'a Value> {
+    match lookup(value, keys) {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(Value::Null) | None => Vec::new(),
+        Some(item) => vec![item],
+    }
+}
+
+/// Formats a postal address entry, joining its parts if it has no formatted value.
+///
+/// # Parameters
+///
+/// * `entry` - The address string or object.
+///
+/// # Returns
+///
+/// The address on one line.
+fn address(entry: &Value) -> String {
+    let formatted = text(entry);
+    if !formatted.is_empty() {
+        return formatted.replace('\n', ", ");
+    }
+    ADDRESS_PART_KEYS
+        .iter()
+        .filter_map(|&key| entry.get(key).and_then(Value::as_str))
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+/// Reads the given and family names of a contact from its name object, a list of name objects,
+/// or fields of the contact itself. A whole name is split with `split_full_name`.
+///
+/// # Parameters
+///
+/// * `entry` - The contact object.
+///
+/// # Returns
+///
+/// The first and last names, either possibly empty.
+fn names(entry: &Value) -> (String, String) {
+    let name = match lookup(entry, NAME_KEYS) {
+        Some(Value::Array(names)) => names.first().unwrap_or(entry),
+        Some(name @ Value::Object(_)) => name,
+        // A plain name string is a whole name
+        Some(name @ Value::String(_)) => return split_full_name(&text(name)),
+        _ => entry,
+    };
+    let first = lookup(name, GIVEN_NAME_KEYS).map(text).unwrap_or_default();
+    let last = lookup(name, FAMILY_NAME_KEYS).map(text).unwrap_or_default();
+    if !first.is_empty() || !last.is_empty() {
+        return (first, last);
+    }
+    let full = lookup(name, DISPLAY_NAME_KEYS)
+        .or_else(|| lookup(entry, DISPLAY_NAME_KEYS))
+        .map(text)
+        .unwrap_or_default();
+    split_full_name(&full)
+}
+
+/// Checks whether a JSON value is shaped like a contact of a phone's export: it has a structured
+/// name or a list of phone numbers or emails, rather than the flat fields of our own contacts.
+///
+/// # Parameters
+///
+/// * `entry` - The JSON value.
+///
+/// # Returns
+///
+/// `true` if the value looks like an exported phone contact.
+fn is_device_contact(entry: &Value) -> bool {
+    entry.is_object()
+        && (matches!(
+            lookup(entry, NAME_KEYS),
+            Some(Value::Object(_) | Value::Array(_))
+        ) || matches!(lookup(entry, PHONE_KEYS), Some(Value::Array(_)))
+            || matches!(lookup(entry, EMAIL_KEYS), Some(Value::Array(_))))
+}
+
+/// Flattens one contact of a phone's export: the first phone number, email, address and web
+/// address go to the contact's columns, the other emails become additional emails.
+///
+/// # Parameters
+///
+/// * `entry` - The contact object.
+///
+/// # Returns
+///
+/// The flattened contact.
+pub(crate) fn flatten_device_contact(entry: &Value) -> DeviceContact {
+    let (first_name, last_name) = names(entry);
+    let phones: Vec<String> = entries(entry, PHONE_KEYS)
+        .into_iter()
+        .map(text)
+        .filter(|phone| !phone.is_empty())
+        .collect();
+    let mut emails: Vec<(String, String)> = entries(entry, EMAIL_KEYS)
+        .into_iter()
+        .map(|email| (label(email), text(email)))
+        .filter(|(_, address)| is_valid_email(address))
+        .collect();
+    let email = if emails.is_empty() {
+        String::new()
+    } else {
+        emails.remove(0).1
+    };
+    let address = entries(entry, ADDRESS_KEYS)
+        .into_iter()
+        .map(address)
+        .find(|address| !address.is_empty())
+        .unwrap_or_default();
+    let website = entries(entry, URL_KEYS)
+        .into_iter()
+        .map(text)
+        .find(|url| !url.is_empty())
+        .unwrap_or_default();
+    let company = entries(entry, ORGANIZATION_KEYS)
+        .into_iter()
+        .map(|organization| match organization {
+            Value::Object(_) => lookup(organization, ORGANIZATION_NAME_KEYS)
+                .map(text)
+                .unwrap_or_default(),
+            _ => text(organization),
+        })
+        .find(|company| !company.is_empty())
+        .unwrap_or_default();
+    let mut contact = Contact::new(
+        first_name,
+        last_name,
+        email,
+        address,
+        phones.first().cloned().unwrap_or_default(),
+    );
+    contact.website = sanitize_text(&website);
+    contact.company = sanitize_text(&company);
+    DeviceContact {
+        contact,
+        emails,
+        dropped_phones: phones.len().saturating_sub(1),
+    }
+}
+
+/// Parses a phone's contacts export: a list of contacts, possibly nested in an object under a
+/// key such as `contacts`, with structured names and lists of phone numbers and emails.
+///
+/// # Parameters
+///
+/// * `json` - The content of the export.
+///
+/// # Returns
+///
+/// The flattened contacts, or an error message if the JSON is invalid or isn't shaped like a
+/// phone's export.
+pub(crate) fn parse_device_export(json: &str) -> Result<Vec<DeviceContact>, String> {
+    let value: Value =
+        serde_json::from_str(json).map_err(|err| format!("Invalid JSON: {}", err))?;
+    let items = match &value {
+        Value::Array(items) => items,
+        Value::Object(_) => match lookup(&value, LIST_KEYS) {
+            Some(Value::Array(items)) => items,
+            _ => return Err("No list of contacts found in the file.".to_string()),
+        },
+        _ => return Err("No list of contacts found in the file.".to_string()),
+    };
+    if !items.iter().any(is_device_contact) {
+        return Err(
+            "The file doesn't look like a phone contacts export (no structured names or lists of \
+             phone numbers)."
+                .to_string(),
+        );
+    }
+    Ok(items
+        .iter()
+        .filter(|item| is_device_contact(item))
+        .map(flatten_device_contact)
+        .collect())
+}
+
+impl PhoneBook {
+    /// Imports the contacts exported from an Android or iOS phone as JSON.
+    ///
+    /// Phone exports nest the name in an object and hold lists of phone numbers and emails. Each
+    /// contact is flattened: its first phone number, email, address and web address fill the
+    /// contact's fields, and its other emails are kept as additional emails. Other phone numbers
+    /// are counted and reported, as contacts hold a single one. The rules of the `IMPORT_RULES`
+    /// file are then applied to each contact.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn import_device_contacts(&mut self) {
+        Self::print_cancel_hint();
+        let Ok(file_name) =
+            Self::get_input_or_cancel("Enter the name of the JSON file exported from the phone: ")
+        else {
+            println!("Import cancelled.");
+            return;
+        };
+        let file_name = match Self::expand_path(&file_name) {
+            Ok(file_name) => file_name,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let json = match fs::read_to_string(&file_name) {
+            Ok(json) => json,
+            Err(err) => {
+                println!("Error opening file: {}", err);
+                return;
+            }
+        };
+        let parsed = match parse_device_export(&json) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let rules = match ImportRule::load_configured() {
+            Ok(rules) => rules,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let mut device_contacts = Vec::with_capacity(parsed.len());
+        for (index, mut device_contact) in parsed.into_iter().enumerate() {
+            apply_import_rules(&rules, &mut device_contact.contact);
+            device_contact.contact.clear_placeholders();
+            match device_contact.contact.validate() {
+                Ok(()) => device_contacts.push(device_contact),
+                Err(err) => println!("Skipping contact {}: {}", index + 1, err),
+            }
+        }
+        if device_contacts.is_empty() {
+            println!("No contacts found in file '{}'.", file_name);
+            return;
+        }
+        let contacts: Vec<Contact> = device_contacts
+            .iter()
+            .map(|device_contact| device_contact.contact.clone())
+            .collect();
+        self.print_import_preview(&contacts, contacts.len());
+        let dropped_phones: usize = device_contacts
+            .iter()
+            .map(|device_contact| device_contact.dropped_phones)
+            .sum();
+        if dropped_phones > 0 {
+            println!(
+                "{} additional phone number(s) will not be imported, as contacts hold one.",
+                dropped_phones
+            );
+        }
+        let confirm = Self::get_input(&format!(
+            "Import {} contacts? (y/n): ",
+            device_contacts.len()
+        ));
+        if confirm.to_lowercase() != "y" {
+            println!("Import cancelled.");
+            return;
+        }
+        // Ctrl-C waits for the import to finish, so it is never left half done
+        let _busy = BusyGuard::enter();
+        let progress = Self::import_progress_bar(device_contacts.len());
+        for device_contact in device_contacts {
+            if let Err(err) = Self::insert_contact_with(
+                &device_contact.contact,
+                &device_contact.emails,
+                &[],
+                "import",
+            ) {
+                println!("Error saving new contact: {}", err);
+            }
+            progress.inc(1);
+        }
+        progress.finish_and_clear();
+        println!("Contacts loaded successfully from file '{}'.", file_name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::open_book;
+
+    #[test]
+    fn nested_phone_export_is_flattened() {
+        let _book = open_book();
+        let json = r#"{
+            "contacts": [
+                {
+                    "name": {"givenName": "John", "familyName": "Doe"},
+                    "phoneNumbers": [
+                        {"label": "_$!<Mobile>!$_", "value": "555-123-4567"},
+                        {"label": "work", "value": "555-987-6543"}
+                    ],
+                    "emailAddresses": [
+                        {"label": "home", "value": "john@example.com"},
+                        {"label": "_$!<Work>!$_", "value": "jdoe@work.example"}
+                    ],
+                    "postalAddresses": [
+                        {"street": "1 Main St", "city": "Springfield", "postalCode": "12345"}
+                    ],
+                    "urlAddresses": [{"value": "https://example.com"}],
+                    "organizations": [{"name": "Acme"}]
+                },
+                {"displayName": "Jane Roe", "phones": ["5550001111"]}
+            ]
+        }"#;
+        let contacts = parse_device_export(json).unwrap();
+        assert_eq!(contacts.len(), 2);
+
+        let john = &contacts[0];
+        assert_eq!(john.contact.first_name, "John");
+        assert_eq!(john.contact.last_name, "Doe");
+        assert_eq!(john.contact.phone, "(555) 123-4567");
+        assert_eq!(john.contact.email, "john@example.com");
+        assert_eq!(john.contact.address, "1 Main St, Springfield, 12345");
+        assert_eq!(john.contact.website, "https://example.com");
+        assert_eq!(john.contact.company, "Acme");
+        assert_eq!(
+            john.emails,
+            [("work".to_string(), "jdoe@work.example".to_string())]
+        );
+        assert_eq!(john.dropped_phones, 1);
+
+        let jane = &contacts[1];
+        assert_eq!(jane.contact.first_name, "Jane");
+        assert_eq!(jane.contact.last_name, "Roe");
+        assert_eq!(jane.contact.phone, "(555) 000-1111");
+        assert_eq!(jane.dropped_phones, 0);
+    }
+
+    #[test]
+    fn flat_contacts_are_not_taken_for_a_phone_export() {
+        let json = r#"[{"first_name": "John", "last_name": "Doe", "phone": "5551234567"}]"#;
+        assert!(parse_device_export(json).is_err());
+    }
+}
@@ -0,0 +1,892 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+use arboard::Clipboard;
+use chrono::Local;
+use csv::{QuoteStyle, WriterBuilder};
+use diesel::prelude::*;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::connection::establish_connection;
+use crate::phone_book::contact::Contact;
+use crate::phone_book::phone_book::PhoneBook;
+use crate::schema::{contacts, tags};
+use crate::settings;
+
+/// Name of the file holding the contacts without any tag in exports grouped by tag.
+const UNTAGGED_FILE_NAME: &str = "untagged";
+
+/// The contacts of each tag, keyed by tag in alphabetical order.
+type TagGroups = BTreeMap<String, Vec<Contact>>;
+
+/// Heading of the section listing contacts whose last name doesn't start with a letter, in
+/// alphabetized directories.
+const OTHER_SECTION: &str = "#";
+
+/// Column headers of the two-column printable list.
+const PRINTABLE_HEADER: [&str; 2] = ["Name", "Number"];
+
+/// Extension of export files written gzip-compressed, e.g. `contacts.csv.gz`.
+const GZIP_EXTENSION: &str = ".gz";
+
+impl PhoneBook {
+    /// Serializes contacts as CSV text, with a header row of the field names.
+    ///
+    /// The header uses the same column names the CSV import expects, so the text can be imported
+    /// again as-is. The delimiter and quoting follow the `CSV_DELIMITER` and `CSV_QUOTING`
+    /// settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `contacts` - The contacts to serialize.
+    /// * `columns` - The fields to write, in order, e.g. from the `EXPORT_COLUMNS` setting.
+    ///
+    /// # Returns
+    ///
+    /// The CSV text, or the error raised while writing it.
+    pub(crate) fn contacts_to_csv(
+        contacts: &[Contact],
+        columns: &[String],
+    ) -> Result<String, csv::Error> {
+        let mut writer = WriterBuilder::new()
+            .delimiter(settings::csv_delimiter().unwrap_or(b','))
+            .quote_style(settings::csv_quoting().unwrap_or(QuoteStyle::Necessary))
+            .from_writer(Vec::new());
+        writer.write_record(columns)?;
+        for contact in contacts {
+            writer.write_record(columns.iter().map(|field| contact.field(field)))?;
+        }
+        let bytes = writer.into_inner().map_err(|err| err.into_error())?;
+        Ok(String::from_utf8(bytes).expect("CSV is written from UTF-8 strings"))
+    }
+    /// Copies text to the system clipboard, printing it instead if no clipboard is available
+    /// (e.g. over SSH or without a display server).
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to copy.
+    /// * `description` - What the text is, used in the confirmation message.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It reports the outcome to the console.
+    pub(crate) fn copy_to_clipboard(text: &str, description: &str) {
+        match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+            Ok(()) => println!("Copied {} to the clipboard.", description),
+            Err(err) => {
+                println!(
+                    "Clipboard unavailable ({}). Here is the {}:",
+                    err, description
+                );
+                println!("{}", text);
+            }
+        }
+    }
+    /// Offers to copy the given search results to the clipboard as CSV.
+    ///
+    /// # Arguments
+    ///
+    /// * `contacts` - The contacts matching the search.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value.
+    pub(crate) fn offer_csv_copy(contacts: &[Contact]) {
+        let confirm =
+            Self::get_input("Copy the matching contacts to the clipboard as CSV? (y/n): ");
+        if confirm.to_lowercase() != "y" {
+            return;
+        }
+        let columns = match settings::export_columns() {
+            Ok(columns) => columns,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        match Self::contacts_to_csv(contacts, &columns) {
+            Ok(csv) => Self::copy_to_clipboard(&csv, "CSV"),
+            Err(err) => println!("Error generating CSV: {}", err),
+        }
+    }
+    /// Copies the name and phone number of the contacts matching a search to the clipboard, one
+    /// contact per line, e.g. to paste a few numbers into a message. Without a clipboard, the
+    /// list is printed instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn copy_names_and_phones(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        let query = Self::get_input("Enter a search query (leave empty for every contact): ");
+        let found_contacts = if query.is_empty() {
+            Self::get_contacts("asc")
+        } else {
+            Self::search_contacts(&query)
+        };
+        let found_contacts = match found_contacts {
+            Ok(found_contacts) => found_contacts,
+            Err(err) => {
+                println!("Error searching for contacts: {}", err);
+                return;
+            }
+        };
+        if found_contacts.is_empty() {
+            println!("No contacts found matching the search query.");
+            return;
+        }
+        let description = format!(
+            "names and phone numbers of {} contact(s)",
+            found_contacts.len()
+        );
+        Self::copy_to_clipboard(&Self::names_and_phones(&found_contacts), &description);
+    }
+    /// Formats contacts as one `name: phone` line each, with the phone extension if any.
+    /// Contacts without a phone number are listed by name only.
+    ///
+    /// # Arguments
+    ///
+    /// * `contacts` - The contacts to list.
+    ///
+    /// # Returns
+    ///
+    /// The lines, e.g. `John Doe: (555) 123-4567 x12`.
+    pub(crate) fn names_and_phones(contacts: &[Contact]) -> String {
+        contacts
+            .iter()
+            .map(|contact| {
+                let name = format!("{} {}", contact.first_name, contact.last_name);
+                if contact.phone.is_empty() {
+                    name.trim().to_string()
+                } else {
+                    format!("{}: {}", name.trim(), contact.full_phone())
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+    /// Exports every contact to a CSV file, with the columns of the `EXPORT_COLUMNS` setting. A
+    /// file name ending in `.gz`, e.g. `contacts.csv.gz`, is written gzip-compressed.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn export_csv(&mut self) {
+        let columns = match settings::export_columns() {
+            Ok(columns) => columns,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        Self::print_cancel_hint();
+        let Ok(file_name) = Self::get_input_or_cancel(
+            "Enter the name of the CSV file to export contacts to (.gz to compress): ",
+        ) else {
+            println!("Export cancelled.");
+            return;
+        };
+        let file_name = match Self::expand_path(&file_name) {
+            Ok(file_name) => file_name,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let contacts = match Self::get_contacts("") {
+            Ok(contacts) => contacts,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        let csv = match Self::contacts_to_csv(&contacts, &columns) {
+            Ok(csv) => csv,
+            Err(err) => {
+                println!("Error generating CSV: {}", err);
+                return;
+            }
+        };
+        match write_export(&file_name, &csv) {
+            Ok(()) => println!("Exported {} contact(s) to {}.", contacts.len(), file_name),
+            Err(err) => println!("Error writing file: {}", err),
+        }
+    }
+    /// Exports the contacts into one file per tag, as CSV or vCard, so each group can be shared
+    /// on its own.
+    ///
+    /// A contact with several tags appears in the file of each of them, and contacts without tags
+    /// go to an "untagged" file.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn export_by_tag(&mut self) {
+        let columns = match settings::export_columns() {
+            Ok(columns) => columns,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        Self::print_cancel_hint();
+        let vcard = match Self::get_input_or_cancel("Export as (1) CSV or (2) vCard: ").as_deref() {
+            Ok("1") => false,
+            Ok("2") => true,
+            Ok(_) => {
+                println!("Invalid choice!");
+                return;
+            }
+            Err(_) => {
+                println!("Export cancelled.");
+                return;
+            }
+        };
+        let Ok(directory) =
+            Self::get_input_or_cancel("Enter the directory to write the files to: ")
+        else {
+            println!("Export cancelled.");
+            return;
+        };
+        let directory = match Self::expand_path(&directory) {
+            Ok(directory) => directory,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let (groups, untagged) = match Self::group_by_tag() {
+            Ok(grouped) => grouped,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        let sender = if vcard {
+            let Some(sender) = Self::vcard_sender() else {
+                return;
+            };
+            sender
+        } else {
+            None
+        };
+        if let Err(err) = fs::create_dir_all(&directory) {
+            println!("Error creating directory: {}", err);
+            return;
+        }
+        let extension = if vcard { "vcf" } else { "csv" };
+        for (stem, contacts) in files_by_tag(&groups, &untagged) {
+            let contents = if vcard {
+                contacts
+                    .iter()
+                    .map(|contact| contact.to_vcard(sender.as_ref()))
+                    .collect()
+            } else {
+                match Self::contacts_to_csv(contacts, &columns) {
+                    Ok(csv) => csv,
+                    Err(err) => {
+                        println!("Error generating CSV: {}", err);
+                        return;
+                    }
+                }
+            };
+            let path = Path::new(&directory).join(format!("{}.{}", stem, extension));
+            match fs::write(&path, contents) {
+                Ok(()) => println!(
+                    "Exported {} contact(s) to {}.",
+                    contacts.len(),
+                    path.display()
+                ),
+                Err(err) => {
+                    println!("Error writing {}: {}", path.display(), err);
+                    return;
+                }
+            }
+        }
+    }
+    /// Exports every contact to a Markdown file laid out like a printed phone book: one section
+    /// per initial letter of the last name, with the contacts sorted by name under each.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn export_directory(&mut self) {
+        Self::print_cancel_hint();
+        let Ok(file_name) = Self::get_input_or_cancel(
+            "Enter the name of the Markdown file to export the directory to, e.g. contacts.md: ",
+        ) else {
+            println!("Export cancelled.");
+            return;
+        };
+        let file_name = match Self::expand_path(&file_name) {
+            Ok(file_name) => file_name,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let contacts = match Self::get_contacts("last_asc") {
+            Ok(contacts) => contacts,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        let sections = group_by_initial(&contacts);
+        match write_export(&file_name, directory_markdown(&sections)) {
+            Ok(()) => println!(
+                "Exported {} contact(s) in {} section(s) to {}.",
+                contacts.len(),
+                sections.len(),
+                file_name
+            ),
+            Err(err) => println!("Error writing file: {}", err),
+        }
+    }
+    /// Exports every contact as a two-column list of names and phone numbers, sorted
+    /// alphabetically, for printing: a Markdown table if the file name ends in `.md`, a PDF
+    /// document if it ends in `.pdf`, and plain text otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn export_printable_list(&mut self) {
+        Self::print_cancel_hint();
+        let Ok(file_name) = Self::get_input_or_cancel(
+            "Enter the name of the file to export the list to, e.g. contacts.txt, contacts.md or contacts.pdf: ",
+        ) else {
+            println!("Export cancelled.");
+            return;
+        };
+        let file_name = match Self::expand_path(&file_name) {
+            Ok(file_name) => file_name,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let contacts = match Self::get_contacts("") {
+            Ok(contacts) => contacts,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        let rows = printable_rows(&contacts);
+        let lowercase = file_name.to_lowercase();
+        let lowercase = lowercase.strip_suffix(GZIP_EXTENSION).unwrap_or(&lowercase);
+        let written = if lowercase.ends_with(".pdf") {
+            let date_format = settings::date_format()
+                .unwrap_or_else(|_| settings::DEFAULT_DATE_FORMAT.to_string());
+            let generated = Local::now().format(&date_format).to_string();
+            let cells: Vec<Vec<&str>> = rows
+                .iter()
+                .map(|row| row.iter().map(String::as_str).collect())
+                .collect();
+            match Self::table_to_pdf(PRINTABLE_HEADER.to_vec(), &cells, &generated) {
+                Ok(pdf) => write_export(&file_name, pdf),
+                Err(err) => {
+                    println!("Error generating PDF: {}", err);
+                    return;
+                }
+            }
+        } else if lowercase.ends_with(".md") {
+            write_export(&file_name, printable_markdown(&rows))
+        } else {
+            write_export(&file_name, printable_text(&rows))
+        };
+        match written {
+            Ok(()) => println!("Exported {} contact(s) to {}.", rows.len(), file_name),
+            Err(err) => println!("Error writing file: {}", err),
+        }
+    }
+    /// Groups every contact by tag.
+    ///
+    /// # Returns
+    ///
+    /// The contacts of each tag, keyed by tag in alphabetical order, and the contacts without any
+    /// tag. Contacts keep their creation order within a group.
+    pub(crate) fn group_by_tag() -> QueryResult<(TagGroups, Vec<Contact>)> {
+        let mut connection = establish_connection();
+        let contacts = contacts::table
+            .order(contacts::id.asc())
+            .load::<Contact>(&mut connection)?;
+        let mut tags_by_contact: HashMap<i32, Vec<String>> = HashMap::new();
+        for (contact_id, name) in tags::table
+            .select((tags::contact_id, tags::name))
+            .load::<(i32, String)>(&mut connection)?
+        {
+            tags_by_contact.entry(contact_id).or_default().push(name);
+        }
+        let mut groups = TagGroups::new();
+        let mut untagged = Vec::new();
+        for contact in contacts {
+            let mut contact_tags = contact
+                .id
+                .and_then(|contact_id| tags_by_contact.remove(&contact_id))
+                .unwrap_or_default();
+            contact_tags.sort();
+            contact_tags.dedup();
+            if contact_tags.is_empty() {
+                untagged.push(contact);
+                continue;
+            }
+            for tag in contact_tags {
+                groups.entry(tag).or_default().push(contact.clone());
+            }
+        }
+        Ok((groups, untagged))
+    }
+}
+
+/// Writes an export file, gzip-compressed if its name ends in `.gz`, so large phone books are
+/// smaller to share or back up.
+///
+/// # Arguments
+///
+/// * `file_name` - The path of the file.
+/// * `contents` - The text, or bytes such as a PDF document, to write.
+///
+/// # Returns
+///
+/// `Ok(())` if the file was written.
+pub(crate) fn write_export(file_name: &str, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    if !file_name.to_lowercase().ends_with(GZIP_EXTENSION) {
+        return fs::write(file_name, contents);
+    }
+    let mut encoder = GzEncoder::new(File::create(file_name)?, Compression::default());
+    encoder.write_all(contents.as_ref())?;
+    encoder.finish()?.sync_all()
+}
+
+/// Names the files of an export grouped by tag.
+///
+/// The untagged contacts always go to `UNTAGGED_FILE_NAME`. Tags whose file names would clash,
+/// such as `work/home` and `work_home`, or a tag named like the untagged file, get a numbered
+/// suffix, e.g. `work_home-2`. Names are compared ignoring case, for case-insensitive file
+/// systems.
+///
+/// # Arguments
+///
+/// * `groups` - The contacts of each tag, from `group_by_tag`.
+/// * `untagged` - The contacts without any tag.
+///
+/// # Returns
+///
+/// The file name, without extension, and contacts of each file: the untagged contacts first if
+/// there are any, then each tag in alphabetical order.
+fn files_by_tag<'a>(
+    groups: &'a TagGroups,
+    untagged: &'a [Contact],
+) -> Vec<(String, &'a [Contact])> {
+    let mut used = HashSet::from([UNTAGGED_FILE_NAME.to_lowercase()]);
+    let mut files = Vec::new();
+    if !untagged.is_empty() {
+        files.push((UNTAGGED_FILE_NAME.to_string(), untagged));
+    }
+    for (tag, contacts) in groups {
+        let base = file_stem(tag);
+        let mut stem = base.clone();
+        let mut suffix = 1;
+        while !used.insert(stem.to_lowercase()) {
+            suffix += 1;
+            stem = format!("{}-{}", base, suffix);
+        }
+        files.push((stem, contacts.as_slice()));
+    }
+    files
+}
+
+/// Turns a tag into a safe file name, replacing anything but letters, digits, `-` and `_`.
+///
+/// # Arguments
+///
+/// * `tag` - The tag.
+///
+/// # Returns
+///
+/// The file name, without extension.
+fn file_stem(tag: &str) -> String {
+    tag.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Groups contacts by the initial letter of their last name, or of their first name when they
+/// have no last name, for alphabetized directories.
+///
+/// # Arguments
+///
+/// * `contacts` - The contacts to group.
+///
+/// # Returns
+///
+/// The contacts of each uppercase letter, in alphabetical order, followed by those whose name
+/// doesn't start with a letter under `OTHER_SECTION`. Within a section, contacts are sorted by
+/// the same name and then first name, ignoring case.
+fn group_by_initial(contacts: &[Contact]) -> Vec<(String, Vec<&Contact>)> {
+    let mut sorted: Vec<&Contact> = contacts.iter().collect();
+    sorted.sort_by_key(|contact| {
+        (
+            directory_name(contact).to_lowercase(),
+            contact.first_name.to_lowercase(),
+        )
+    });
+    let mut letters: BTreeMap<String, Vec<&Contact>> = BTreeMap::new();
+    let mut others = Vec::new();
+    for contact in sorted {
+        match directory_name(contact)
+            .chars()
+            .next()
+            .filter(|c| c.is_alphabetic())
+        {
+            Some(initial) => letters
+                .entry(initial.to_uppercase().collect())
+                .or_default()
+                .push(contact),
+            None => others.push(contact),
+        }
+    }
+    let mut sections: Vec<(String, Vec<&Contact>)> = letters.into_iter().collect();
+    if !others.is_empty() {
+        sections.push((OTHER_SECTION.to_string(), others));
+    }
+    sections
+}
+
+/// Returns the name a contact is filed under in alphabetized directories.
+///
+/// # Arguments
+///
+/// * `contact` - The contact.
+///
+/// # Returns
+///
+/// The last name, or the first name when the contact has no last name.
+fn directory_name(contact: &Contact) -> &str {
+    if contact.last_name.is_empty() {
+        &contact.first_name
+    } else {
+        &contact.last_name
+    }
+}
+
+/// Returns the name a contact is listed as in alphabetized directories.
+///
+/// # Arguments
+///
+/// * `contact` - The contact.
+///
+/// # Returns
+///
+/// "Last, First", or whichever of the two names the contact has.
+fn directory_label(contact: &Contact) -> String {
+    match (contact.last_name.is_empty(), contact.first_name.is_empty()) {
+        (true, _) => contact.first_name.clone(),
+        (false, true) => contact.last_name.clone(),
+        (false, false) => format!("{}, {}", contact.last_name, contact.first_name),
+    }
+}
+
+/// Builds the rows of the two-column printable list: each contact's name and phone number,
+/// sorted alphabetically like a printed phone book.
+///
+/// # Arguments
+///
+/// * `contacts` - The contacts to list.
+///
+/// # Returns
+///
+/// The name and number of each contact, sorted by last name, then first name, ignoring case.
+fn printable_rows(contacts: &[Contact]) -> Vec<[String; 2]> {
+    let mut sorted: Vec<&Contact> = contacts.iter().collect();
+    sorted.sort_by_key(|contact| {
+        (
+            directory_name(contact).to_lowercase(),
+            contact.first_name.to_lowercase(),
+        )
+    });
+    sorted
+        .into_iter()
+        .map(|contact| [directory_label(contact), contact.full_phone()])
+        .collect()
+}
+
+/// Renders the printable list as plain text, the numbers aligned in a column after the names.
+///
+/// # Arguments
+///
+/// * `rows` - The name and number of each contact, e.g. from `printable_rows`.
+///
+/// # Returns
+///
+/// The text, with a header line.
+fn printable_text(rows: &[[String; 2]]) -> String {
+    let width = rows
+        .iter()
+        .map(|[name, _]| name.chars().count())
+        .chain([PRINTABLE_HEADER[0].len()])
+        .max()
+        .unwrap_or_default();
+    let mut text = String::new();
+    for [name, number] in
+        std::iter::once(PRINTABLE_HEADER.map(String::from)).chain(rows.iter().cloned())
+    {
+        let line = format!("{:<width$}  {}", name, number, width = width);
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// Renders the printable list as a Markdown table.
+///
+/// # Arguments
+///
+/// * `rows` - The name and number of each contact, e.g. from `printable_rows`.
+///
+/// # Returns
+///
+/// The Markdown text.
+fn printable_markdown(rows: &[[String; 2]]) -> String {
+    let mut markdown = format!(
+        "# Phone Book\n\n| {} | {} |\n| --- | --- |\n",
+        PRINTABLE_HEADER[0], PRINTABLE_HEADER[1]
+    );
+    for [name, number] in rows {
+        // A pipe would end the cell early
+        markdown.push_str(&format!(
+            "| {} | {} |\n",
+            name.replace('|', "\\|"),
+            number.replace('|', "\\|")
+        ));
+    }
+    markdown
+}
+
+/// Renders grouped contacts as a Markdown directory, with a heading per section and a line per
+/// contact giving their name, phone number and email.
+///
+/// # Arguments
+///
+/// * `sections` - The contacts of each section, e.g. from `group_by_initial`.
+///
+/// # Returns
+///
+/// The Markdown text.
+fn directory_markdown(sections: &[(String, Vec<&Contact>)]) -> String {
+    let mut markdown = String::from("# Phone Book\n");
+    for (heading, contacts) in sections {
+        markdown.push_str(&format!("\n## {}\n\n", heading));
+        for contact in contacts {
+            let mut line = format!(
+                "- **{}**: {}",
+                directory_label(contact),
+                contact.full_phone()
+            );
+            if !contact.email.is_empty() {
+                line.push_str(&format!(", {}", contact.email));
+            }
+            markdown.push_str(&line);
+            markdown.push('\n');
+        }
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+    use crate::test_support::{contact, open_book, set, temp_path};
+
+    #[test]
+    fn only_the_configured_columns_are_exported_in_order() {
+        let _book = open_book();
+        set("EXPORT_COLUMNS", "first_name, phone");
+        let columns = settings::export_columns().unwrap();
+        let contacts = [
+            contact("John", "Doe", "5551234567", "john@example.com"),
+            contact("Jane", "Roe", "5559876543", "jane@example.com"),
+        ];
+        let csv = PhoneBook::contacts_to_csv(&contacts, &columns).unwrap();
+        assert_eq!(
+            csv,
+            "first_name,phone\nJohn,(555) 123-4567\nJane,(555) 987-6543\n"
+        );
+
+        set("EXPORT_COLUMNS", "first_name,nickname");
+        assert!(settings::export_columns().is_err());
+    }
+
+    #[test]
+    fn clashing_tag_file_names_get_a_suffix() {
+        let _book = open_book();
+        let john = contact("John", "Doe", "5551234567", "");
+        let jane = contact("Jane", "Roe", "5559876543", "");
+        let groups = BTreeMap::from([
+            ("untagged".to_string(), vec![john.clone()]),
+            ("work/home".to_string(), vec![john.clone()]),
+            ("work_home".to_string(), vec![jane.clone()]),
+        ]);
+        let untagged = [jane];
+        let stems: Vec<(String, usize)> = files_by_tag(&groups, &untagged)
+            .into_iter()
+            .map(|(stem, contacts)| (stem, contacts.len()))
+            .collect();
+        assert_eq!(
+            stems,
+            [
+                ("untagged".to_string(), 1),
+                ("untagged-2".to_string(), 1),
+                ("work_home".to_string(), 1),
+                ("work_home-2".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn semicolon_delimiter_is_used_in_exports() {
+        let _book = open_book();
+        set("CSV_DELIMITER", "semicolon");
+        let columns = ["first_name".to_string(), "address".to_string()];
+        let mut john = contact("John", "Doe", "5551234567", "");
+        john.set_field("address", "1 Main St; Apt 2".to_string());
+        let csv = PhoneBook::contacts_to_csv(&[john], &columns).unwrap();
+        assert_eq!(csv, "first_name;address\nJohn;\"1 Main St; Apt 2\"\n");
+    }
+
+    #[test]
+    fn csv_gz_export_reads_back_through_a_gzip_decoder() {
+        let _book = open_book();
+        let columns = settings::export_columns().unwrap();
+        let contacts = [
+            contact("John", "Doe", "5551234567", "john@example.com"),
+            contact("Jane", "Roe", "5559876543", "jane@example.com"),
+        ];
+        let csv = PhoneBook::contacts_to_csv(&contacts, &columns).unwrap();
+        let path = temp_path("csv.gz");
+        let file_name = path.to_str().unwrap();
+        write_export(file_name, &csv).unwrap();
+
+        let mut decompressed = String::new();
+        GzDecoder::new(File::open(&path).unwrap())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(decompressed, csv);
+        assert_eq!(decompressed.lines().count(), 3);
+        assert!(decompressed.contains("jane@example.com"));
+    }
+
+    #[test]
+    fn directory_has_a_section_per_initial_in_order() {
+        let _book = open_book();
+        let contacts = [
+            contact("Cid", "Clark", "5550000003", ""),
+            contact("Ann", "adams", "5550000001", "ann@example.com"),
+            contact("Bob", "Brown", "5550000002", ""),
+            contact("Al", "Allen", "5550000004", ""),
+            contact("Cher", "", "5550000005", ""),
+            contact("Zed", "007", "5550000006", ""),
+        ];
+        let markdown = directory_markdown(&group_by_initial(&contacts));
+        assert_eq!(
+            markdown,
+            "# Phone Book\n\
+             \n## A\n\n\
+             - **adams, Ann**: (555) 000-0001, ann@example.com\n\
+             - **Allen, Al**: (555) 000-0004\n\
+             \n## B\n\n\
+             - **Brown, Bob**: (555) 000-0002\n\
+             \n## C\n\n\
+             - **Cher**: (555) 000-0005\n\
+             - **Clark, Cid**: (555) 000-0003\n\
+             \n## #\n\n\
+             - **007, Zed**: (555) 000-0006\n"
+        );
+    }
+
+    #[test]
+    fn printable_list_has_only_names_and_numbers_in_sorted_order() {
+        let _book = open_book();
+        let mut bob = contact("Bob", "Brown", "5550000002", "bob@example.com");
+        bob.set_field("address", "1 Main St".to_string());
+        let contacts = [
+            bob,
+            contact("Ann", "adams", "5550000001", "ann@example.com"),
+            contact("Cher", "", "5550000003", ""),
+        ];
+        let rows = printable_rows(&contacts);
+        assert_eq!(
+            printable_text(&rows),
+            "Name        Number\n\
+             adams, Ann  (555) 000-0001\n\
+             Brown, Bob  (555) 000-0002\n\
+             Cher        (555) 000-0003\n"
+        );
+        assert_eq!(
+            printable_markdown(&rows),
+            "# Phone Book\n\n\
+             | Name | Number |\n\
+             | --- | --- |\n\
+             | adams, Ann | (555) 000-0001 |\n\
+             | Brown, Bob | (555) 000-0002 |\n\
+             | Cher | (555) 000-0003 |\n"
+        );
+    }
+
+    #[test]
+    fn pdf_gz_export_is_compressed() {
+        let _book = open_book();
+        let pdf = PhoneBook::table_to_pdf(
+            PRINTABLE_HEADER.to_vec(),
+            &[vec!["Doe, John", "(555) 123-4567"]],
+            "2024-01-01",
+        )
+        .unwrap();
+        let path = temp_path("pdf.gz");
+        write_export(path.to_str().unwrap(), &pdf).unwrap();
+
+        let mut decompressed = Vec::new();
+        GzDecoder::new(File::open(&path).unwrap())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(decompressed, pdf);
+    }
+}
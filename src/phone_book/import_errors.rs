@@ -0,0 +1,118 @@
+use std::fs::File;
+use std::path::Path;
+
+use csv::{StringRecord, Writer, WriterBuilder};
+
+/// Columns written before the columns of the imported file: the line of the row in that file
+/// and why it was skipped. Import ignores them, so the file can be imported again once fixed.
+const ERROR_COLUMNS: [&str; 2] = ["line", "reason"];
+
+/// Define the file the rows skipped by a CSV import are written to, with the reason each was
+/// skipped, so the user can fix them and import only those again.
+///
+/// The file is named after the imported file, e.g. `contacts.errors.csv` for `contacts.csv`,
+/// and only created once a row is skipped.
+pub(crate) struct ImportErrors {
+    /// Path of the file.
+    path: String,
+    /// The header row of the imported file.
+    header: StringRecord,
+    /// The writer, opened with the first skipped row.
+    writer: Option<Writer<File>>,
+    /// The first error raised while writing, reported by `finish`.
+    error: Option<csv::Error>,
+    /// Number of rows skipped.
+    count: usize,
+}
+
+impl ImportErrors {
+    /// Prepares the file of skipped rows for an import.
+    ///
+    /// # Parameters
+    ///
+    /// * `file_name` - The path of the imported file.
+    /// * `header` - The header row of the imported file.
+    ///
+    /// # Returns
+    ///
+    /// The file, not created yet.
+    pub(crate) fn new(file_name: &str, header: StringRecord) -> Self {
+        Self {
+            path: Path::new(file_name)
+                .with_extension("errors.csv")
+                .to_string_lossy()
+                .into_owned(),
+            header,
+            writer: None,
+            error: None,
+            count: 0,
+        }
+    }
+    /// Writes a skipped row with the reason it was skipped.
+    ///
+    /// # Parameters
+    ///
+    /// * `line` - The line of the row in the imported file.
+    /// * `record` - The fields of the row, empty if the row couldn't be read.
+    /// * `reason` - Why the row was skipped.
+    pub(crate) fn add(&mut self, line: u64, record: &StringRecord, reason: &str) {
+        self.count += 1;
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(err) = self.write(line, record, reason) {
+            self.error = Some(err);
+        }
+    }
+    /// Writes a row, creating the file and writing its header first if needed.
+    ///
+    /// # Parameters
+    ///
+    /// * `line` - The line of the row in the imported file.
+    /// * `record` - The fields of the row.
+    /// * `reason` - Why the row was skipped.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the row was written.
+    fn write(&mut self, line: u64, record: &StringRecord, reason: &str) -> csv::Result<()> {
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => {
+                // Rows may have a different number of fields than the header
+                let mut writer = WriterBuilder::new().flexible(true).from_path(&self.path)?;
+                writer.write_record(ERROR_COLUMNS.iter().copied().chain(&self.header))?;
+                self.writer.insert(writer)
+            }
+        };
+        // Short rows are padded, so the missing fields can be filled in
+        let missing = self.header.len().saturating_sub(record.len());
+        let line = line.to_string();
+        writer.write_record(
+            [line.as_str(), reason]
+                .into_iter()
+                .chain(record)
+                .chain(std::iter::repeat_n("", missing)),
+        )
+    }
+    /// Returns how many rows were skipped.
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+    /// Finishes writing the file.
+    ///
+    /// # Returns
+    ///
+    /// The path of the file, or an error message if it couldn't be written.
+    pub(crate) fn finish(self) -> Result<String, String> {
+        if let Some(err) = self.error {
+            return Err(format!("Error writing '{}': {}", self.path, err));
+        }
+        if let Some(mut writer) = self.writer {
+            writer
+                .flush()
+                .map_err(|err| format!("Error writing '{}': {}", self.path, err))?;
+        }
+        Ok(self.path)
+    }
+}
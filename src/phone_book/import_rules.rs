@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::phone_book::contact::{Contact, FIELDS};
+use crate::settings;
+
+/// Define a transformation applied to a field of imported contacts.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Transform {
+    /// Converts the value to uppercase, e.g. for state codes.
+    Uppercase,
+    /// Converts the value to lowercase, e.g. for emails.
+    Lowercase,
+    /// Removes a prefix from values starting with it, e.g. a dialing prefix from phone numbers.
+    StripPrefix { prefix: String },
+    /// Replaces every occurrence of a text.
+    Replace { from: String, to: String },
+    /// Replaces whole values found in a table, e.g. the labels of the source system with ours.
+    /// Values missing from the table are kept.
+    Map { values: HashMap<String, String> },
+}
+
+/// Define a rule of the `IMPORT_RULES` file: a transformation and the contact field it applies
+/// to, e.g. `{"field": "address", "action": "uppercase"}`.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ImportRule {
+    /// The column name of the field, as listed in `FIELDS`.
+    pub field: String,
+    /// The transformation.
+    #[serde(flatten)]
+    pub transform: Transform,
+}
+
+impl ImportRule {
+    /// Parses import rules from JSON: an array of rules, applied in order.
+    ///
+    /// # Parameters
+    ///
+    /// * `json`: The rules, e.g.
+    ///   `[{"field": "phone", "action": "strip_prefix", "prefix": "+1"}]`.
+    ///
+    /// # Returns
+    ///
+    /// The rules, or an error message if the JSON is invalid or names an unknown field.
+    pub fn parse(json: &str) -> Result<Vec<Self>, String> {
+        let rules: Vec<Self> =
+            serde_json::from_str(json).map_err(|err| format!("Invalid import rules: {}", err))?;
+        if let Some(rule) = rules
+            .iter()
+            .find(|rule| !FIELDS.iter().any(|&(field, _)| field == rule.field))
+        {
+            let fields: Vec<&str> = FIELDS.iter().map(|&(field, _)| field).collect();
+            return Err(format!(
+                "Invalid import rules: unknown field '{}' (expected one of {}).",
+                rule.field,
+                fields.join(", ")
+            ));
+        }
+        Ok(rules)
+    }
+    /// Loads the rules of the file named by the `IMPORT_RULES` setting.
+    ///
+    /// # Returns
+    ///
+    /// The rules, none if the setting is unset, or an error message if the file can't be read or
+    /// parsed.
+    pub fn load_configured() -> Result<Vec<Self>, String> {
+        let Some(path) = settings::import_rules_file() else {
+            return Ok(Vec::new());
+        };
+        let json = fs::read_to_string(&path)
+            .map_err(|err| format!("Error reading import rules file '{}': {}", path, err))?;
+        Self::parse(&json)
+    }
+    /// Applies the rule to a contact.
+    ///
+    /// # Parameters
+    ///
+    /// * `contact`: The imported contact.
+    pub fn apply(&self, contact: &mut Contact) {
+        let value = contact.field(&self.field);
+        let transformed = match &self.transform {
+            Transform::Uppercase => value.to_uppercase(),
+            Transform::Lowercase => value.to_lowercase(),
+            Transform::StripPrefix { prefix } => value
+                .strip_prefix(prefix.as_str())
+                .unwrap_or(value)
+                .to_string(),
+            Transform::Replace { from, to } if !from.is_empty() => value.replace(from, to),
+            Transform::Replace { .. } => value.to_string(),
+            Transform::Map { values } => {
+                values.get(value).map_or(value, String::as_str).to_string()
+            }
+        };
+        contact.set_field(&self.field, transformed);
+    }
+}
+
+/// Applies import rules to a contact, in order.
+///
+/// # Parameters
+///
+/// * `rules`: The rules, e.g. from `ImportRule::load_configured`.
+/// * `contact`: The imported contact.
+pub fn apply_import_rules(rules: &[ImportRule], contact: &mut Contact) {
+    for rule in rules {
+        rule.apply(contact);
+    }
+}
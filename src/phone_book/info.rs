@@ -0,0 +1,193 @@
+use std::fs;
+
+use comfy_table::Cell;
+use diesel::prelude::*;
+use indicatif::HumanBytes;
+
+use crate::connection::{self, establish_connection};
+use crate::migrations::schema_version;
+use crate::phone_book::contact::styled_table;
+use crate::phone_book::phone_book::PhoneBook;
+use crate::schema::contacts;
+use crate::settings;
+
+/// Diagnostic details about the database in use, shown by INFO.
+pub struct DatabaseInfo {
+    /// The path of the database, as configured.
+    pub path: String,
+    /// Whether the database only lives in memory, e.g. `:memory:`, and is lost on exit.
+    pub in_memory: bool,
+    /// The size of the database file in bytes, `None` if it has no file.
+    pub size: Option<u64>,
+    /// The size of the write-ahead log in bytes, `None` if there is none.
+    pub wal_size: Option<u64>,
+    /// The number of contacts.
+    pub contacts: i64,
+    /// The version of the latest migration applied, e.g. `20250419100000`.
+    pub schema_version: Option<String>,
+}
+
+impl DatabaseInfo {
+    /// Warns that the database has grown past the size of the `DATABASE_SIZE_WARNING_MB`
+    /// setting, where SQLite gets slow to back up and search, suggesting to archive old contacts.
+    ///
+    /// # Returns
+    ///
+    /// The warning, or `None` if the database is smaller, has no file or the warning is off.
+    pub fn size_warning(&self) -> Option<String> {
+        let limit = settings::size_warning().ok().flatten()?;
+        let size = self.size? + self.wal_size.unwrap_or(0);
+        (size > limit).then(|| {
+            format!(
+                "Warning: the database is {}, over the {} of DATABASE_SIZE_WARNING_MB. {}",
+                HumanBytes(size),
+                HumanBytes(limit),
+                "Consider exporting old contacts (CX) and deleting them to keep it fast."
+            )
+        })
+    }
+}
+
+/// Checks whether a database path names an in-memory database rather than a file.
+///
+/// # Arguments
+///
+/// * `path` - The path of the database.
+///
+/// # Returns
+///
+/// `true` for `:memory:` and `file:` URIs opened with `mode=memory`.
+pub(crate) fn is_in_memory(path: &str) -> bool {
+    path == ":memory:" || path.starts_with("file::memory:") || path.contains("mode=memory")
+}
+
+impl PhoneBook {
+    /// Prints the database path, its size on disk, the number of contacts and the schema
+    /// version, which is what troubleshooting usually starts with.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the details to the console.
+    pub(crate) fn show_info(&mut self) {
+        let info = match Self::database_info() {
+            Ok(info) => info,
+            Err(err) => {
+                println!("Error reading the database details: {}", err);
+                return;
+            }
+        };
+        let size = match (info.in_memory, info.size, info.wal_size) {
+            (true, _, _) => "in memory, not saved on exit".to_string(),
+            (false, Some(size), Some(wal_size)) if wal_size > 0 => format!(
+                "{} (+ {} in the write-ahead log)",
+                HumanBytes(size),
+                HumanBytes(wal_size)
+            ),
+            (false, Some(size), _) => HumanBytes(size).to_string(),
+            (false, None, _) => "unknown".to_string(),
+        };
+        let mut table = styled_table();
+        table
+            .add_row(vec![
+                Cell::new("Database").add_attribute(comfy_table::Attribute::Bold),
+                Cell::new(&info.path),
+            ])
+            .add_row(vec![
+                Cell::new("Size").add_attribute(comfy_table::Attribute::Bold),
+                Cell::new(size),
+            ])
+            .add_row(vec![
+                Cell::new("Contacts").add_attribute(comfy_table::Attribute::Bold),
+                Cell::new(info.contacts),
+            ])
+            .add_row(vec![
+                Cell::new("Schema version").add_attribute(comfy_table::Attribute::Bold),
+                Cell::new(info.schema_version.as_deref().unwrap_or("none")),
+            ]);
+        println!("{}", table);
+        if let Some(warning) = info.size_warning() {
+            println!("{}", warning);
+        }
+    }
+    /// Gathers diagnostic details about the database in use.
+    ///
+    /// # Returns
+    ///
+    /// The details, or an error message if the database can't be queried.
+    pub fn database_info() -> Result<DatabaseInfo, String> {
+        let path =
+            connection::database_url().ok_or_else(|| "DATABASE_URL must be set".to_string())?;
+        let mut connection = establish_connection();
+        let contacts = contacts::table
+            .count()
+            .get_result(&mut connection)
+            .map_err(|err| err.to_string())?;
+        let schema_version = schema_version(&mut connection).map_err(|err| err.to_string())?;
+        let in_memory = is_in_memory(&path);
+        let file_size = |path: &str| {
+            fs::metadata(path)
+                .ok()
+                .filter(|_| !in_memory)
+                .map(|metadata| metadata.len())
+        };
+        Ok(DatabaseInfo {
+            size: file_size(&path),
+            wal_size: file_size(&format!("{}-wal", path)),
+            path,
+            in_memory,
+            contacts,
+            schema_version,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contact, open_book, set};
+
+    #[test]
+    fn reported_contact_count_matches_the_inserted_contacts() {
+        let mut book = open_book();
+        assert_eq!(PhoneBook::database_info().unwrap().contacts, 0);
+        for first_name in ["John", "Jane", "Mary"] {
+            book.add(contact(first_name, "Doe", "5551234567", ""))
+                .unwrap();
+        }
+        let info = PhoneBook::database_info().unwrap();
+        assert_eq!(info.contacts, 3);
+        assert_eq!(Some(info.path), connection::database_url());
+        assert!(!info.in_memory);
+        assert!(info.size.is_some_and(|size| size > 0));
+        assert!(info.schema_version.is_some());
+    }
+
+    #[test]
+    fn memory_databases_are_recognized() {
+        assert!(is_in_memory(":memory:"));
+        assert!(is_in_memory("file::memory:?cache=shared"));
+        assert!(is_in_memory("file:contacts?mode=memory"));
+        assert!(!is_in_memory("contacts.sqlite"));
+    }
+
+    #[test]
+    fn size_warning_fires_above_the_configured_size() {
+        let _book = open_book();
+        set("DATABASE_SIZE_WARNING_MB", "1");
+        let mut info = PhoneBook::database_info().unwrap();
+        assert_eq!(info.size_warning(), None);
+
+        info.size = Some(1024 * 1024);
+        info.wal_size = Some(512 * 1024);
+        let warning = info.size_warning().unwrap();
+        assert!(warning.starts_with("Warning: the database is 1.50 MiB, over the 1.00 MiB"));
+        assert!(warning.contains("Consider exporting old contacts"));
+
+        set("DATABASE_SIZE_WARNING_MB", "0");
+        assert_eq!(info.size_warning(), None);
+    }
+}
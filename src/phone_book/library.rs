@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::Utc;
+use diesel::prelude::*;
+
+use crate::connection::{self, establish_connection};
+use crate::phone_book::contact::{Contact, FIELDS};
+use crate::phone_book::phone_book::{Options, PhoneBook};
+use crate::schema::contacts;
+use crate::settings;
+
+/// Define the orders in which contacts can be listed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    /// By first name, then last name, A to Z.
+    FirstNameAsc,
+    /// By first name, then last name, Z to A.
+    FirstNameDesc,
+    /// By last name, then first name, A to Z.
+    LastNameAsc,
+    /// By last name, then first name, Z to A.
+    LastNameDesc,
+    /// Oldest contacts first.
+    CreatedAsc,
+    /// Newest contacts first.
+    CreatedDesc,
+    /// By the digits of the phone number, lowest first, whatever their formatting. Contacts
+    /// without a phone number come last.
+    PhoneAsc,
+    /// By the digits of the phone number, highest first. Contacts without a phone number come
+    /// last.
+    PhoneDesc,
+    /// By company, then last and first name, A to Z. Contacts without a company come last.
+    CompanyAsc,
+    /// By company, Z to A, then last and first name, A to Z. Contacts without a company come
+    /// last.
+    CompanyDesc,
+}
+
+impl SortOrder {
+    /// Returns the key naming the order in the `DEFAULT_SORT` setting, one of
+    /// `settings::SORT_KEYS`.
+    ///
+    /// # Returns
+    ///
+    /// The sort key, e.g. `last_asc`.
+    pub fn key(self) -> &'static str {
+        match self {
+            SortOrder::FirstNameAsc => "first_asc",
+            SortOrder::FirstNameDesc => "first_desc",
+            SortOrder::LastNameAsc => "last_asc",
+            SortOrder::LastNameDesc => "last_desc",
+            SortOrder::CreatedAsc => "created_asc",
+            SortOrder::CreatedDesc => "created_desc",
+            SortOrder::PhoneAsc => "phone_asc",
+            SortOrder::PhoneDesc => "phone_desc",
+            SortOrder::CompanyAsc => "company_asc",
+            SortOrder::CompanyDesc => "company_desc",
+        }
+    }
+    /// Returns the key of an order, falling back to the `DEFAULT_SORT` setting.
+    ///
+    /// # Returns
+    ///
+    /// The sort key, or an empty string for creation order if neither is set.
+    pub(crate) fn key_or_default(order: Option<Self>) -> String {
+        match order {
+            Some(order) => order.key().to_string(),
+            None => settings::default_sort().ok().flatten().unwrap_or_default(),
+        }
+    }
+}
+
+/// Define the errors of the methods for using the phone book from other programs.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The database can't be opened, configured or migrated, e.g. in a missing directory.
+    Connection(String),
+    /// A setting is invalid, e.g. `REQUIRED_FIELDS` names a field contacts don't have.
+    Settings(String),
+    /// The contact breaks a validation rule, e.g. a required field is empty or the email is
+    /// malformed.
+    InvalidContact(String),
+    /// A query failed.
+    Database(diesel::result::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Connection(message)
+            | Error::Settings(message)
+            | Error::InvalidContact(message) => f.write_str(message),
+            Error::Database(err) => write!(f, "Database error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Database(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<diesel::result::Error> for Error {
+    fn from(err: diesel::result::Error) -> Self {
+        Error::Database(err)
+    }
+}
+
+/// Methods for using the phone book from other programs: they return data and errors instead of
+/// prompting and printing, and the interactive operations are built on the same functions.
+impl PhoneBook {
+    /// Opens a phone book stored in the given SQLite database, creating and migrating it if
+    /// needed. Each phone book works on its own database, even once others are opened, and
+    /// neither changes the one named by the `DATABASE_URL` environment variable.
+    ///
+    /// # Arguments
+    ///
+    /// * `database_url` - The path of the SQLite database.
+    ///
+    /// # Returns
+    ///
+    /// The phone book, or an error if the database can't be opened or the settings are invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use phone_book_management_cli_rust::phone_book::{Contact, PhoneBook};
+    ///
+    /// let mut phone_book = PhoneBook::open("contacts.sqlite").unwrap();
+    /// let contact = Contact::new(
+    ///     "John".to_string(),
+    ///     "Doe".to_string(),
+    ///     "john@example.com".to_string(),
+    ///     "123 Main St".to_string(),
+    ///     "5551234567".to_string(),
+    /// );
+    /// let contact_id = phone_book.add(contact).unwrap();
+    /// assert!(phone_book.get(contact_id).unwrap().is_some());
+    /// ```
+    pub fn open(database_url: &str) -> Result<Self, Error> {
+        connection::activate_database(database_url);
+        connection::try_establish_connection().map_err(Error::Connection)?;
+        // Stored settings are read again, in case another program changed them
+        settings::reload();
+        settings::validate().map_err(Error::Settings)?;
+        Ok(Self {
+            contacts: Vec::new(),
+            options: Options::default(),
+            running: true,
+            list_positions: HashMap::new(),
+            database_url: Some(database_url.to_string()),
+        })
+    }
+    /// Adds a contact, after checking it against the configured rules.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - The contact to add, e.g. from `Contact::new`.
+    ///
+    /// # Returns
+    ///
+    /// The id of the new contact, or an error if the contact is invalid or couldn't be saved.
+    pub fn add(&mut self, contact: Contact) -> Result<i32, Error> {
+        self.activate();
+        Self::check_contact(&contact)?;
+        Ok(Self::insert_contact(&contact, "create")?)
+    }
+    /// Loads a contact by id.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact_id` - The id of the contact.
+    ///
+    /// # Returns
+    ///
+    /// The contact, or `None` if no contact has that id.
+    pub fn get(&self, contact_id: i32) -> Result<Option<Contact>, Error> {
+        self.activate();
+        Ok(Self::get_contact(contact_id)?)
+    }
+    /// Replaces the details of a contact, after checking them against the configured rules. The
+    /// contact's additional emails and tags are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact_id` - The id of the contact to update.
+    /// * `contact` - The new details of the contact.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the contact was updated, `false` if no contact has that id, or an error if the
+    /// details are invalid or couldn't be saved.
+    pub fn update(&mut self, contact_id: i32, contact: Contact) -> Result<bool, Error> {
+        self.activate();
+        Self::check_contact(&contact)?;
+        let contact = Contact {
+            updated_at: Utc::now().naive_utc(),
+            ..contact
+        };
+        Ok(Self::save_contact_update(contact_id, &contact, None, None)? > 0)
+    }
+    /// Deletes a contact along with its additional emails, tags and relationships.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact_id` - The id of the contact to delete.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the contact was deleted, `false` if no contact has that id.
+    pub fn delete(&mut self, contact_id: i32) -> Result<bool, Error> {
+        self.activate();
+        Ok(Self::delete_contact_by_id(contact_id)? > 0)
+    }
+    /// Searches the contacts like the Q operation, through the full-text index.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The search query.
+    ///
+    /// # Returns
+    ///
+    /// The matching contacts, best matches first.
+    pub fn search(&self, query: &str) -> Result<Vec<Contact>, Error> {
+        self.activate();
+        Ok(Self::search_contacts(query)?)
+    }
+    /// Finds the contacts whose first name, last name or full name contains the query, ignoring
+    /// case: "ann" finds Ann Doe and Joanne Dodd, and "ann doe" only Ann Doe.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The text to look for in the names.
+    ///
+    /// # Returns
+    ///
+    /// The matching contacts, sorted by first and last name.
+    pub fn search_by_name(&self, query: &str) -> Result<Vec<Contact>, Error> {
+        self.activate();
+        let mut connection = establish_connection();
+        let pattern = format!("%{}%", Self::escape_like(query.trim()));
+        let full_name = contacts::first_name.concat(" ").concat(contacts::last_name);
+        Ok(contacts::table
+            .filter(
+                contacts::first_name
+                    .like(&pattern)
+                    .escape('\\')
+                    .or(contacts::last_name.like(&pattern).escape('\\'))
+                    .or(full_name.like(&pattern).escape('\\')),
+            )
+            .order((contacts::first_name.asc(), contacts::last_name.asc()))
+            .load::<Contact>(&mut connection)?)
+    }
+    /// Lists every contact in the given order.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - The order of the contacts, or `None` for the order of the `DEFAULT_SORT`
+    ///   setting, creation order if it is unset.
+    ///
+    /// # Returns
+    ///
+    /// The contacts.
+    pub fn list(&self, order: Option<SortOrder>) -> Result<Vec<Contact>, Error> {
+        self.activate();
+        Ok(Self::get_contacts(&SortOrder::key_or_default(order))?)
+    }
+    /// Makes the connections opened from now on use the database of this phone book, if it was
+    /// opened with `open`, so each phone book works on its own file.
+    fn activate(&self) {
+        if let Some(database_url) = &self.database_url {
+            connection::activate_database(database_url);
+        }
+    }
+    /// Checks a contact against the configured rules before it is saved.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - The contact to check.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the contact is valid, otherwise an error describing the first problem found.
+    fn check_contact(contact: &Contact) -> Result<(), Error> {
+        Self::check_required_fields(contact)?;
+        contact.validate().map_err(Error::InvalidContact)
+    }
+    /// Checks that the fields of the `REQUIRED_FIELDS` setting are filled in, as the interactive
+    /// prompts do.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - The contact to check.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if every required field is filled in, otherwise an error naming the first
+    /// missing one.
+    fn check_required_fields(contact: &Contact) -> Result<(), Error> {
+        for field in settings::required_fields().map_err(Error::Settings)? {
+            if contact.field(&field).is_empty() {
+                let label = FIELDS
+                    .iter()
+                    .find(|&&(name, _)| name == field)
+                    .map_or(field.as_str(), |&(_, label)| label);
+                return Err(Error::InvalidContact(format!("{} is required.", label)));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,904 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use comfy_table::Cell;
+use diesel::prelude::*;
+use diesel::update;
+
+use crate::connection::establish_connection;
+use crate::phone_book::audit::{AuditEntry, ContactSnapshot};
+use crate::phone_book::contact::{styled_table, Contact, FIELDS};
+use crate::phone_book::operations::Cancelled;
+use crate::phone_book::phone_book::PhoneBook;
+use crate::schema::{contacts, emails};
+use crate::settings;
+use crate::shutdown::BusyGuard;
+
+/// Common given names, separated by spaces, used to spot a first name stored as the last name.
+const COMMON_GIVEN_NAMES: &str = "\
+    aaron adam alex alice amanda amy andrew angela anna anne anthony barbara ben betty brian \
+    carol charles chris christopher daniel david deborah donald dorothy edward elizabeth \
+    emily emma eric frank george helen jack james jane jason jeffrey jennifer jessica john \
+    joseph joshua karen kevin laura linda lisa margaret maria mark mary matthew melissa \
+    michael michelle nancy olivia patricia paul peter rebecca richard robert ronald ruth ryan \
+    sandra sarah scott sharon stephen steven susan thomas timothy william";
+
+/// Define what makes two contacts duplicates of each other.
+#[derive(Clone, Copy)]
+pub enum DuplicateKey {
+    /// The same phone number, ignoring formatting.
+    Phone,
+    /// The same first name, last name and email, ignoring case.
+    NameAndEmail,
+}
+
+/// Define which of a set of duplicate contacts is kept.
+#[derive(Clone, Copy)]
+pub enum KeepStrategy {
+    /// The contact with the most fields filled in.
+    MostComplete,
+    /// The most recently updated contact.
+    Newest,
+}
+
+/// A set of duplicate contacts: the one to keep and the ones to remove.
+pub struct DuplicateGroup {
+    pub key: String,
+    pub kept: Contact,
+    pub removed: Vec<Contact>,
+}
+
+impl DuplicateKey {
+    /// Computes the key two duplicate contacts share.
+    ///
+    /// # Returns
+    ///
+    /// The key, or `None` if the contact lacks the fields needed to compare it.
+    fn of(self, contact: &Contact) -> Option<String> {
+        match self {
+            DuplicateKey::Phone => {
+                let digits: String = contact.phone.chars().filter(char::is_ascii_digit).collect();
+                Some(digits).filter(|digits| !digits.is_empty())
+            }
+            DuplicateKey::NameAndEmail => {
+                if contact.email.trim().is_empty() {
+                    return None;
+                }
+                Some(format!(
+                    "{} {} <{}>",
+                    contact.first_name.trim(),
+                    contact.last_name.trim(),
+                    contact.email.trim()
+                ))
+                .map(|key| key.to_lowercase())
+            }
+        }
+    }
+}
+
+impl PhoneBook {
+    /// Re-runs phone number standardization over every stored contact.
+    ///
+    /// This is useful after importing legacy data, which is stored as-is, or after turning the
+    /// `STANDARDIZE_PHONE` setting back on. Only rows whose phone number actually changes are
+    /// updated, all in a single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints how many contacts were changed.
+    pub(crate) fn normalize_all_phones(&mut self) {
+        if !settings::standardize_phone().unwrap_or(true) {
+            println!("Phone number standardization is turned off (STANDARDIZE_PHONE).");
+            return;
+        }
+        match Self::normalize_phones() {
+            Ok(changed) => println!("Normalized {} phone number(s).", changed),
+            Err(err) => println!("Error normalizing phone numbers: {}", err),
+        }
+    }
+    /// Standardizes the phone number of every contact whose number isn't standardized yet.
+    ///
+    /// # Returns
+    ///
+    /// The number of contacts that were changed.
+    pub fn normalize_phones() -> QueryResult<usize> {
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            let now = Utc::now().naive_utc();
+            let mut changed = 0;
+            for contact in contacts::table.load::<Contact>(connection)? {
+                let phone = Contact::standardize_phone_number(&contact.phone);
+                let Some(contact_id) = contact.id.filter(|_| phone != contact.phone) else {
+                    continue;
+                };
+                let before = ContactSnapshot::load(connection, contact_id)?;
+                changed += update(contacts::table.filter(contacts::id.eq(contact_id)))
+                    .set((contacts::phone.eq(phone), contacts::updated_at.eq(now)))
+                    .execute(connection)?;
+                AuditEntry::record(
+                    connection,
+                    "normalize phone",
+                    Some(contact_id),
+                    before.as_ref(),
+                )?;
+            }
+            Ok(changed)
+        })
+    }
+    /// Lowercases and trims every stored email, primary and additional, so legacy data entered
+    /// with mixed case matches when searching and finding duplicates by email.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints how many emails were changed.
+    pub(crate) fn normalize_all_emails(&mut self) {
+        match Self::normalize_emails() {
+            Ok(changed) => println!("Normalized {} email(s).", changed),
+            Err(err) => println!("Error normalizing emails: {}", err),
+        }
+    }
+    /// Lowercases and trims the primary and additional emails of every contact, in a single
+    /// transaction. Each changed contact is recorded once in the audit log.
+    ///
+    /// # Returns
+    ///
+    /// The number of emails that were changed.
+    pub fn normalize_emails() -> QueryResult<usize> {
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            let mut additional: BTreeMap<i32, Vec<(i32, String)>> = BTreeMap::new();
+            for (email_id, contact_id, address) in emails::table
+                .select((emails::id, emails::contact_id, emails::address))
+                .load::<(Option<i32>, i32, String)>(connection)?
+            {
+                if let Some(email_id) = email_id {
+                    additional
+                        .entry(contact_id)
+                        .or_default()
+                        .push((email_id, address));
+                }
+            }
+            let now = Utc::now().naive_utc();
+            let mut changed = 0;
+            for contact in contacts::table.load::<Contact>(connection)? {
+                let Some(contact_id) = contact.id else {
+                    continue;
+                };
+                let email = contact.email.trim().to_lowercase();
+                let changed_additional: Vec<(i32, String)> = additional
+                    .remove(&contact_id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|(email_id, address)| {
+                        let normalized = address.trim().to_lowercase();
+                        (normalized != address).then_some((email_id, normalized))
+                    })
+                    .collect();
+                if email == contact.email && changed_additional.is_empty() {
+                    continue;
+                }
+                let before = ContactSnapshot::load(connection, contact_id)?;
+                if email != contact.email {
+                    changed += update(contacts::table.filter(contacts::id.eq(contact_id)))
+                        .set(contacts::email.eq(email))
+                        .execute(connection)?;
+                }
+                for (email_id, address) in changed_additional {
+                    changed += update(emails::table.filter(emails::id.eq(email_id)))
+                        .set(emails::address.eq(address))
+                        .execute(connection)?;
+                }
+                update(contacts::table.filter(contacts::id.eq(contact_id)))
+                    .set(contacts::updated_at.eq(now))
+                    .execute(connection)?;
+                AuditEntry::record(
+                    connection,
+                    "normalize email",
+                    Some(contact_id),
+                    before.as_ref(),
+                )?;
+            }
+            Ok(changed)
+        })
+    }
+}
+
+impl PhoneBook {
+    /// Scans the whole phone book for duplicate contacts and removes all but one of each set.
+    ///
+    /// The user chooses what makes contacts duplicates and which one is kept. A report of what
+    /// would be removed is shown first, and nothing is deleted until the user confirms. The
+    /// database is backed up, unless `AUTO_BACKUP` is off, and the removal runs in a single
+    /// transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the report to the console.
+    pub(crate) fn dedupe_contacts(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        Self::print_cancel_hint();
+        let key = match Self::get_input_or_cancel(
+            "Find duplicates by (1) phone number or (2) name and email: ",
+        )
+        .as_deref()
+        {
+            Ok("1") => DuplicateKey::Phone,
+            Ok("2") => DuplicateKey::NameAndEmail,
+            Ok(_) => {
+                println!("Invalid choice!");
+                return;
+            }
+            Err(_) => {
+                println!("Deduplication cancelled.");
+                return;
+            }
+        };
+        let strategy = match Self::get_input_or_cancel(
+            "Keep (1) the most complete or (2) the newest contact: ",
+        )
+        .as_deref()
+        {
+            Ok("1") => KeepStrategy::MostComplete,
+            Ok("2") => KeepStrategy::Newest,
+            Ok(_) => {
+                println!("Invalid choice!");
+                return;
+            }
+            Err(_) => {
+                println!("Deduplication cancelled.");
+                return;
+            }
+        };
+        let contacts = match Self::get_contacts("") {
+            Ok(contacts) => contacts,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        let groups = Self::find_duplicates(contacts, key, strategy);
+        if groups.is_empty() {
+            println!("No duplicate contacts found.");
+            return;
+        }
+        Self::print_duplicate_report(&groups);
+        let removed: usize = groups.iter().map(|group| group.removed.len()).sum();
+        let confirm = Self::get_input(&format!("Remove {} duplicate contacts? (y/n): ", removed));
+        if confirm.to_lowercase() != "y" {
+            println!("Deduplication cancelled.");
+            return;
+        }
+        if !Self::backup_before("deduplication") {
+            return;
+        }
+        match Self::remove_duplicates(&groups) {
+            Ok(removed) => {
+                // The last listing may refer to removed contacts
+                self.contacts.clear();
+                println!("Removed {} duplicate contact(s).", removed);
+            }
+            Err(err) => println!("Error removing duplicate contacts: {}", err),
+        }
+    }
+    /// Groups contacts sharing the same key and picks the one to keep in each group.
+    ///
+    /// # Arguments
+    ///
+    /// * `contacts` - The contacts to scan.
+    /// * `key` - What makes two contacts duplicates.
+    /// * `strategy` - Which duplicate to keep.
+    ///
+    /// # Returns
+    ///
+    /// The groups with at least one contact to remove, ordered by key.
+    pub fn find_duplicates(
+        contacts: Vec<Contact>,
+        key: DuplicateKey,
+        strategy: KeepStrategy,
+    ) -> Vec<DuplicateGroup> {
+        let mut groups: BTreeMap<String, Vec<Contact>> = BTreeMap::new();
+        for contact in contacts {
+            if let Some(group_key) = key.of(&contact) {
+                groups.entry(group_key).or_default().push(contact);
+            }
+        }
+        groups
+            .into_iter()
+            .filter(|(_, contacts)| contacts.len() > 1)
+            .map(|(key, mut contacts)| {
+                // Sort the contact to keep first; ties go to the oldest contact.
+                contacts.sort_by(|a, b| {
+                    let preferred = match strategy {
+                        KeepStrategy::MostComplete => {
+                            Self::completeness(b).cmp(&Self::completeness(a))
+                        }
+                        KeepStrategy::Newest => b.updated_at.cmp(&a.updated_at),
+                    };
+                    preferred.then(a.id.cmp(&b.id))
+                });
+                let kept = contacts.remove(0);
+                DuplicateGroup {
+                    key,
+                    kept,
+                    removed: contacts,
+                }
+            })
+            .collect()
+    }
+    /// Counts the fields of a contact that are filled in.
+    fn completeness(contact: &Contact) -> usize {
+        FIELDS
+            .iter()
+            .filter(|&&(field, _)| !contact.field(field).trim().is_empty())
+            .count()
+    }
+    /// Prints which contact is kept and which are removed for each set of duplicates.
+    fn print_duplicate_report(groups: &[DuplicateGroup]) {
+        let describe = |contact: &Contact| {
+            format!(
+                "#{} {} {}",
+                contact.id.unwrap_or_default(),
+                contact.first_name,
+                contact.last_name
+            )
+            .trim()
+            .to_string()
+        };
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("Duplicate Of").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Kept").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Removed").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        for group in groups {
+            let removed: Vec<String> = group.removed.iter().map(describe).collect();
+            table.add_row(vec![
+                Cell::new(&group.key),
+                Cell::new(describe(&group.kept)),
+                Cell::new(removed.join("\n")),
+            ]);
+        }
+        println!("{}", table);
+    }
+    /// Deletes the duplicates of every group, in a single transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `groups` - The duplicate groups found by `find_duplicates`.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted contacts.
+    pub fn remove_duplicates(groups: &[DuplicateGroup]) -> QueryResult<usize> {
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            let mut removed = 0;
+            for contact_id in groups
+                .iter()
+                .flat_map(|group| &group.removed)
+                .filter_map(|contact| contact.id)
+            {
+                let before = ContactSnapshot::load(connection, contact_id)?;
+                removed += Self::delete_contact_rows(connection, contact_id)?;
+                AuditEntry::record(connection, "dedupe", Some(contact_id), before.as_ref())?;
+            }
+            Ok(removed)
+        })
+    }
+    /// Scans the whole phone book for contacts failing the current validation rules, such as
+    /// data stored before a rule existed, and reports each problem.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the report to the console.
+    pub(crate) fn validate_all(&mut self) {
+        let invalid = match Self::find_invalid_contacts() {
+            Ok(invalid) => invalid,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        if invalid.is_empty() {
+            println!("All contacts pass validation.");
+            return;
+        }
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("Contact").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Problem").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        for (contact, problems) in &invalid {
+            let name = format!("{} {}", contact.first_name, contact.last_name);
+            table.add_row(vec![
+                Cell::new(format!(
+                    "#{} {}",
+                    contact.id.unwrap_or_default(),
+                    name.trim()
+                )),
+                Cell::new(problems.join("\n")),
+            ]);
+        }
+        println!("{}", table);
+        println!("{} contact(s) failed validation.", invalid.len());
+        let confirm = Self::get_input("Fix the flagged contacts now? (y/n): ");
+        if confirm.to_lowercase() == "y" {
+            self.fix_invalid_contacts(invalid);
+        }
+    }
+    /// Steps through invalid contacts, letting the user fix, skip or stop at each one.
+    ///
+    /// A fixed contact is validated again before it is saved; if problems remain, the user is
+    /// asked to fix it again or skip it.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    /// * `invalid` - The invalid contacts with their problems, from `find_invalid_contacts`.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    fn fix_invalid_contacts(&mut self, invalid: Vec<(Contact, Vec<String>)>) {
+        let required_fields = match settings::required_fields() {
+            Ok(required_fields) => required_fields,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let total = invalid.len();
+        let mut fixed = 0;
+        'contacts: for (position, (mut contact, mut problems)) in invalid.into_iter().enumerate() {
+            println!("Contact {} of {}:", position + 1, total);
+            Self::print_contact_details(&contact);
+            loop {
+                for problem in &problems {
+                    println!("- {}", problem);
+                }
+                let choice = Self::get_input("(F)ix, (S)kip or (Q)uit: ").to_lowercase();
+                match choice.as_str() {
+                    "f" => {}
+                    "s" => continue 'contacts,
+                    "q" => break 'contacts,
+                    _ => {
+                        println!("Invalid choice!");
+                        continue;
+                    }
+                }
+                Self::print_cancel_hint();
+                let Ok(fixed_contact) = Self::prompt_contact_fix(&contact) else {
+                    println!("Fix cancelled.");
+                    continue;
+                };
+                problems = fixed_contact.problems(&required_fields);
+                if !problems.is_empty() {
+                    // Keep the edits, so fixing again starts from them
+                    contact = fixed_contact;
+                    println!("The contact still fails validation:");
+                    continue;
+                }
+                match Self::save_fixed_contact(&fixed_contact) {
+                    Ok(()) => {
+                        fixed += 1;
+                        println!("Contact saved.");
+                    }
+                    Err(err) => println!("Error saving contact: {}", err),
+                }
+                continue 'contacts;
+            }
+        }
+        // The last listing may show contacts as they were before the fixes
+        self.contacts.clear();
+        println!("Fixed {} of {} flagged contact(s).", fixed, total);
+    }
+    /// Prompts for new values of every field of a contact, keeping the current value when the
+    /// input is left empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - The contact being fixed.
+    ///
+    /// # Returns
+    ///
+    /// The contact with the new values, or `Err(Cancelled)` if the user typed the cancel token.
+    fn prompt_contact_fix(contact: &Contact) -> Result<Contact, Cancelled> {
+        let mut values = Vec::with_capacity(FIELDS.len());
+        for &(field, label) in FIELDS {
+            let current = contact.field(field);
+            let value = Self::get_input_or_cancel(&format!(
+                "Enter new {} (leave empty to keep '{}'): ",
+                label.to_lowercase(),
+                current
+            ))?;
+            values.push(if value.is_empty() {
+                current.to_string()
+            } else {
+                value
+            });
+        }
+        Ok(Contact {
+            id: contact.id,
+            created_at: contact.created_at,
+            ..Self::contact_from_values(values)
+        })
+    }
+    /// Saves the fields of a fixed contact, recording the change in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - The fixed contact.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the contact was saved.
+    fn save_fixed_contact(contact: &Contact) -> QueryResult<()> {
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            let before = match contact.id {
+                Some(contact_id) => ContactSnapshot::load(connection, contact_id)?,
+                None => None,
+            };
+            update(contacts::table.filter(contacts::id.eq(contact.id)))
+                .set((
+                    contacts::first_name.eq(&contact.first_name),
+                    contacts::last_name.eq(&contact.last_name),
+                    contacts::email.eq(&contact.email),
+                    contacts::address.eq(&contact.address),
+                    contacts::phone.eq(&contact.phone),
+                    contacts::website.eq(&contact.website),
+                    contacts::extension.eq(&contact.extension),
+                    contacts::company.eq(&contact.company),
+                    contacts::preferred_method.eq(&contact.preferred_method),
+                    contacts::updated_at.eq(&contact.updated_at),
+                ))
+                .execute(connection)?;
+            AuditEntry::record(connection, "fix", contact.id, before.as_ref())
+        })
+    }
+    /// Finds the contacts failing the current validation rules.
+    ///
+    /// # Returns
+    ///
+    /// Each invalid contact with its problems, in creation order, or an error message if the
+    /// settings or the contacts can't be read.
+    pub fn find_invalid_contacts() -> Result<Vec<(Contact, Vec<String>)>, String> {
+        let required_fields = settings::required_fields()?;
+        let contacts = Self::get_contacts("")
+            .map_err(|err| format!("Error fetching contacts from the database: {}", err))?;
+        Ok(contacts
+            .into_iter()
+            .filter_map(|contact| {
+                let problems = contact.problems(&required_fields);
+                (!problems.is_empty()).then_some((contact, problems))
+            })
+            .collect())
+    }
+}
+
+impl PhoneBook {
+    /// Looks for contacts whose first and last names seem to be swapped, as happens with imports
+    /// whose columns were mixed up, and offers to swap each one back.
+    ///
+    /// Nothing is changed without the user confirming each contact.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn fix_swapped_names(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        let suspects = match Self::find_swapped_names() {
+            Ok(suspects) => suspects,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        if suspects.is_empty() {
+            println!("No contacts with swapped names found.");
+            return;
+        }
+        let total = suspects.len();
+        let mut swapped = 0;
+        for (position, (contact, reason)) in suspects.into_iter().enumerate() {
+            let (first_name, last_name) = Self::swapped_names(&contact);
+            println!(
+                "Contact {} of {}: #{} '{}' '{}' ({}).",
+                position + 1,
+                total,
+                contact.id.unwrap_or_default(),
+                contact.first_name,
+                contact.last_name,
+                reason
+            );
+            let choice = Self::get_input(&format!(
+                "Change to first name '{}' and last name '{}'? (y)es, (n)o or (q)uit: ",
+                first_name, last_name
+            ))
+            .to_lowercase();
+            match choice.as_str() {
+                "y" => match Self::swap_names(&contact) {
+                    Ok(()) => swapped += 1,
+                    Err(err) => println!("Error saving contact: {}", err),
+                },
+                "q" => break,
+                _ => {}
+            }
+        }
+        // The last listing may show the names as they were
+        self.contacts.clear();
+        println!(
+            "Swapped the names of {} of {} flagged contact(s).",
+            swapped, total
+        );
+    }
+    /// Finds the contacts whose first and last names seem to be swapped.
+    ///
+    /// # Returns
+    ///
+    /// Each suspect contact with the reason it was flagged, in creation order.
+    pub fn find_swapped_names() -> QueryResult<Vec<(Contact, &'static str)>> {
+        Ok(Self::get_contacts("")?
+            .into_iter()
+            .filter_map(|contact| {
+                let reason = Self::swapped_names_reason(&contact)?;
+                Some((contact, reason))
+            })
+            .collect())
+    }
+    /// Tells why a contact's names look swapped, if they do.
+    ///
+    /// A contact is flagged when its first name ends with a comma, as in "Doe, John" split in
+    /// two; when its first name is in capitals but its last name isn't, as surnames are often
+    /// written; or when its last name is a common given name but its first name isn't.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - The contact to check.
+    ///
+    /// # Returns
+    ///
+    /// The reason, or `None` if the names look right.
+    pub(crate) fn swapped_names_reason(contact: &Contact) -> Option<&'static str> {
+        let first_name = contact.first_name.trim();
+        let last_name = contact.last_name.trim();
+        if first_name.is_empty() || last_name.is_empty() {
+            return None;
+        }
+        let is_capitalized = |name: &str| {
+            name.chars().filter(|c| c.is_alphabetic()).count() > 1 && name.to_uppercase() == name
+        };
+        let is_given_name = |name: &str| {
+            let name = name.to_lowercase();
+            COMMON_GIVEN_NAMES
+                .split_whitespace()
+                .any(|given| given == name)
+        };
+        if first_name.ends_with(',') {
+            Some("the first name ends with a comma")
+        } else if is_capitalized(first_name) && !is_capitalized(last_name) {
+            Some("the first name is in capitals, as surnames often are")
+        } else if is_given_name(last_name) && !is_given_name(first_name) {
+            Some("the last name is a common given name")
+        } else {
+            None
+        }
+    }
+    /// Computes a contact's names once swapped back, dropping a trailing comma from the surname.
+    ///
+    /// # Returns
+    ///
+    /// The new `(first name, last name)`.
+    fn swapped_names(contact: &Contact) -> (String, String) {
+        (
+            contact.last_name.trim().to_string(),
+            contact
+                .first_name
+                .trim()
+                .trim_end_matches(',')
+                .trim()
+                .to_string(),
+        )
+    }
+    /// Swaps a contact's first and last names, recording the change in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - The contact whose names to swap.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the contact was saved.
+    fn swap_names(contact: &Contact) -> QueryResult<()> {
+        let Some(contact_id) = contact.id else {
+            return Ok(());
+        };
+        let (first_name, last_name) = Self::swapped_names(contact);
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            let before = ContactSnapshot::load(connection, contact_id)?;
+            update(contacts::table.filter(contacts::id.eq(contact_id)))
+                .set((
+                    contacts::first_name.eq(first_name),
+                    contacts::last_name.eq(last_name),
+                    contacts::updated_at.eq(Utc::now().naive_utc()),
+                ))
+                .execute(connection)?;
+            AuditEntry::record(connection, "swap names", Some(contact_id), before.as_ref())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phone_book::contact_email::ContactEmail;
+    use crate::test_support::{contact, open_book, set};
+
+    #[test]
+    fn raw_phone_numbers_are_reformatted() {
+        let mut book = open_book();
+        let formatted = book.add(contact("Ann", "Lee", "5550001111", "")).unwrap();
+        set("STANDARDIZE_PHONE", "false");
+        let raw = book
+            .add(contact("John", "Doe", "555.123.4567", ""))
+            .unwrap();
+        let short = book.add(contact("Bob", "Ray", "555-0199", "")).unwrap();
+        settings::store("STANDARDIZE_PHONE", None).unwrap();
+
+        assert_eq!(PhoneBook::normalize_phones().unwrap(), 1);
+        let phone = |contact_id| book.get(contact_id).unwrap().unwrap().phone;
+        assert_eq!(phone(raw), "(555) 123-4567");
+        assert_eq!(phone(formatted), "(555) 000-1111");
+        assert_eq!(phone(short), "555-0199");
+        assert_eq!(PhoneBook::normalize_phones().unwrap(), 0);
+    }
+
+    #[test]
+    fn duplicates_collapse_to_unique_rows() {
+        let mut book = open_book();
+        let sparse = book
+            .add(contact("John", "Doe", "555-123-4567", ""))
+            .unwrap();
+        let mut complete = contact("John", "Doe", "5551234567", "john@example.com");
+        complete.address = "1 Main St".to_string();
+        let complete = book.add(complete).unwrap();
+        let jane = book.add(contact("Jane", "Roe", "5559876543", "")).unwrap();
+
+        let groups = PhoneBook::find_duplicates(
+            book.list(None).unwrap(),
+            DuplicateKey::Phone,
+            KeepStrategy::MostComplete,
+        );
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].key, "5551234567");
+        assert_eq!(groups[0].kept.id, Some(complete));
+        assert_eq!(PhoneBook::remove_duplicates(&groups).unwrap(), 1);
+        let ids: Vec<Option<i32>> = book.list(None).unwrap().iter().map(|c| c.id).collect();
+        assert_eq!(ids, [Some(complete), Some(jane)]);
+        assert!(book.get(sparse).unwrap().is_none());
+    }
+
+    #[test]
+    fn newest_duplicate_by_name_and_email_is_kept() {
+        let mut book = open_book();
+        let older = book
+            .add(contact("Ann", "Lee", "5550000001", "ann@example.com"))
+            .unwrap();
+        let mut newer = contact("ANN", "lee", "5550000002", "Ann@Example.com");
+        newer.updated_at += chrono::Duration::minutes(1);
+        let newer = book.add(newer).unwrap();
+        book.add(contact("Ann", "Lee", "5550000003", "")).unwrap();
+
+        let groups = PhoneBook::find_duplicates(
+            book.list(None).unwrap(),
+            DuplicateKey::NameAndEmail,
+            KeepStrategy::Newest,
+        );
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].kept.id, Some(newer));
+        let removed: Vec<Option<i32>> = groups[0].removed.iter().map(|c| c.id).collect();
+        assert_eq!(removed, [Some(older)]);
+        assert_eq!(PhoneBook::remove_duplicates(&groups).unwrap(), 1);
+        assert_eq!(book.list(None).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn contact_with_a_bad_email_is_flagged() {
+        let _book = open_book();
+        PhoneBook::insert_contact(&contact("John", "Doe", "5551234567", "john@"), "import")
+            .unwrap();
+        PhoneBook::insert_contact(
+            &contact("Jane", "Roe", "5559876543", "jane@example.com"),
+            "import",
+        )
+        .unwrap();
+        let invalid = PhoneBook::find_invalid_contacts().unwrap();
+        assert_eq!(invalid.len(), 1);
+        let (flagged, problems) = &invalid[0];
+        assert_eq!(flagged.first_name, "John");
+        assert_eq!(problems, &["Invalid email (john@).".to_string()]);
+    }
+
+    #[test]
+    fn clearly_swapped_names_are_flagged_and_swapped_back() {
+        let mut book = open_book();
+        book.add(contact("Smith", "John", "5551234567", ""))
+            .unwrap();
+        book.add(contact("Mary", "Major", "5559876543", ""))
+            .unwrap();
+        let flagged = PhoneBook::find_swapped_names().unwrap();
+        assert_eq!(flagged.len(), 1);
+        let (swapped, reason) = &flagged[0];
+        assert_eq!(swapped.first_name, "Smith");
+        assert_eq!(*reason, "the last name is a common given name");
+        PhoneBook::swap_names(swapped).unwrap();
+        let fixed = book.get(swapped.id.unwrap()).unwrap().unwrap();
+        assert_eq!(
+            (fixed.first_name.as_str(), fixed.last_name.as_str()),
+            ("John", "Smith")
+        );
+        assert!(PhoneBook::find_swapped_names().unwrap().is_empty());
+    }
+
+    #[test]
+    fn mixed_case_emails_are_lowercased() {
+        let mut book = open_book();
+        let mut mixed = contact("John", "Doe", "5551234567", "");
+        mixed.email = " John.Doe@Example.COM".to_string();
+        // Saved without validation, like the emails stored before they were validated
+        let john = PhoneBook::insert_contact(&mixed, "create").unwrap();
+        let jane = book
+            .add(contact("Jane", "Doe", "5559876543", "jane@example.com"))
+            .unwrap();
+        let mut connection = establish_connection();
+        ContactEmail::replace_for(
+            &mut connection,
+            jane,
+            &[("work".to_string(), "Jane@Work.Example".to_string())],
+        )
+        .unwrap();
+
+        assert_eq!(PhoneBook::normalize_emails(), Ok(2));
+        assert_eq!(
+            book.get(john).unwrap().unwrap().email,
+            "john.doe@example.com"
+        );
+        let additional: Vec<String> = ContactEmail::load_for(&mut connection, jane)
+            .unwrap()
+            .into_iter()
+            .map(|email| email.address)
+            .collect();
+        assert_eq!(additional, ["jane@work.example"]);
+        assert_eq!(PhoneBook::normalize_emails(), Ok(0));
+    }
+}
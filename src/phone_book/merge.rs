@@ -0,0 +1,386 @@
+use std::collections::HashSet;
+use std::fs;
+
+use chrono::Utc;
+use comfy_table::Cell;
+use diesel::QueryResult;
+
+use crate::phone_book::contact::{styled_table, Contact, FIELDS};
+use crate::phone_book::device_import::parse_device_export;
+use crate::phone_book::import_rules::{apply_import_rules, ImportRule};
+use crate::phone_book::phone_book::PhoneBook;
+use crate::settings;
+
+/// Define what merging an incoming contact does to the phone book.
+pub enum MergeAction {
+    /// No contact matches the incoming one: the contact is added.
+    Add(Contact),
+    /// One contact matches the incoming one and some of its fields differ: they are replaced
+    /// with the non-empty incoming ones.
+    Update {
+        /// The id of the matching contact.
+        contact_id: i32,
+        /// The matching contact with the incoming fields applied.
+        contact: Contact,
+        /// The changed fields, described as `label: old → new`.
+        changes: Vec<String>,
+    },
+    /// The matching contact already has every non-empty incoming field.
+    Unchanged,
+    /// The incoming contact can't be merged safely, for the given reason.
+    Conflict(String),
+}
+
+/// Define the outcome of merging one incoming contact.
+pub struct MergeEntry {
+    /// The name of the incoming contact.
+    pub name: String,
+    /// What merging it does.
+    pub action: MergeAction,
+}
+
+impl PhoneBook {
+    /// Reconciles the phone book with a CSV file or a phone's JSON export, to sync contacts kept
+    /// in two places.
+    ///
+    /// Incoming contacts are matched by phone number, or by first name, last name and email when
+    /// they have no phone number (see `plan_merge`): new ones are added, and the matching contacts
+    /// get the incoming fields that aren't empty. Contacts matching several existing ones, or an
+    /// existing contact already matched earlier in the file, are reported as conflicts and left
+    /// alone. The report is shown before anything changes, so answering no makes it a dry run.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the report and outcome to the console.
+    pub(crate) fn merge_from_file(&mut self) {
+        Self::print_cancel_hint();
+        let Ok(file_name) = Self::get_input_or_cancel(
+            "Enter the name of the CSV or JSON file to merge contacts from: ",
+        ) else {
+            println!("Merge cancelled.");
+            return;
+        };
+        let file_name = match Self::expand_path(&file_name) {
+            Ok(file_name) => file_name,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let incoming = match Self::read_contacts_file(&file_name) {
+            Ok(incoming) => incoming,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        if incoming.is_empty() {
+            println!("No contacts found in file '{}'.", file_name);
+            return;
+        }
+        let entries = match Self::plan_merge(incoming) {
+            Ok(entries) => entries,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        let (adds, updates) = print_merge_report(&entries);
+        if adds + updates == 0 {
+            println!("Nothing to merge.");
+            return;
+        }
+        let confirm = Self::get_input(&format!(
+            "Add {} and update {} contact(s)? (y/n, n for a dry run): ",
+            adds, updates
+        ));
+        if confirm.to_lowercase() != "y" {
+            println!("Dry run: nothing was changed.");
+            return;
+        }
+        if !Self::backup_before("merge") {
+            return;
+        }
+        match Self::apply_merge(entries) {
+            Ok(()) => println!("Added {} and updated {} contact(s).", adds, updates),
+            Err(err) => println!("Error merging contacts: {}", err),
+        }
+    }
+    /// Reads the contacts of a file to merge or seed the phone book with: a phone's JSON export if
+    /// the name ends in `.json`, otherwise a CSV file with the same columns as the CSV import. The
+    /// `IMPORT_RULES` are applied as on import, and rows that can't be imported are reported and
+    /// skipped. When several columns have the same name, the first is used and a warning printed.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - The path of the file.
+    ///
+    /// # Returns
+    ///
+    /// The valid contacts of the file, or an error message if it can't be read.
+    pub(crate) fn read_contacts_file(file_name: &str) -> Result<Vec<Contact>, String> {
+        if file_name.to_lowercase().ends_with(".json") {
+            let json = fs::read_to_string(file_name)
+                .map_err(|err| format!("Error opening file: {}", err))?;
+            let rules = ImportRule::load_configured()?;
+            let mut contacts = Vec::new();
+            for (index, mut device_contact) in parse_device_export(&json)?.into_iter().enumerate() {
+                apply_import_rules(&rules, &mut device_contact.contact);
+                device_contact.contact.clear_placeholders();
+                match device_contact.contact.validate() {
+                    Ok(()) => contacts.push(device_contact.contact),
+                    Err(err) => println!("Skipping contact {}: {}", index + 1, err),
+                }
+            }
+            return Ok(contacts);
+        }
+        let encoding = settings::import_encoding()?;
+        let (mut reader, columns) = Self::open_csv_import(file_name, encoding)?;
+        for warning in columns.duplicate_warnings() {
+            println!("{}", warning);
+        }
+        let mut contacts = Vec::new();
+        let mut replaced = false;
+        for record in reader.records() {
+            if let Ok(record) = &record {
+                replaced |= Self::has_replaced_characters(record, encoding);
+            }
+            match columns.parse(record) {
+                Ok(contact) => contacts.push(contact),
+                Err(err) => println!("{}", err),
+            }
+        }
+        if replaced {
+            println!("Warning: the file contains bytes that aren't valid in IMPORT_ENCODING; they were replaced.");
+        }
+        Ok(contacts)
+    }
+    /// Works out what merging incoming contacts does, without changing anything.
+    ///
+    /// A contact with a phone number matches the contacts with the same digits. One without a
+    /// phone number matches the contacts with the same first name, last name and email, ignoring
+    /// case, like deduplication by name and email: a name alone is too often shared to tell two
+    /// people apart. A contact with neither a phone number nor an email is always added.
+    ///
+    /// # Arguments
+    ///
+    /// * `incoming` - The contacts to merge.
+    ///
+    /// # Returns
+    ///
+    /// The outcome for each incoming contact, in order.
+    pub fn plan_merge(incoming: Vec<Contact>) -> QueryResult<Vec<MergeEntry>> {
+        let mut matched = HashSet::new();
+        let mut entries = Vec::with_capacity(incoming.len());
+        for contact in incoming {
+            let name = format!("{} {}", contact.first_name, contact.last_name)
+                .trim()
+                .to_string();
+            let (mut existing, key) = if contact.phone.is_empty() {
+                let existing = Self::find_contacts_by_name_and_email(
+                    &contact.first_name,
+                    &contact.last_name,
+                    &contact.email,
+                )?;
+                (existing, "name and email match")
+            } else {
+                (
+                    Self::find_contacts_by_phone(&contact.phone)?,
+                    "phone number matches",
+                )
+            };
+            let action = match existing.len() {
+                0 => MergeAction::Add(contact),
+                1 => {
+                    let current = existing.remove(0);
+                    let contact_id = current.id.unwrap_or_default();
+                    if matched.insert(contact_id) {
+                        merge_fields(contact_id, current, &contact)
+                    } else {
+                        MergeAction::Conflict(format!(
+                            "contact #{} was already matched earlier in the file",
+                            contact_id
+                        ))
+                    }
+                }
+                count => MergeAction::Conflict(format!("the {} {} contacts", key, count)),
+            };
+            entries.push(MergeEntry { name, action });
+        }
+        Ok(entries)
+    }
+    /// Saves the additions and updates of a merge. Every change is recorded in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The outcome of each incoming contact, from `plan_merge`.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if every change was saved.
+    pub fn apply_merge(entries: Vec<MergeEntry>) -> QueryResult<()> {
+        let mut additions = Vec::new();
+        for entry in entries {
+            match entry.action {
+                MergeAction::Add(contact) => additions.push(contact),
+                MergeAction::Update {
+                    contact_id,
+                    contact,
+                    ..
+                } => {
+                    Self::save_contact_update(contact_id, &contact, None, None)?;
+                }
+                MergeAction::Unchanged | MergeAction::Conflict(_) => {}
+            }
+        }
+        Self::insert_contacts(&additions, "merge")
+    }
+}
+
+/// Applies the non-empty incoming fields to a matching contact. The phone number is left as it
+/// is, since the two either share its digits or the incoming contact has none.
+///
+/// # Arguments
+///
+/// * `contact_id` - The id of the matching contact.
+/// * `current` - The matching contact.
+/// * `incoming` - The incoming contact.
+///
+/// # Returns
+///
+/// `MergeAction::Update` with the changed fields, or `MergeAction::Unchanged`.
+fn merge_fields(contact_id: i32, current: Contact, incoming: &Contact) -> MergeAction {
+    let mut contact = current;
+    let mut changes = Vec::new();
+    for &(field, label) in FIELDS.iter().filter(|&&(field, _)| field != "phone") {
+        let value = incoming.field(field);
+        let old = contact.field(field);
+        if value.is_empty() || value == old {
+            continue;
+        }
+        let old = if old.is_empty() { "(empty)" } else { old };
+        changes.push(format!("{}: {} → {}", label, old, value));
+        contact.set_field(field, value.to_string());
+    }
+    if changes.is_empty() {
+        return MergeAction::Unchanged;
+    }
+    contact.updated_at = Utc::now().naive_utc();
+    MergeAction::Update {
+        contact_id,
+        contact,
+        changes,
+    }
+}
+
+/// Prints what a merge does to each incoming contact, and how many contacts are unchanged.
+///
+/// # Arguments
+///
+/// * `entries` - The outcome of each incoming contact.
+///
+/// # Returns
+///
+/// The number of contacts added and updated by the merge.
+fn print_merge_report(entries: &[MergeEntry]) -> (usize, usize) {
+    let mut table = styled_table();
+    table.set_header(vec![
+        Cell::new("Action").add_attribute(comfy_table::Attribute::Bold),
+        Cell::new("Name").add_attribute(comfy_table::Attribute::Bold),
+        Cell::new("Details").add_attribute(comfy_table::Attribute::Bold),
+    ]);
+    let (mut adds, mut updates, mut unchanged, mut conflicts) = (0, 0, 0, 0);
+    for entry in entries {
+        let (action, details) = match &entry.action {
+            MergeAction::Add(contact) => {
+                adds += 1;
+                ("add", contact.full_phone())
+            }
+            MergeAction::Update {
+                contact_id,
+                changes,
+                ..
+            } => {
+                updates += 1;
+                ("update", format!("#{}: {}", contact_id, changes.join("\n")))
+            }
+            MergeAction::Conflict(reason) => {
+                conflicts += 1;
+                ("conflict", reason.clone())
+            }
+            MergeAction::Unchanged => {
+                unchanged += 1;
+                continue;
+            }
+        };
+        table.add_row(vec![
+            Cell::new(action),
+            Cell::new(&entry.name),
+            Cell::new(details),
+        ]);
+    }
+    if adds + updates + conflicts > 0 {
+        println!("{}", table);
+    }
+    println!(
+        "{} to add, {} to update, {} conflict(s), {} unchanged.",
+        adds, updates, conflicts, unchanged
+    );
+    (adds, updates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contact, open_book, set};
+
+    #[test]
+    fn file_with_a_new_and_a_changed_contact_is_reconciled() {
+        let mut book = open_book();
+        set("REQUIRED_FIELDS", "first_name");
+        let john = book.add(contact("John", "Doe", "5551234567", "")).unwrap();
+        let ann = book
+            .add(contact("Ann", "Lee", "", "ann@example.com"))
+            .unwrap();
+        let mut moved = contact("Ann", "Lee", "", "ANN@example.com");
+        moved.address = "2 Side St".to_string();
+        let incoming = vec![
+            contact("John", "Doe", "(555) 123-4567", "john@example.com"),
+            contact("Jane", "Roe", "5559876543", ""),
+            moved,
+            contact("Ann", "Lee", "", ""),
+        ];
+
+        let entries = PhoneBook::plan_merge(incoming).unwrap();
+        let actions: Vec<(&str, Option<i32>)> = entries
+            .iter()
+            .map(|entry| match &entry.action {
+                MergeAction::Add(_) => ("add", None),
+                MergeAction::Update { contact_id, .. } => ("update", Some(*contact_id)),
+                MergeAction::Unchanged => ("unchanged", None),
+                MergeAction::Conflict(_) => ("conflict", None),
+            })
+            .collect();
+        assert_eq!(
+            actions,
+            [
+                ("update", Some(john)),
+                ("add", None),
+                ("update", Some(ann)),
+                ("add", None),
+            ]
+        );
+
+        PhoneBook::apply_merge(entries).unwrap();
+        let contacts = book.list(None).unwrap();
+        assert_eq!(contacts.len(), 4);
+        assert_eq!(book.get(john).unwrap().unwrap().email, "john@example.com");
+        assert_eq!(book.get(ann).unwrap().unwrap().address, "2 Side St");
+        assert_eq!(contacts[2].first_name, "Jane");
+        assert_eq!(contacts[2].phone, "(555) 987-6543");
+    }
+}
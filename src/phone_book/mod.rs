@@ -1,3 +1,35 @@
+pub mod audit;
+pub mod backup;
+pub mod bulk;
+pub mod clone;
 pub mod contact;
+pub mod contact_email;
+pub mod device_import;
+pub mod export;
+pub mod import_errors;
+pub mod import_rules;
+pub mod info;
+pub mod library;
+pub mod maintenance;
+pub mod merge;
 pub mod operations;
+pub mod owner;
+pub mod pagination;
+pub mod pdf;
+#[allow(clippy::module_inception)]
 pub mod phone_book;
+pub mod preferences;
+pub mod qr_code;
+pub mod reconnect;
+pub mod relationship;
+pub mod reports;
+pub mod saved_search;
+pub mod search;
+pub mod seed;
+pub mod tag;
+pub mod vcard;
+pub mod xlsx;
+
+pub use contact::Contact;
+pub use library::{Error, SortOrder};
+pub use phone_book::{Options, PhoneBook};
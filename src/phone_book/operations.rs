@@ -1,40 +1,372 @@
+use std::env;
 use std::fs::File;
 use std::io;
 use std::io::BufReader;
+use std::io::IsTerminal;
+use std::io::Read;
 use std::io::Write;
 
-use comfy_table::modifiers::UTF8_ROUND_CORNERS;
-use comfy_table::presets::UTF8_FULL;
 use comfy_table::{Cell, Table};
-use csv::ReaderBuilder;
+use crossterm::style::Stylize;
+use csv::{Reader, ReaderBuilder, StringRecord};
+use diesel::dsl::sql;
 use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Bool, Integer};
+use diesel::sqlite::SqliteConnection;
 use diesel::{delete, update};
+use encoding_rs::{Encoding, UTF_8};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use indicatif::{ProgressBar, ProgressStyle};
 
 use crate::connection::establish_connection;
-use crate::phone_book::contact::Contact;
+use crate::phone_book::audit::{AuditEntry, ContactSnapshot};
+use crate::phone_book::contact::{
+    fit_to_terminal, format_timestamp, is_valid_email, sanitize_text, split_full_name,
+    styled_table, truncate_display, Contact, ContactMethod, FIELDS,
+};
+use crate::phone_book::contact_email::ContactEmail;
+use crate::phone_book::import_errors::ImportErrors;
+use crate::phone_book::import_rules::{apply_import_rules, ImportRule};
+use crate::phone_book::library::SortOrder;
 use crate::phone_book::phone_book::PhoneBook;
-use crate::schema::contacts;
+use crate::phone_book::relationship::Relationship;
+use crate::phone_book::tag::Tag;
+use crate::schema::{contacts, emails};
+use crate::settings;
+use crate::shutdown::{self, BusyGuard};
+
+/// Message shown when an operation needs contacts but the phone book has none.
+const EMPTY_BOOK_MESSAGE: &str = "Your phone book is empty — add a contact with C.";
+
+/// Signature shared by every handler in the operations table.
+pub(crate) type Handler = fn(&mut PhoneBook);
 
 /// Define a list of operations available in the phone book.
-const OPERATIONS: &[(char, &str)] = &[
-    ('C', "Create"),
-    ('Q', "Fuzzy Query"),
-    ('F', "Upload contacts from a CSV file"),
-    ('U', "Update"),
-    ('D', "Delete"),
-    ('E', "Exit"),
-    ('L', "List in original order based on creation time"),
-    ('A', "List in ascending order"),
-    ('Z', "List in descending order"),
-    ('?', "Show available operations"),
+///
+/// Each entry maps a command key to its description and the handler that runs it. Both the help
+/// menu and the dispatcher in `start` are driven by this table, so a command can't be listed
+/// without a handler or handled without being listed.
+const OPERATIONS: &[(&str, &str, Handler)] = &[
+    ("C", "Create", PhoneBook::create_contact),
+    ("CL", "Clone a contact", PhoneBook::clone_contact),
+    ("Q", "Fuzzy Query", PhoneBook::search_contact),
+    ("P", "Search names by prefix", PhoneBook::search_name_prefix),
+    ("PL", "Look up a phone number", PhoneBook::look_up_phone),
+    (
+        "AS",
+        "Search by several field:value criteria",
+        PhoneBook::advanced_search,
+    ),
+    ("SS", "Save an advanced search", PhoneBook::save_search),
+    ("RS", "Run a saved search", PhoneBook::run_saved_search),
+    ("LS", "List saved searches", PhoneBook::list_saved_searches),
+    (
+        "F",
+        "Upload contacts from a CSV file",
+        PhoneBook::load_contacts_from_csv,
+    ),
+    (
+        "FJ",
+        "Import contacts from a phone's JSON export",
+        PhoneBook::import_device_contacts,
+    ),
+    (
+        "MF",
+        "Merge contacts from a CSV or JSON file",
+        PhoneBook::merge_from_file,
+    ),
+    (
+        "CP",
+        "Copy the names and phone numbers of matching contacts",
+        PhoneBook::copy_names_and_phones,
+    ),
+    ("CX", "Export contacts to a CSV file", PhoneBook::export_csv),
+    (
+        "XL",
+        "Export contacts to an Excel (XLSX) file",
+        PhoneBook::export_xlsx,
+    ),
+    (
+        "PX",
+        "Export contacts to a PDF document",
+        PhoneBook::export_pdf,
+    ),
+    ("TX", "Export contacts by tag", PhoneBook::export_by_tag),
+    (
+        "VC",
+        "Export contacts to a vCard file",
+        PhoneBook::export_vcard,
+    ),
+    (
+        "AX",
+        "Export an alphabetized directory for printing",
+        PhoneBook::export_directory,
+    ),
+    (
+        "PB",
+        "Export a two-column list of names and numbers for printing",
+        PhoneBook::export_printable_list,
+    ),
+    ("V", "Print a contact as vCard", PhoneBook::print_vcard),
+    (
+        "QR",
+        "Share a contact as a QR code",
+        PhoneBook::show_qr_code,
+    ),
+    ("U", "Update", PhoneBook::update_contact),
+    ("D", "Delete", PhoneBook::delete_contact),
+    (
+        "BU",
+        "Apply a change to all matching contacts",
+        PhoneBook::bulk_update_contacts,
+    ),
+    ("E", "Exit", PhoneBook::exit),
+    (
+        "L",
+        "List in the default order (DEFAULT_SORT, creation time if unset)",
+        PhoneBook::list_contacts,
+    ),
+    ("A", "List in ascending order", |phone_book| {
+        phone_book.list_contacts_in_order(Some(SortOrder::FirstNameAsc))
+    }),
+    ("Z", "List in descending order", |phone_book| {
+        phone_book.list_contacts_in_order(Some(SortOrder::FirstNameDesc))
+    }),
+    ("PA", "List by phone number, ascending", |phone_book| {
+        phone_book.list_contacts_in_order(Some(SortOrder::PhoneAsc))
+    }),
+    ("PD", "List by phone number, descending", |phone_book| {
+        phone_book.list_contacts_in_order(Some(SortOrder::PhoneDesc))
+    }),
+    ("CA", "List by company, ascending", |phone_book| {
+        phone_book.list_contacts_in_order(Some(SortOrder::CompanyAsc))
+    }),
+    ("CD", "List by company, descending", |phone_book| {
+        phone_book.list_contacts_in_order(Some(SortOrder::CompanyDesc))
+    }),
+    (
+        "LT",
+        "List contacts with any or all of several tags",
+        PhoneBook::list_contacts_by_tags,
+    ),
+    ("R", "Show a random contact", PhoneBook::show_random_contact),
+    (
+        "RL",
+        "Add a relationship between two contacts",
+        PhoneBook::link_contacts,
+    ),
+    (
+        "NP",
+        "Normalize all phone numbers",
+        PhoneBook::normalize_all_phones,
+    ),
+    (
+        "NE",
+        "Normalize all emails to lowercase",
+        PhoneBook::normalize_all_emails,
+    ),
+    ("S", "Configure settings", PhoneBook::configure),
+    (
+        "INFO",
+        "Show the database path, size and schema version",
+        PhoneBook::show_info,
+    ),
+    ("SO", "Set the owner profile", PhoneBook::set_owner),
+    (
+        "W",
+        "Show the owner profile (whoami)",
+        PhoneBook::show_owner,
+    ),
+    (
+        "DD",
+        "Remove duplicate contacts",
+        PhoneBook::dedupe_contacts,
+    ),
+    (
+        "VA",
+        "Report contacts failing validation",
+        PhoneBook::validate_all,
+    ),
+    (
+        "SW",
+        "Find and fix swapped first/last names",
+        PhoneBook::fix_swapped_names,
+    ),
+    (
+        "AC",
+        "Report contacts by area code",
+        PhoneBook::report_by_area_code,
+    ),
+    (
+        "CO",
+        "Report contacts by company",
+        PhoneBook::report_by_company,
+    ),
+    (
+        "SP",
+        "Report phone numbers shared by different names",
+        PhoneBook::report_shared_phones,
+    ),
+    (
+        "MC",
+        "Mark a contact as contacted today",
+        PhoneBook::mark_contacted,
+    ),
+    (
+        "RC",
+        "List contacts to reconnect with",
+        PhoneBook::list_reconnect,
+    ),
+    ("AU", "Show the audit log", PhoneBook::show_audit),
+    (
+        "AE",
+        "Export the audit log to a CSV file",
+        PhoneBook::export_audit_csv,
+    ),
+    (
+        "RV",
+        "Revert a change from the audit log",
+        PhoneBook::revert_audit_entry,
+    ),
+    ("?", "Show available operations", |phone_book| {
+        phone_book.show_operations()
+    }),
 ];
 
+/// Input that aborts the operation in progress when typed at any of its prompts.
+pub(crate) const CANCEL_TOKEN: &str = ":q";
+
+/// Input that empties a field instead of keeping the value it is pre-filled with.
+pub(crate) const CLEAR_TOKEN: &str = "-";
+
+/// Marker returned by prompts when the user types the cancel token.
+pub(crate) struct Cancelled;
+
+/// Number of parsed rows shown before an import is confirmed.
+const IMPORT_PREVIEW_ROWS: usize = 5;
+
+/// CSV reader over an imported file, transcoded to UTF-8.
+pub(crate) type ImportReader = Reader<Box<dyn Read>>;
+
+/// Number of imported contacts saved per transaction, so large imports are fast without holding
+/// the whole file in memory.
+const IMPORT_BATCH_SIZE: usize = 500;
+
+/// Headers of a single column holding whole names, compared ignoring case, which the CSV import
+/// splits when the file has no first or last name column.
+const FULL_NAME_HEADERS: &[&str] = &["name", "full_name", "full name", "fullname", "display name"];
+
+// Reject a table that maps the same key twice, or a key `start` could never match, at compile time.
+const _: () = assert!(
+    has_unique_uppercase_keys(OPERATIONS),
+    "operation keys must be unique and uppercase"
+);
+
+/// Formats a field of a contact for a listing.
+///
+/// # Parameters
+///
+/// * `contact` - The listed contact.
+/// * `field` - The column name of the field.
+/// * `widths` - The maximum widths of the `COLUMN_WIDTHS` setting.
+/// * `placeholder` - The text shown for an empty field.
+///
+/// # Returns
+///
+/// The value, cut with an ellipsis if it is longer than the field's width, or `placeholder` if
+/// it is empty.
+fn listed_value(
+    contact: &Contact,
+    field: &str,
+    widths: &[(String, usize)],
+    placeholder: &str,
+) -> String {
+    let value = contact.field(field);
+    if value.is_empty() {
+        return placeholder.to_string();
+    }
+    match widths.iter().find(|(name, _)| name == field) {
+        Some(&(_, width)) => truncate_display(value, width),
+        None => value.to_string(),
+    }
+}
+
+/// Lists CSV columns for the user, numbered from 1 as spreadsheets show them.
+///
+/// # Parameters
+///
+/// * `indexes` - The indexes of the columns, from 0.
+///
+/// # Returns
+///
+/// The column numbers, e.g. `3, 5`.
+fn column_numbers(indexes: &[usize]) -> String {
+    indexes
+        .iter()
+        .map(|index| (index + 1).to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Finds a contact field in `FIELDS`, where the values entered for a contact are kept in order.
+///
+/// # Parameters
+///
+/// * `field` - The column name of the field, e.g. from the `PROMPT_ORDER` setting.
+///
+/// # Returns
+///
+/// The position of the field in `FIELDS` and its human-readable name.
+pub(crate) fn field_position(field: &str) -> (usize, &'static str) {
+    FIELDS
+        .iter()
+        .enumerate()
+        .find(|(_, &(name, _))| name == field)
+        .map(|(position, &(_, label))| (position, label))
+        .expect("settings only list contact fields")
+}
+
+/// Checks that every key in the operations table is uppercase and appears only once.
+///
+/// This is a `const fn` so the check runs at compile time; `str` comparison isn't available in
+/// const contexts, hence the byte-wise loops.
+const fn has_unique_uppercase_keys(operations: &[(&str, &str, Handler)]) -> bool {
+    let mut i = 0;
+    while i < operations.len() {
+        let key = operations[i].0.as_bytes();
+        let mut k = 0;
+        while k < key.len() {
+            if key[k].is_ascii_lowercase() {
+                return false;
+            }
+            k += 1;
+        }
+        let mut j = i + 1;
+        while j < operations.len() {
+            let other = operations[j].0.as_bytes();
+            if key.len() == other.len() {
+                let mut k = 0;
+                while k < key.len() && key[k] == other[k] {
+                    k += 1;
+                }
+                if k == key.len() {
+                    return false;
+                }
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
 impl PhoneBook {
     /// Creates a new contact by prompting the user for contact information.
     ///
     /// This method interactively asks the user to input various details for a new contact,
-    /// including first name, last name, phone number, email, and address. It then creates
-    /// a new `Contact` struct with this information and adds it to the phone book.
+    /// including first name, last name, company, phone number and extension, email, address,
+    /// website and preferred contact method. It then creates a new `Contact` struct with this
+    /// information and adds it to the phone book.
     ///
     /// # Arguments
     ///
@@ -42,76 +374,276 @@ impl PhoneBook {
     ///
     /// # Effects
     ///
-    /// - Prompts the user for contact information.
-    /// - Creates a new `Contact` instance if all required fields are provided.
+    /// - Prompts the user for contact information, including any additional labeled emails and
+    ///   tags. Tags are completed against the existing ones.
+    /// - Prompts for a field again when its value is invalid, e.g. a malformed email.
+    /// - Creates a new `Contact` instance if all required fields are provided and every field is
+    ///   within its maximum length.
     /// - Adds the new contact to the phone book's contacts list.
     ///
     /// # Notes
     ///
-    /// - First name and phone number are required fields. If either is left empty,
-    ///   the contact creation is cancelled, and an appropriate message is set.
-    /// - Last name, email, and address are optional fields.
+    /// - The fields listed in the `REQUIRED_FIELDS` setting (first name and phone number by
+    ///   default) are required. If one is left empty, the contact creation is cancelled, and an
+    ///   appropriate message is printed.
+    /// - The remaining fields are optional.
+    /// - Only the fields of the `PROMPT_ORDER` setting are prompted for, in its order; the others
+    ///   are left empty.
+    /// - Typing the cancel token (`:q`) at any prompt cancels the contact creation.
     pub fn create_contact(&mut self) {
-        let first_name = Self::get_input("Enter first name (required): ");
-        if first_name.is_empty() {
-            println!("First name is required. Contact creation cancelled.");
-            return;
+        let required_fields = match settings::required_fields() {
+            Ok(required_fields) => required_fields,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let prompt_order = match settings::prompt_order() {
+            Ok(prompt_order) => prompt_order,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        Self::print_cancel_hint();
+        let mut values = vec![String::new(); FIELDS.len()];
+        for field in &prompt_order {
+            let (position, label) = field_position(field);
+            let Ok(value) = Self::prompt_field("Enter", field, label, &required_fields, "") else {
+                println!("Contact creation cancelled.");
+                return;
+            };
+            if value.is_empty() && required_fields.contains(field) {
+                println!("{} is required. Contact creation cancelled.", label);
+                return;
+            }
+            values[position] = value;
         }
-        let last_name = Self::get_input("Enter last name (optional): ");
-        let phone_number = Self::get_input("Enter phone number (required): ");
-        if phone_number.is_empty() {
-            println!("Phone number is required. Contact creation cancelled.");
+        let new_contact = Self::contact_from_values(values);
+        if let Err(err) = new_contact.validate() {
+            println!("{} Contact creation cancelled.", err);
             return;
         }
-        let email = Self::get_input("Enter email (optional): ");
-        let address = Self::get_input("Enter address (optional): ");
-        let new_contact = Contact::new(first_name, last_name, email, address, phone_number);
+        let Ok(additional_emails) = Self::prompt_additional_emails() else {
+            println!("Contact creation cancelled.");
+            return;
+        };
+        let Ok(tags) = Self::prompt_tags("Enter tags") else {
+            println!("Contact creation cancelled.");
+            return;
+        };
         new_contact.print_contact();
-        Self::add_contact(new_contact);
+        ContactEmail::print_emails(&additional_emails);
+        if let Err(err) =
+            Self::insert_contact_with(&new_contact, &additional_emails, &tags, "create")
+        {
+            println!("Error saving new contact: {}", err);
+            return;
+        }
         println!("Contact created successfully!");
     }
+    /// Prompts the user for one contact field, marking it as required or optional.
+    ///
+    /// A value failing the validation rules, such as a malformed email or a phone number with too
+    /// few digits, is reported and prompted for again on the spot, whether the input comes from a
+    /// terminal or is piped in, so no invalid value is ever saved. The preferred contact method is
+    /// prompted for again until it is one of the allowed methods or left empty, and is returned as
+    /// the method's key.
+    ///
+    /// # Arguments
+    ///
+    /// * `verb` - The start of the prompt, e.g. "Enter" or "Enter new".
+    /// * `field` - The column name of the field being prompted for.
+    /// * `label` - The human-readable name of the field.
+    /// * `required_fields` - The fields that must not be left empty.
+    /// * `current` - The value the field is pre-filled with, kept if the input is left empty and
+    ///   cleared with `CLEAR_TOKEN`, or an empty string for none.
+    ///
+    /// # Returns
+    ///
+    /// The entered value, or `Err(Cancelled)` if the user typed the cancel token.
+    pub(crate) fn prompt_field(
+        verb: &str,
+        field: &str,
+        label: &str,
+        required_fields: &[String],
+        current: &str,
+    ) -> Result<String, Cancelled> {
+        let required = required_fields.iter().any(|required| required == field);
+        let mut hint = if required { "required" } else { "optional" }.to_string();
+        if field == "preferred_method" {
+            hint.push_str(&format!(", {}", ContactMethod::keys()));
+        }
+        if !current.is_empty() {
+            hint.push_str(&format!(
+                ", empty keeps '{}', {} clears",
+                current, CLEAR_TOKEN
+            ));
+        }
+        let prompt = format!("{} {} ({}): ", verb, label.to_lowercase(), hint);
+        loop {
+            let value = Self::get_input_or_cancel(&prompt)?;
+            if value.is_empty() {
+                return Ok(current.to_string());
+            }
+            if value == CLEAR_TOKEN && !current.is_empty() {
+                return Ok(String::new());
+            }
+            if field == "preferred_method" {
+                match ContactMethod::parse(&value) {
+                    Some(method) => return Ok(method.key().to_string()),
+                    None => println!(
+                        "Invalid contact method '{}'. Enter one of {}.",
+                        value,
+                        ContactMethod::keys()
+                    ),
+                }
+                continue;
+            }
+            let problems = Contact::field_problems(field, &value);
+            if problems.is_empty() {
+                return Ok(value);
+            }
+            for problem in problems {
+                println!("{}", problem);
+            }
+        }
+    }
+    /// Builds a `Contact` from field values entered in the order of `FIELDS`.
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - The first name, last name, company, phone number, extension, email, address,
+    ///   website and preferred contact method, in that order.
+    ///
+    /// # Returns
+    ///
+    /// A new `Contact` created through `Contact::new`, so the phone number is standardized.
+    pub(crate) fn contact_from_values(values: Vec<String>) -> Contact {
+        let [
+            first_name,
+            last_name,
+            company,
+            phone_number,
+            extension,
+            email,
+            address,
+            website,
+            preferred_method,
+        ]: [String; 9] = values
+            .try_into()
+            .expect("one value is entered per contact field");
+        Contact {
+            preferred_method: sanitize_text(&preferred_method),
+            company: sanitize_text(&company),
+            website: sanitize_text(&website),
+            extension: sanitize_text(&extension),
+            ..Contact::new(first_name, last_name, email, address, phone_number)
+        }
+    }
     /// Lists the contacts in the phone book in the specified order.
     ///
     /// # Parameters
     ///
     /// * `self` - A mutable reference to the `PhoneBook` instance.
-    /// * `order` - A string slice representing the order in which to list the contacts.
-    ///               It can be either "asc" for ascending order or "desc" for descending order.
+    /// * `order` - The order in which to list the contacts, or `None` for the order set by
+    ///   `DEFAULT_SORT`.
     ///
     /// # Return
     ///
-    /// This function does not return any value. It prints the table of contacts to the console.
-    pub fn list_contacts_in_order(&mut self, order: &str) {
-        match Self::get_contacts(order) {
+    /// This function does not return any value. It prints the table of contacts to the console, a
+    /// page at a time if they don't fit on one.
+    pub fn list_contacts_in_order(&mut self, order: Option<SortOrder>) {
+        if Self::report_if_empty() {
+            self.contacts.clear();
+            return;
+        }
+        match self.list(order) {
             Ok(contacts) => {
                 self.contacts = contacts;
-                Self::print_contacts(&self.contacts);
+                self.print_contact_pages(&SortOrder::key_or_default(order));
             }
             Err(err) => {
                 println!("Error fetching contacts from the database: {}", err);
             }
         }
     }
+    /// Lists the contacts in the order set by `DEFAULT_SORT`, or in creation order if it is unset.
+    ///
+    /// # Parameters
+    ///
+    /// * `self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the table of contacts to the console.
+    pub fn list_contacts(&mut self) {
+        self.list_contacts_in_order(None);
+    }
+    /// Loads every contact in the given order.
+    ///
+    /// # Parameters
+    ///
+    /// * `order` - "asc" or "desc" to sort by first name, one of `settings::SORT_KEYS`, e.g.
+    ///   `phone_asc` to sort by phone number or `company_asc` to group by company, or anything
+    ///   else for creation order.
+    ///
+    /// # Returns
+    ///
+    /// The contacts, in the requested order.
     pub fn get_contacts(order: &str) -> QueryResult<Vec<Contact>> {
         let mut connection = establish_connection();
-        let contacts_result = match order {
-            "asc" => contacts::table
-                .order(contacts::first_name.asc())
+        match order {
+            "asc" | "first_asc" => contacts::table
+                .order((contacts::first_name.asc(), contacts::last_name.asc()))
                 .load::<Contact>(&mut connection),
-            "desc" => contacts::table
-                .order(contacts::first_name.desc())
+            "desc" | "first_desc" => contacts::table
+                .order((contacts::first_name.desc(), contacts::last_name.desc()))
+                .load::<Contact>(&mut connection),
+            "last_asc" => contacts::table
+                .order((contacts::last_name.asc(), contacts::first_name.asc()))
+                .load::<Contact>(&mut connection),
+            "last_desc" => contacts::table
+                .order((contacts::last_name.desc(), contacts::first_name.desc()))
+                .load::<Contact>(&mut connection),
+            "created_desc" => contacts::table
+                .order((contacts::created_at.desc(), contacts::id.desc()))
+                .load::<Contact>(&mut connection),
+            // `phone_digits` holds the digits only, so formatting doesn't affect the order. Numbers
+            // are compared by value, and those without digits are listed last.
+            "phone_asc" => contacts::table
+                .order(sql::<Bool>("phone_digits = ''"))
+                .then_order_by(sql::<BigInt>("CAST(phone_digits AS INTEGER)").asc())
+                .then_order_by((contacts::first_name.asc(), contacts::last_name.asc()))
+                .load::<Contact>(&mut connection),
+            "phone_desc" => contacts::table
+                .order(sql::<Bool>("phone_digits = ''"))
+                .then_order_by(sql::<BigInt>("CAST(phone_digits AS INTEGER)").desc())
+                .then_order_by((contacts::first_name.asc(), contacts::last_name.asc()))
+                .load::<Contact>(&mut connection),
+            // Contacts of the same company are grouped, and those without one are listed last.
+            "company_asc" => contacts::table
+                .order(contacts::company.eq(""))
+                .then_order_by(contacts::company.asc())
+                .then_order_by((contacts::last_name.asc(), contacts::first_name.asc()))
+                .load::<Contact>(&mut connection),
+            "company_desc" => contacts::table
+                .order(contacts::company.eq(""))
+                .then_order_by(contacts::company.desc())
+                .then_order_by((contacts::last_name.asc(), contacts::first_name.asc()))
                 .load::<Contact>(&mut connection),
             _ => contacts::table.load::<Contact>(&mut connection),
-        };
-        contacts_result
+        }
     }
     /// Displays a list of stored contacts in the phone book.
     ///
     /// This function iterates through the contacts stored in the phone book and displays them in a formatted table.
     /// If no contacts are found, it prints a message indicating that no contacts were found.
+    /// The creation and last update times are only shown when the `--show-timestamps` option is set.
     ///
     /// # Parameters
     ///
+    /// * `&self` - A reference to the `PhoneBook` instance.
     /// * `contacts` - A slice of `Contact` instances representing the contacts to be displayed.
     ///
     /// # Return
@@ -120,40 +652,163 @@ impl PhoneBook {
     ///
     /// # Example
     ///
-    /// ```rust
+    /// ```ignore
     /// let mut phone_book = PhoneBook::new();
     /// phone_book.add_contact(Contact::new("John", "Doe", "john@example.com", "123 Main St", "1234567890"));
-    /// PhoneBook::print_contacts(&phone_book.contacts);
+    /// phone_book.print_contacts(&phone_book.contacts);
     /// ```
-    fn print_contacts(contacts: &Vec<Contact>) {
+    pub(crate) fn print_contacts(&self, contacts: &[Contact]) {
         if contacts.is_empty() {
             println!("No contacts found.");
             return;
         }
-        let mut table = Table::new();
-        table
-            .load_preset(UTF8_FULL)
-            .apply_modifier(UTF8_ROUND_CORNERS)
-            .set_header(vec![
-                Cell::new("#").add_attribute(comfy_table::Attribute::Bold),
-                Cell::new("First Name").add_attribute(comfy_table::Attribute::Bold),
-                Cell::new("Last Name").add_attribute(comfy_table::Attribute::Bold),
-                Cell::new("Phone Number").add_attribute(comfy_table::Attribute::Bold),
-                Cell::new("Email").add_attribute(comfy_table::Attribute::Bold),
-                Cell::new("Address").add_attribute(comfy_table::Attribute::Bold),
-            ]);
+        println!("{}", self.contacts_listing(contacts, 0));
+    }
+    /// Renders contacts as a table, or one per line if the `--compact` option is set.
+    ///
+    /// # Parameters
+    ///
+    /// * `&self` - A reference to the `PhoneBook` instance.
+    /// * `contacts` - The contacts to list.
+    /// * `skipped` - How many contacts precede them in the full list, so they are numbered from
+    ///   `skipped + 1`.
+    ///
+    /// # Returns
+    ///
+    /// The listing, without a trailing line break.
+    pub(crate) fn contacts_listing(&self, contacts: &[Contact], skipped: usize) -> String {
+        if self.options.compact {
+            self.compact_listing(contacts, skipped)
+        } else {
+            self.contacts_table(contacts, skipped).to_string()
+        }
+    }
+    /// Renders contacts one per line, e.g. `#3  John Doe  (123) 456-7890  john@x.com`, with
+    /// the columns aligned. This suits small terminals and piping through `grep`.
+    ///
+    /// # Parameters
+    ///
+    /// * `&self` - A reference to the `PhoneBook` instance.
+    /// * `contacts` - The contacts to list.
+    /// * `skipped` - How many contacts precede them in the full list, so they are numbered from
+    ///   `skipped + 1` as in the table.
+    ///
+    /// # Returns
+    ///
+    /// The lines, with creation and update times if the `--show-timestamps` option is set. Empty
+    /// fields show the `EMPTY_PLACEHOLDER` setting, and values longer than their `COLUMN_WIDTHS`
+    /// width are cut with an ellipsis.
+    pub(crate) fn compact_listing(&self, contacts: &[Contact], skipped: usize) -> String {
+        let placeholder = settings::empty_placeholder();
+        // The setting is validated at startup, so values are shown whole if it has since changed.
+        let widths = settings::column_widths().unwrap_or_default();
+        let field =
+            |contact: &Contact, name: &str| listed_value(contact, name, &widths, &placeholder);
+        let rows: Vec<Vec<String>> = contacts
+            .iter()
+            .enumerate()
+            .map(|(index, contact)| {
+                let name = format!(
+                    "{} {}",
+                    listed_value(contact, "first_name", &widths, ""),
+                    listed_value(contact, "last_name", &widths, "")
+                );
+                let mut row = vec![
+                    format!("#{}", skipped + index + 1),
+                    name.trim().to_string(),
+                    field(contact, "phone"),
+                    field(contact, "email"),
+                ];
+                if self.options.show_timestamps {
+                    row.push(format_timestamp(&contact.created_at));
+                    row.push(format_timestamp(&contact.updated_at));
+                }
+                row
+            })
+            .collect();
+        let mut widths = vec![0; rows.first().map_or(0, Vec::len)];
+        for row in &rows {
+            for (width, value) in widths.iter_mut().zip(row) {
+                *width = (*width).max(value.chars().count());
+            }
+        }
+        rows.iter()
+            .map(|row| {
+                let line: Vec<String> = row
+                    .iter()
+                    .zip(&widths)
+                    .map(|(value, &width)| format!("{:<width$}", value, width = width))
+                    .collect();
+                line.join("  ").trim_end().to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+    /// Builds the table listing contacts, with their numbers.
+    ///
+    /// # Parameters
+    ///
+    /// * `&self` - A reference to the `PhoneBook` instance.
+    /// * `contacts` - The contacts to list.
+    /// * `skipped` - How many contacts precede them in the full list, so they are numbered from
+    ///   `skipped + 1`.
+    ///
+    /// # Returns
+    ///
+    /// The table, fitted to the terminal width, with a company column if any of the contacts has
+    /// one, and creation and update times if the `--show-timestamps` option is set. Empty fields
+    /// show the `EMPTY_PLACEHOLDER` setting, and values longer than their `COLUMN_WIDTHS` width
+    /// are cut with an ellipsis.
+    pub(crate) fn contacts_table(&self, contacts: &[Contact], skipped: usize) -> Table {
+        let show_company = contacts.iter().any(|contact| !contact.company.is_empty());
+        let mut header = vec![
+            Cell::new("#").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("First Name").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Last Name").add_attribute(comfy_table::Attribute::Bold),
+        ];
+        if show_company {
+            header.push(Cell::new("Company").add_attribute(comfy_table::Attribute::Bold));
+        }
+        header.extend([
+            Cell::new("Phone Number").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Email").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Address").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        if self.options.show_timestamps {
+            header.push(Cell::new("Created").add_attribute(comfy_table::Attribute::Bold));
+            header.push(Cell::new("Updated").add_attribute(comfy_table::Attribute::Bold));
+        }
+        let mut table = styled_table();
+        table.set_header(header);
 
+        let placeholder = settings::empty_placeholder();
+        // The setting is validated at startup, so values are shown whole if it has since changed.
+        let widths = settings::column_widths().unwrap_or_default();
+        let field_cell = |contact: &Contact, field: &str| {
+            Cell::new(listed_value(contact, field, &widths, &placeholder))
+        };
         for (index, contact) in contacts.iter().enumerate() {
-            table.add_row(vec![
-                Cell::new(format!("{}", index + 1)),
-                Cell::new(&contact.first_name),
-                Cell::new(&contact.last_name),
-                Cell::new(&contact.phone),
-                Cell::new(&contact.email),
-                Cell::new(&contact.address),
+            let mut row = vec![
+                Cell::new(format!("{}", skipped + index + 1)),
+                field_cell(contact, "first_name"),
+                field_cell(contact, "last_name"),
+            ];
+            if show_company {
+                row.push(field_cell(contact, "company"));
+            }
+            row.extend([
+                field_cell(contact, "phone"),
+                field_cell(contact, "email"),
+                field_cell(contact, "address"),
             ]);
+            if self.options.show_timestamps {
+                row.push(Cell::new(format_timestamp(&contact.created_at)));
+                row.push(Cell::new(format_timestamp(&contact.updated_at)));
+            }
+            table.add_row(row);
         }
-        println!("{}", table);
+        fit_to_terminal(&mut table);
+        table
     }
     /// Prompts the user for input and returns the entered string.
     ///
@@ -170,304 +825,1684 @@ impl PhoneBook {
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```ignore
     /// let name = get_input("Enter your name: ");
     /// println!("Hello, {}!", name);
     /// ```
     pub(crate) fn get_input(prompt: &str) -> String {
-        print!("{}", prompt);
+        print!("{}", Self::styled_prompt(prompt));
         io::stdout().flush().unwrap();
         let mut input = String::new();
         io::stdin().read_line(&mut input).unwrap();
         input.trim().to_string()
     }
-    /// Adds a new contact to the phone book's contacts list.
+    /// Renders a prompt with the `PROMPT_PREFIX` and `PROMPT_COLOR` settings, separating the
+    /// prefix from the prompt with a space.
     ///
-    /// This method takes a `Contact` struct as an argument and adds it to the `contacts` vector.
+    /// The color is left out when the `NO_COLOR` environment variable is set or standard output
+    /// isn't a terminal, so piped output has no escape codes.
     ///
     /// # Arguments
     ///
-    /// * `self` - A mutable reference to the `PhoneBook` instance.
-    /// * `contact` - A `Contact` struct representing the new contact to be added.
+    /// * `prompt` - The text of the prompt.
     ///
-    /// # Return
+    /// # Returns
     ///
-    /// This function does not return any value. The `contacts` vector of the `PhoneBook` instance is updated.
-    pub fn add_contact(contact: Contact) {
-        use crate::schema::contacts;
-        let mut connection = establish_connection();
-        diesel::insert_into(contacts::table)
-            .values(&contact)
-            .execute(&mut connection)
-            .expect("Error saving new contact");
+    /// The prompt as printed.
+    fn styled_prompt(prompt: &str) -> String {
+        let prefix = settings::prompt_prefix();
+        let prompt = if prefix.is_empty() {
+            prompt.to_string()
+        } else {
+            format!("{} {}", prefix, prompt)
+        };
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        match settings::prompt_color() {
+            Ok(Some(color)) if !no_color && io::stdout().is_terminal() => {
+                prompt.with(color).to_string()
+            }
+            _ => prompt,
+        }
     }
-    /// Displays the available operations in a table format for the `PhoneBook` struct.
-    ///
-    /// This function creates a new `Table` instance, sets the table header, adds rows for each operation,
-    /// and adds a footer to display the last command. It then prints the table to the console.
+    /// Prompts the user for input like `get_input`, but lets them abort the current operation.
     ///
     /// # Arguments
     ///
-    /// * `&self` - self A reference to the `PhoneBook` instance.
+    /// * `prompt` - A string slice that holds the text to be displayed as the input prompt.
     ///
-    /// # Return
+    /// # Returns
     ///
-    /// This function does not return any value. It prints the table to the console.
-    pub fn show_operations(&self) {
-        let mut table = Table::new();
-        table
-            .load_preset(UTF8_FULL)
-            .apply_modifier(UTF8_ROUND_CORNERS)
-            .set_header(vec![
-                Cell::new("Option").add_attribute(comfy_table::Attribute::Bold),
-                Cell::new("Description").add_attribute(comfy_table::Attribute::Bold),
-            ]);
-        // Add the operations as rows in the table
-        for &(option, description) in OPERATIONS {
-            table.add_row(vec![
-                Cell::new(format!("{} | {}", option, option.to_lowercase())),
-                Cell::new(description),
-            ]);
+    /// The user's trimmed input, or `Err(Cancelled)` if they typed the cancel token (`:q`).
+    pub(crate) fn get_input_or_cancel(prompt: &str) -> Result<String, Cancelled> {
+        let input = Self::get_input(prompt);
+        if input == CANCEL_TOKEN {
+            Err(Cancelled)
+        } else {
+            Ok(input)
         }
-        // Print the table
-        println!("{}", table);
     }
-    /// Deletes a contact from the phone book's contacts list based on the provided index.
-    ///
-    /// This method takes an index as an argument and removes the contact at that index from the `contacts` vector.
-    /// If the index is out of bounds, it prints an error message.
+    /// Prompts for the index of a contact in the last listing, asking again until the user enters
+    /// a valid one or cancels. Input that isn't a number and numbers outside the listing are
+    /// reported differently.
     ///
     /// # Arguments
     ///
-    /// * `self` - A mutable reference to the `PhoneBook` instance.
-    /// * `index` - An integer representing the index of the contact to be deleted.
+    /// * `&self` - A reference to the `PhoneBook` instance, whose `contacts` are the last listing.
+    /// * `prompt` - A string slice that holds the text to be displayed as the input prompt.
     ///
-    /// # Return
+    /// # Returns
     ///
-    /// This function does not return any value. The `contacts` vector of the `PhoneBook` instance is updated.
-    pub fn delete_contact(&mut self) {
-        let index_result =
-            Self::get_input("Enter the index of the contact to delete: ").parse::<usize>();
-        if index_result.is_err() {
-            println!("Invalid contact index!");
-            return;
-        }
-        let index = index_result.unwrap();
-        if index < 1 || index > self.contacts.len() {
-            println!("Invalid contact index!");
-            return;
+    /// The 1-based index of the contact, or `Err(Cancelled)` if the user typed the cancel token
+    /// or no contacts are listed.
+    pub(crate) fn prompt_contact_index(&self, prompt: &str) -> Result<usize, Cancelled> {
+        let count = self.contacts.len();
+        if count == 0 {
+            println!("No contacts are listed. List them first, e.g. with L, to see their indexes.");
+            return Err(Cancelled);
         }
-        self.contacts[index - 1].print_contact();
-        let confirm = Self::get_input("Are you sure you want to delete this contact? (y/n): ");
-        if confirm.to_lowercase() == "y" {
-            let contact = &self.contacts[index - 1];
-            let mut conn = establish_connection();
-            match delete(contacts::table.filter(contacts::id.eq(contact.id))).execute(&mut conn) {
-                Ok(_) => {
-                    self.contacts.remove(index - 1);
-                    println!("Contact at index {} deleted successfully.", index);
-                }
-                Err(e) => {
-                    println!("Error deleting contact from database: {}", e);
-                }
+        loop {
+            let input = Self::get_input_or_cancel(prompt)?;
+            match input.parse::<usize>() {
+                Ok(index) if (1..=count).contains(&index) => return Ok(index),
+                Ok(index) => println!(
+                    "There is no contact #{}: enter a number from 1 to {}, or {} to cancel.",
+                    index, count, CANCEL_TOKEN
+                ),
+                Err(_) => println!(
+                    "'{}' is not a number: enter the # shown in the listing, or {} to cancel.",
+                    input, CANCEL_TOKEN
+                ),
             }
-        } else {
-            println!("Contact deletion cancelled.");
         }
     }
-    /// Updates a contact in the phone book's contacts list based on the provided index.
-    ///
-    /// This function prompts the user to enter the index of the contact to update, validates the input,
-    /// and then asks for new contact details. If the index is valid and all required fields are provided,
-    /// the contact at the specified index is updated with the new details.
-    ///
-    /// # Parameters
+    /// Checks whether the phone book has no contacts and, if so, tells the user how to add one.
     ///
-    /// * `self` - A mutable reference to the `PhoneBook` instance.
+    /// Operations that need contacts call this first, so an empty phone book gets the same
+    /// helpful message everywhere.
     ///
-    /// # Return
+    /// # Returns
     ///
-    /// This function does not return any value. The `contacts` vector of the `PhoneBook` instance is updated.
-    pub(crate) fn update_contact(&mut self) {
-        let index_result =
-            Self::get_input("Enter the index of the contact to update: ").parse::<usize>();
-        if index_result.is_err() {
-            println!("Invalid contact index!");
-            return;
-        }
-        let index = index_result.unwrap();
-        if index < 1 || index > self.contacts.len() {
-            println!("Invalid contact index!");
-            return;
-        }
-        self.contacts[index - 1].print_contact();
-        println!("Updating contact details...");
-        let new_first_name = Self::get_input("Enter new first name: ");
-        if new_first_name.is_empty() {
-            println!("First name is required. Contact update cancelled.");
-            return;
-        }
-        let new_last_name = Self::get_input("Enter new last name: ");
-        let new_phone_number = Self::get_input("Enter new phone number: ");
-        if new_phone_number.is_empty() {
-            println!("Phone number is required. Contact update cancelled.");
-            return;
-        }
-        let new_email = Self::get_input("Enter new email: ");
-        let new_address = Self::get_input("Enter new address: ");
-        let mut updated_contact = Contact::new(
-            new_first_name,
-            new_last_name,
-            new_email,
-            new_address,
-            new_phone_number,
-        );
-        let mut conn = establish_connection();
-        let contact_id_result = self.contacts.get(index - 1).map(|c| c.id);
-
-        if let Some(contact_id) = contact_id_result {
-            updated_contact.id = contact_id;
-            let num_rows_updated = update(contacts::table)
-                .filter(contacts::id.eq(contact_id))
-                .set((
-                    contacts::first_name.eq(&updated_contact.first_name),
-                    contacts::last_name.eq(&updated_contact.last_name),
-                    contacts::email.eq(&updated_contact.email),
-                    contacts::address.eq(&updated_contact.address),
-                    contacts::phone.eq(&updated_contact.phone),
-                ))
-                .execute(&mut conn);
-
-            match num_rows_updated {
-                Ok(_) => {
-                    println!("Contact updated successfully!");
-                }
-                Err(err) => {
-                    println!("Error updating contact: {}", err);
-                }
-            }
-            self.contacts[index - 1] = updated_contact;
-        } else {
-            println!("Invalid contact ID.");
+    /// `true` if the phone book is empty. If the contacts can't be counted, `false` is returned
+    /// so the operation reports the database error itself.
+    pub(crate) fn report_if_empty() -> bool {
+        let mut connection = establish_connection();
+        let empty = contacts::table
+            .count()
+            .get_result::<i64>(&mut connection)
+            .is_ok_and(|count| count == 0);
+        if empty {
+            println!("{}", EMPTY_BOOK_MESSAGE);
         }
+        empty
     }
-    /// Searches for contacts in the phone book based on a given search query.
+    /// Expands a leading `~` and environment variables such as `$HOME` in a path entered by the
+    /// user, as a shell would.
     ///
-    /// This function takes a search query as input, converts it to lowercase, and then iterates through
-    /// the list of contacts in the phone book. If any contact's first name, last name, email, address,
-    /// or phone number contains the search query, the contact is added to a new vector of found contacts.
+    /// # Arguments
     ///
-    /// If no contacts are found matching the search query, a message is printed to the console indicating
-    /// that no contacts were found. Otherwise, the found contacts are displayed using the `print_contacts`
-    /// function.
+    /// * `path` - The path as entered by the user.
     ///
-    /// # Parameters
+    /// # Returns
     ///
-    /// * `&self` - A reference to the `PhoneBook` instance.
+    /// The expanded path, or an error message if it refers to an undefined variable.
+    pub(crate) fn expand_path(path: &str) -> Result<String, String> {
+        shellexpand::full(path)
+            .map(|expanded| expanded.into_owned())
+            .map_err(|err| format!("Invalid path '{}': {}", path, err))
+    }
+    /// Tells the user how to cancel the operation they are starting.
+    pub(crate) fn print_cancel_hint() {
+        println!("(Type {} at any prompt to cancel.)", CANCEL_TOKEN);
+    }
+    /// Adds a new contact to the phone book's contacts list.
+    ///
+    /// This method takes a `Contact` struct as an argument and saves it to the database, recording
+    /// the addition in the audit log in the same transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - A `Contact` struct representing the new contact to be added.
+    /// * `operation` - The operation recorded in the audit log, e.g. "create" or "import".
     ///
     /// # Return
     ///
-    /// This function does not return any value. It prints the search results to the console.
-    pub(crate) fn search_contact(&mut self) {
-        let query = Self::get_input("Enter a search query: ").to_lowercase();
-        let mut found_contacts: Vec<Contact> = Vec::new();
-        match Self::get_contacts("") {
-            Ok(contacts) => {
-                for contact in contacts {
-                    if vec![
-                        &contact.first_name,
-                        &contact.last_name,
-                        &contact.email,
-                        &contact.address,
-                        &contact.phone,
-                    ]
-                    .iter()
-                    .any(|field| field.to_lowercase().contains(&query))
-                    {
-                        found_contacts.push(contact);
-                    }
-                }
-            }
-            Err(_) => {
-                println!("Error searching for contacts.");
-                return;
-            }
-        }
-        if found_contacts.is_empty() {
-            println!("No contacts found matching the search query.");
-        } else {
-            Self::print_contacts(&found_contacts);
-        }
+    /// The id the database assigned to the new contact.
+    pub fn add_contact(contact: Contact, operation: &str) -> i32 {
+        Self::insert_contact(&contact, operation).expect("Error saving new contact")
     }
-    /// Loads contacts from a CSV file into the phone book.
+    /// Saves a new contact to the database, recording the addition in the audit log in the same
+    /// transaction.
     ///
-    /// # Parameters
+    /// # Arguments
+    ///
+    /// * `contact` - The contact to save.
+    /// * `operation` - The operation recorded in the audit log, e.g. "create" or "import".
+    ///
+    /// # Return
+    ///
+    /// The id the database assigned to the new contact.
+    pub(crate) fn insert_contact(contact: &Contact, operation: &str) -> QueryResult<i32> {
+        Self::insert_contact_with(contact, &[], &[], operation)
+    }
+    /// Saves a new contact along with its additional emails and tags, recording the addition in
+    /// the audit log, all in one transaction: a failure leaves no part of the contact behind.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - The contact to save.
+    /// * `emails` - The contact's additional `(label, address)` emails.
+    /// * `tags` - The contact's tags.
+    /// * `operation` - The operation recorded in the audit log, e.g. "create" or "import".
+    ///
+    /// # Return
+    ///
+    /// The id the database assigned to the new contact.
+    pub(crate) fn insert_contact_with(
+        contact: &Contact,
+        emails: &[(String, String)],
+        tags: &[String],
+        operation: &str,
+    ) -> QueryResult<i32> {
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            Self::insert_audited(connection, contact, emails, tags, operation)
+        })
+    }
+    /// Saves new contacts to the database in a single transaction, recording each addition in
+    /// the audit log: either all of them are saved or none is. Pressing Ctrl-C meanwhile rolls
+    /// the transaction back.
+    ///
+    /// # Arguments
+    ///
+    /// * `contacts` - The contacts to save.
+    /// * `operation` - The operation recorded in the audit log, e.g. "import".
+    ///
+    /// # Return
+    ///
+    /// `Ok(())` if every contact was saved, `Error::RollbackTransaction` if Ctrl-C was pressed.
+    pub(crate) fn insert_contacts(contacts: &[Contact], operation: &str) -> QueryResult<()> {
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            for contact in contacts {
+                if shutdown::requested() {
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+                Self::insert_audited(connection, contact, &[], &[], operation)?;
+            }
+            Ok(())
+        })
+    }
+    /// Inserts a contact with its additional emails and tags, and records the addition in the
+    /// audit log, within the caller's transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The database connection to use.
+    /// * `contact` - The contact to save.
+    /// * `emails` - The contact's additional `(label, address)` emails.
+    /// * `tags` - The contact's tags.
+    /// * `operation` - The operation recorded in the audit log.
+    ///
+    /// # Return
+    ///
+    /// The id the database assigned to the new contact.
+    fn insert_audited(
+        connection: &mut SqliteConnection,
+        contact: &Contact,
+        emails: &[(String, String)],
+        tags: &[String],
+        operation: &str,
+    ) -> QueryResult<i32> {
+        diesel::insert_into(contacts::table)
+            .values(contact)
+            .execute(connection)?;
+        let contact_id =
+            diesel::select(sql::<Integer>("last_insert_rowid()")).get_result(connection)?;
+        if !emails.is_empty() {
+            ContactEmail::replace_for(connection, contact_id, emails)?;
+        }
+        if !tags.is_empty() {
+            Tag::replace_for(connection, contact_id, tags)?;
+        }
+        AuditEntry::record(connection, operation, Some(contact_id), None)?;
+        Ok(contact_id)
+    }
+    /// Prints a contact along with its additional emails, tags and relationships.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - The contact to display.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the contact details to the console.
+    pub(crate) fn print_contact_details(contact: &Contact) {
+        contact.print_contact();
+        if !contact.company.is_empty() {
+            println!("Company: {}", contact.company);
+        }
+        if !contact.website.is_empty() {
+            println!("Website: {}", contact.website);
+        }
+        if !contact.preferred_method.is_empty() {
+            println!("Preferred contact method: {}", contact.preferred_method);
+        }
+        if let Some(last_contacted) = contact.last_contacted {
+            println!("Last contacted: {}", last_contacted);
+        }
+        if let Some(contact_id) = contact.id {
+            let mut connection = establish_connection();
+            match ContactEmail::load_for(&mut connection, contact_id) {
+                Ok(emails) => ContactEmail::print_emails(
+                    &emails
+                        .into_iter()
+                        .map(|email| (email.label, email.address))
+                        .collect::<Vec<_>>(),
+                ),
+                Err(err) => println!("Error fetching additional emails: {}", err),
+            }
+            match Tag::load_for(&mut connection, contact_id) {
+                Ok(tags) if !tags.is_empty() => println!("Tags: {}", tags.join(", ")),
+                Ok(_) => {}
+                Err(err) => println!("Error fetching tags: {}", err),
+            }
+            match Relationship::load_for(&mut connection, contact_id) {
+                Ok(relationships) => {
+                    for (label, related) in relationships {
+                        println!("{}", Relationship::describe(&label, &related));
+                    }
+                }
+                Err(err) => println!("Error fetching relationships: {}", err),
+            }
+        }
+    }
+    /// Prompts the user for any number of additional, labeled emails.
+    ///
+    /// Invalid addresses are rejected and prompted for again. Leaving the label empty finishes
+    /// the list.
+    ///
+    /// # Return
+    ///
+    /// The entered `(label, address)` pairs, or `Err(Cancelled)` if the user typed the cancel
+    /// token.
+    fn prompt_additional_emails() -> Result<Vec<(String, String)>, Cancelled> {
+        let mut emails = Vec::new();
+        loop {
+            let label = Self::get_input_or_cancel(
+                "Enter a label for an additional email, e.g. work (leave empty to finish): ",
+            )?;
+            if label.is_empty() {
+                return Ok(emails);
+            }
+            let address = loop {
+                let address = Self::get_input_or_cancel(&format!("Enter {} email: ", label))?;
+                if is_valid_email(&address) {
+                    break address;
+                }
+                println!("'{}' is not a valid email address.", address);
+            };
+            emails.push((label, address));
+        }
+    }
+    /// Looks up the handler for the given command key in the operations table.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The command key entered by the user, already uppercased.
+    ///
+    /// # Returns
+    ///
+    /// The handler registered for `key`, or `None` if no operation uses that key.
+    pub(crate) fn find_operation(key: &str) -> Option<Handler> {
+        OPERATIONS
+            .iter()
+            .find(|&&(option, _, _)| option == key)
+            .map(|&(_, _, handler)| handler)
+    }
+    /// Asks the user to confirm and, if they do, stops the interactive loop.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    pub(crate) fn exit(&mut self) {
+        let confirmation = Self::get_input("Are you sure you want to exit? (y/n): ");
+        if confirmation == "y" {
+            println!("Exiting the phone book...");
+            self.running = false;
+        }
+    }
+    /// Displays the available operations in a table format for the `PhoneBook` struct.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - self A reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the table to the console.
+    pub fn show_operations(&self) {
+        println!("{}", Self::operations_menu());
+    }
+    /// Builds the menu of operations shown by `show_operations`.
+    ///
+    /// This function creates a new `Table` instance, sets the table header and adds a row for each
+    /// operation, listing its key in both cases since either is accepted.
+    ///
+    /// # Returns
+    ///
+    /// The table of operations, in the order of the operations table.
+    pub(crate) fn operations_menu() -> Table {
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("Option").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Description").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        // Add the operations as rows in the table
+        for &(option, description, _) in OPERATIONS {
+            table.add_row(vec![
+                Cell::new(format!("{} | {}", option, option.to_lowercase())),
+                Cell::new(description),
+            ]);
+        }
+        table
+    }
+    /// Deletes a contact from the phone book's contacts list based on the provided index.
+    ///
+    /// This method takes an index as an argument and removes the contact at that index from the `contacts` vector.
+    /// An index that isn't a number or is out of bounds is reported and asked for again.
+    /// In a terminal, when several contacts are listed, a checklist lets the user delete many of
+    /// them at once instead, with `delete_selected_contacts`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A mutable reference to the `PhoneBook` instance.
+    /// * `index` - An integer representing the index of the contact to be deleted.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. The `contacts` vector of the `PhoneBook` instance is updated.
+    pub fn delete_contact(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        // In a terminal, several listed contacts can be ticked and deleted at once
+        if self.contacts.len() > 1 && io::stdin().is_terminal() && io::stdout().is_terminal() {
+            self.delete_selected_contacts();
+            return;
+        }
+        Self::print_cancel_hint();
+        let Ok(index) = self.prompt_contact_index("Enter the index of the contact to delete: ")
+        else {
+            println!("Contact deletion cancelled.");
+            return;
+        };
+        Self::print_contact_details(&self.contacts[index - 1]);
+        self.delete_contact_at(index);
+    }
+    /// Deletes a listed contact once the user confirms, after its details were shown.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A mutable reference to the `PhoneBook` instance.
+    /// * `index` - The 1-based index of the contact in the last listing.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. The contact is removed from the listing.
+    fn delete_contact_at(&mut self, index: usize) {
+        let confirm = Self::get_input("Are you sure you want to delete this contact? (y/n): ");
+        if confirm.to_lowercase() == "y" {
+            let result = match self.contacts[index - 1].id {
+                Some(contact_id) => Self::delete_contact_by_id(contact_id),
+                None => Ok(0),
+            };
+            match result {
+                Ok(_) => {
+                    self.contacts.remove(index - 1);
+                    println!("Contact at index {} deleted successfully.", index);
+                }
+                Err(e) => {
+                    println!("Error deleting contact from database: {}", e);
+                }
+            }
+        } else {
+            println!("Contact deletion cancelled.");
+        }
+    }
+    /// Deletes a contact, recording the deletion in the audit log in the same transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact_id` - The id of the contact to delete.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted contacts, 0 if no contact has that id.
+    pub(crate) fn delete_contact_by_id(contact_id: i32) -> QueryResult<usize> {
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            let before = ContactSnapshot::load(connection, contact_id)?;
+            let deleted = Self::delete_contact_rows(connection, contact_id)?;
+            if deleted > 0 {
+                AuditEntry::record(connection, "delete", Some(contact_id), before.as_ref())?;
+            }
+            Ok(deleted)
+        })
+    }
+    /// Deletes a contact along with its additional emails, tags and relationships.
+    ///
+    /// The related rows are removed by the `ON DELETE CASCADE` foreign keys, which
+    /// `establish_connection` enforces by enabling the `foreign_keys` pragma.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The database connection to use.
+    /// * `contact_id` - The id of the contact to delete.
+    ///
+    /// # Returns
+    ///
+    /// The number of deleted contacts.
+    pub(crate) fn delete_contact_rows(
+        connection: &mut SqliteConnection,
+        contact_id: i32,
+    ) -> QueryResult<usize> {
+        delete(contacts::table.filter(contacts::id.eq(contact_id))).execute(connection)
+    }
+    /// Updates a contact in the phone book's contacts list based on the provided index.
+    ///
+    /// This function prompts the user to enter the index of the contact to update, validates the input,
+    /// and then asks for new contact details. If the index is valid and all required fields are provided,
+    /// the contact at the specified index is updated with the new details. Only the fields of the
+    /// `PROMPT_ORDER` setting are prompted for, in its order; the others keep their value.
+    ///
+    /// # Parameters
+    ///
+    /// * `self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. The `contacts` vector of the `PhoneBook` instance is updated.
+    pub(crate) fn update_contact(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        Self::print_cancel_hint();
+        let Ok(index) = self.prompt_contact_index("Enter the index of the contact to update: ")
+        else {
+            println!("Contact update cancelled.");
+            return;
+        };
+        Self::print_contact_details(&self.contacts[index - 1]);
+        self.update_contact_at(index);
+    }
+    /// Prompts for the new details of a listed contact and saves them, after its current details
+    /// were shown.
+    ///
+    /// # Parameters
+    ///
+    /// * `self` - A mutable reference to the `PhoneBook` instance.
+    /// * `index` - The 1-based index of the contact in the last listing.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. The contact is updated in the listing.
+    fn update_contact_at(&mut self, index: usize) {
+        println!("Updating contact details...");
+        let required_fields = match settings::required_fields() {
+            Ok(required_fields) => required_fields,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let prompt_order = match settings::prompt_order() {
+            Ok(prompt_order) => prompt_order,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        // Fields left out of PROMPT_ORDER, or left empty, keep their current value
+        let current = &self.contacts[index - 1];
+        let mut values: Vec<String> = FIELDS
+            .iter()
+            .map(|&(field, _)| current.field(field).to_string())
+            .collect();
+        for field in &prompt_order {
+            let (position, label) = field_position(field);
+            let Ok(value) = Self::prompt_field(
+                "Enter new",
+                field,
+                label,
+                &required_fields,
+                current.field(field),
+            ) else {
+                println!("Contact update cancelled.");
+                return;
+            };
+            if value.is_empty() && required_fields.contains(field) {
+                println!("{} is required. Contact update cancelled.", label);
+                return;
+            }
+            values[position] = value;
+        }
+        let mut updated_contact = Self::contact_from_values(values);
+        if let Err(err) = updated_contact.validate() {
+            println!("{} Contact update cancelled.", err);
+            return;
+        }
+        let Ok(additional_emails) = Self::prompt_additional_email_changes() else {
+            println!("Contact update cancelled.");
+            return;
+        };
+        let Ok(tags) = Self::prompt_tag_changes() else {
+            println!("Contact update cancelled.");
+            return;
+        };
+        let contact_id_result = self.contacts.get(index - 1).map(|c| c.id);
+
+        if let Some(contact_id) = contact_id_result {
+            updated_contact.id = contact_id;
+            updated_contact.created_at = self.contacts[index - 1].created_at;
+            updated_contact.last_contacted = self.contacts[index - 1].last_contacted;
+            let result = match contact_id {
+                Some(contact_id) => Self::save_contact_update(
+                    contact_id,
+                    &updated_contact,
+                    additional_emails.as_deref(),
+                    tags.as_deref(),
+                ),
+                None => Ok(0),
+            };
+            match result {
+                Ok(_) => println!("Contact updated successfully!"),
+                Err(err) => println!("Error updating contact: {}", err),
+            }
+            self.contacts[index - 1] = updated_contact;
+        } else {
+            println!("Invalid contact ID.");
+        }
+    }
+    /// Saves the new details of a contact, recording the update in the audit log in the same
+    /// transaction.
+    ///
+    /// The emails and tags are saved with the contact, so the audit log snapshot is complete.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact_id` - The id of the contact to update.
+    /// * `contact` - The new details of the contact.
+    /// * `additional_emails` - The new additional `(label, address)` emails, or `None` to keep
+    ///   them.
+    /// * `tags` - The new tags, or `None` to keep them.
+    ///
+    /// # Returns
+    ///
+    /// The number of updated contacts, 0 if no contact has that id.
+    pub(crate) fn save_contact_update(
+        contact_id: i32,
+        contact: &Contact,
+        additional_emails: Option<&[(String, String)]>,
+        tags: Option<&[String]>,
+    ) -> QueryResult<usize> {
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            let before = ContactSnapshot::load(connection, contact_id)?;
+            let updated = update(contacts::table)
+                .filter(contacts::id.eq(contact_id))
+                .set((
+                    contacts::first_name.eq(&contact.first_name),
+                    contacts::last_name.eq(&contact.last_name),
+                    contacts::email.eq(&contact.email),
+                    contacts::address.eq(&contact.address),
+                    contacts::phone.eq(&contact.phone),
+                    contacts::website.eq(&contact.website),
+                    contacts::extension.eq(&contact.extension),
+                    contacts::company.eq(&contact.company),
+                    contacts::preferred_method.eq(&contact.preferred_method),
+                    contacts::updated_at.eq(&contact.updated_at),
+                ))
+                .execute(connection)?;
+            if updated == 0 {
+                return Ok(0);
+            }
+            if let Some(additional_emails) = additional_emails {
+                ContactEmail::replace_for(connection, contact_id, additional_emails)?;
+            }
+            if let Some(tags) = tags {
+                Tag::replace_for(connection, contact_id, tags)?;
+            }
+            AuditEntry::record(connection, "update", Some(contact_id), before.as_ref())?;
+            Ok(updated)
+        })
+    }
+    /// Asks whether to edit a contact's additional emails and, if so, prompts for the new ones.
+    ///
+    /// # Return
+    ///
+    /// The new `(label, address)` pairs, `None` if the emails should be kept as they are, or
+    /// `Err(Cancelled)` if the user typed the cancel token.
+    fn prompt_additional_email_changes() -> Result<Option<Vec<(String, String)>>, Cancelled> {
+        let edit = Self::get_input_or_cancel("Edit additional emails? (y/n): ")?;
+        if edit.to_lowercase() != "y" {
+            return Ok(None);
+        }
+        Self::prompt_additional_emails().map(Some)
+    }
+    /// Asks whether to edit a contact's tags and, if so, prompts for the new ones.
+    ///
+    /// # Return
+    ///
+    /// The new tags, `None` if the tags should be kept as they are, or `Err(Cancelled)` if the
+    /// user typed the cancel token.
+    fn prompt_tag_changes() -> Result<Option<Vec<String>>, Cancelled> {
+        let edit = Self::get_input_or_cancel("Edit tags? (y/n): ")?;
+        if edit.to_lowercase() != "y" {
+            return Ok(None);
+        }
+        Self::prompt_tags("Enter new tags").map(Some)
+    }
+    /// Prompts the user for a comma-separated list of tags, completing each against the tags
+    /// already in use.
+    ///
+    /// # Parameters
+    ///
+    /// * `prompt` - The start of the prompt, e.g. "Enter tags".
+    ///
+    /// # Return
+    ///
+    /// The canonical tags, without duplicates, or `Err(Cancelled)` if the user typed the cancel
+    /// token.
+    fn prompt_tags(prompt: &str) -> Result<Vec<String>, Cancelled> {
+        let mut connection = establish_connection();
+        let existing = Tag::all_names(&mut connection).unwrap_or_default();
+        if !existing.is_empty() {
+            println!("Existing tags: {}", existing.join(", "));
+        }
+        let input =
+            Self::get_input_or_cancel(&format!("{}, comma-separated (optional): ", prompt))?;
+        let mut tags: Vec<String> = Vec::new();
+        for typed in input
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+        {
+            let tag = Tag::resolve(typed, &existing);
+            if tag != typed {
+                println!("Using existing tag '{}' for '{}'.", tag, typed);
+            }
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        Ok(tags)
+    }
+    /// Searches for contacts in the phone book based on a given search query.
+    ///
+    /// This function takes a search query as input and looks up every contact whose first name,
+    /// last name, email, address, phone number, website or one of its additional emails matches
+    /// the search query, ignoring case. In a terminal, the matches are shown live as the query is
+    /// typed; otherwise the query is read as a single line.
+    ///
+    /// If no contacts are found matching the search query, a message is printed to the console indicating
+    /// that no contacts were found. A single match is shown in detail, with an offer to edit or
+    /// delete it. Otherwise, the found contacts are displayed using the `print_contacts`
+    /// function, and the user is offered to copy them to the clipboard as CSV.
+    ///
+    /// # Parameters
+    ///
+    /// * `&self` - A reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the search results to the console.
+    pub(crate) fn search_contact(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        let query = if io::stdin().is_terminal() && io::stdout().is_terminal() {
+            match self.read_search_as_you_type() {
+                Ok(Some(query)) => {
+                    // The live results were drawn on the alternate screen, so echo the query
+                    println!("Search query: {}", query);
+                    query
+                }
+                Ok(None) => {
+                    println!("Search cancelled.");
+                    return;
+                }
+                Err(err) => {
+                    println!("Live search unavailable ({}).", err);
+                    Self::get_input("Enter a search query: ")
+                }
+            }
+        } else {
+            Self::get_input("Enter a search query: ")
+        };
+        let found_contacts = match Self::search_contacts(&query) {
+            Ok(found_contacts) => found_contacts,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        match found_contacts.as_slice() {
+            [] => println!("No contacts found matching the search query."),
+            [contact] => self.act_on_single_match(contact.clone()),
+            _ => {
+                self.print_contacts(&found_contacts);
+                Self::offer_csv_copy(&found_contacts);
+                // The results become the last listing, so their indexes can be used right away
+                self.contacts = found_contacts;
+            }
+        }
+    }
+    /// Shows the details of the only contact a search found, rather than a one-row table, and
+    /// offers to edit or delete it right away.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A mutable reference to the `PhoneBook` instance.
+    /// * `contact` - The contact found.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. The contact becomes the last listing, as index 1.
+    fn act_on_single_match(&mut self, contact: Contact) {
+        println!("One contact found:");
+        Self::print_contact_details(&contact);
+        self.contacts = vec![contact];
+        let action = Self::get_input("Edit (e), delete (d), or press Enter to continue: ");
+        match action.to_lowercase().as_str() {
+            "e" => self.update_contact_at(1),
+            "d" => self.delete_contact_at(1),
+            _ => {}
+        }
+    }
+    /// Finds the contacts matching the query.
+    ///
+    /// The query goes through the full-text index, where every word must start a word of a
+    /// field or an additional email. Queries the index can't handle, such as punctuation only,
+    /// and databases where the index isn't available fall back to a substring scan.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The text to look for, matched case-insensitively.
+    ///
+    /// # Returns
+    ///
+    /// The matching contacts, best matches first.
+    pub fn search_contacts(query: &str) -> QueryResult<Vec<Contact>> {
+        let mut connection = establish_connection();
+        Self::search_contacts_in(&mut connection, query)
+    }
+    /// Finds the contacts matching the query, using the given connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The database connection to use.
+    /// * `query` - The text to look for, matched case-insensitively.
+    ///
+    /// # Returns
+    ///
+    /// The matching contacts, best matches first. See `search_contacts`.
+    pub(crate) fn search_contacts_in(
+        connection: &mut SqliteConnection,
+        query: &str,
+    ) -> QueryResult<Vec<Contact>> {
+        if let Some(fts_query) = Self::fts_query(query) {
+            if let Ok(found_contacts) = Self::search_contacts_fts(connection, &fts_query) {
+                return Ok(found_contacts);
+            }
+        }
+        Self::search_contacts_like(connection, query)
+    }
+    /// Finds the contacts with a field, or an additional email, containing the query.
+    ///
+    /// The contacts are joined with their additional emails, so a contact matching through
+    /// several of its emails is still returned only once.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The database connection to use.
+    /// * `query` - The text to look for, matched case-insensitively.
+    ///
+    /// # Returns
+    ///
+    /// The matching contacts, in creation order.
+    fn search_contacts_like(
+        connection: &mut SqliteConnection,
+        query: &str,
+    ) -> QueryResult<Vec<Contact>> {
+        let pattern = format!("%{}%", Self::escape_like(query));
+        contacts::table
+            .left_join(emails::table)
+            .filter(
+                contacts::first_name
+                    .like(&pattern)
+                    .escape('\\')
+                    .or(contacts::last_name.like(&pattern).escape('\\'))
+                    .or(contacts::email.like(&pattern).escape('\\'))
+                    .or(contacts::address.like(&pattern).escape('\\'))
+                    .or(contacts::phone.like(&pattern).escape('\\'))
+                    .or(contacts::website.like(&pattern).escape('\\'))
+                    .or(contacts::company.like(&pattern).escape('\\'))
+                    .nullable()
+                    .or(emails::address.like(&pattern).escape('\\')),
+            )
+            .select(contacts::all_columns)
+            .distinct()
+            .order(contacts::id.asc())
+            .load::<Contact>(connection)
+    }
+    /// Escapes the `LIKE` wildcards in user input, so they match literally.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to escape.
+    ///
+    /// # Returns
+    ///
+    /// The text with `\`, `%` and `_` prefixed by a backslash.
+    pub(crate) fn escape_like(text: &str) -> String {
+        text.replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    }
+    /// Picks a contact at random and displays it, to help the user reconnect with people.
+    ///
+    /// # Parameters
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the chosen contact to the console.
+    pub(crate) fn show_random_contact(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        match Self::get_random_contact() {
+            Ok(Some(contact)) => Self::print_contact_details(&contact),
+            Ok(None) => println!("No contacts found."),
+            Err(err) => println!("Error fetching a random contact: {}", err),
+        }
+    }
+    /// Fetches a random contact from the database.
+    ///
+    /// # Returns
+    ///
+    /// The chosen contact, or `None` if the phone book is empty.
+    pub fn get_random_contact() -> QueryResult<Option<Contact>> {
+        let mut connection = establish_connection();
+        contacts::table
+            .order(sql::<Integer>("RANDOM()"))
+            .first::<Contact>(&mut connection)
+            .optional()
+    }
+    /// Fetches a contact by its id.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact_id` - The id of the contact.
+    ///
+    /// # Returns
+    ///
+    /// The contact, or `None` if there is no contact with that id.
+    pub fn get_contact(contact_id: i32) -> QueryResult<Option<Contact>> {
+        let mut connection = establish_connection();
+        contacts::table
+            .filter(contacts::id.eq(contact_id))
+            .first::<Contact>(&mut connection)
+            .optional()
+    }
+    /// Prints the first few contacts parsed from an import file, along with the total count.
+    ///
+    /// # Parameters
+    ///
+    /// * `&self` - A reference to the `PhoneBook` instance.
+    /// * `contacts` - The first contacts parsed from the import file; only the first
+    ///   `IMPORT_PREVIEW_ROWS` are shown.
+    /// * `total` - The number of contacts parsed from the import file.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the preview table to the console.
+    pub(crate) fn print_import_preview(&self, contacts: &[Contact], total: usize) {
+        let preview = &contacts[..contacts.len().min(IMPORT_PREVIEW_ROWS)];
+        println!(
+            "Preview of the first {} of {} contacts to import:",
+            preview.len(),
+            total
+        );
+        self.print_contacts(preview);
+    }
+    /// Creates the progress bar shown while imported contacts are saved.
+    ///
+    /// The bar is hidden when standard output isn't a terminal, so piped output isn't cluttered
+    /// with progress updates.
+    ///
+    /// # Parameters
+    ///
+    /// * `total` - The number of contacts being imported.
+    ///
+    /// # Returns
+    ///
+    /// A progress bar with an ETA, or a hidden one outside a terminal.
+    pub(crate) fn import_progress_bar(total: usize) -> ProgressBar {
+        Self::progress_bar(total, io::stdout().is_terminal())
+    }
+    /// Creates the progress bar of `import_progress_bar`.
+    ///
+    /// # Parameters
+    ///
+    /// * `total` - The number of contacts being imported.
+    /// * `shown` - Whether the bar is drawn, i.e. standard output is a terminal.
+    ///
+    /// # Returns
+    ///
+    /// A progress bar with an ETA, or a hidden one if it isn't shown.
+    fn progress_bar(total: usize, shown: bool) -> ProgressBar {
+        if !shown {
+            return ProgressBar::hidden();
+        }
+        let progress = ProgressBar::new(total as u64);
+        progress.set_style(
+            ProgressStyle::with_template("{bar:40} {pos}/{len} contacts (ETA {eta})")
+                .expect("progress template is valid"),
+        );
+        progress
+    }
+    /// Reports why saving a batch of imported contacts failed, and how many contacts the batches
+    /// committed before it saved.
+    ///
+    /// # Parameters
+    ///
+    /// * `err` - The error saving the batch, `Error::RollbackTransaction` if Ctrl-C was pressed.
+    /// * `imported` - The number of contacts saved by the previous batches.
+    fn report_import_failure(err: &diesel::result::Error, imported: usize) {
+        if matches!(err, diesel::result::Error::RollbackTransaction) {
+            println!("Import interrupted: the batch in progress was rolled back.");
+            println!(
+                "{} contact(s) from {} completed batch(es) remain imported.",
+                imported,
+                imported / IMPORT_BATCH_SIZE
+            );
+        } else {
+            println!("Error saving contacts: {}", err);
+            println!("{} contact(s) were imported before the error.", imported);
+        }
+    }
+    /// Normalizes a CSV header before it is matched against the contact field names.
+    ///
+    /// Files exported from Excel start with a UTF-8 byte order mark, which would otherwise stick
+    /// to the first header, and hand-edited files often pad headers with spaces.
+    ///
+    /// # Parameters
+    ///
+    /// * `header` - The header as read from the file.
+    ///
+    /// # Returns
+    ///
+    /// The header without a leading byte order mark or surrounding whitespace.
+    fn normalize_header(header: &str) -> &str {
+        header.trim_start_matches('\u{feff}').trim()
+    }
+    /// Wraps an imported file in a reader yielding UTF-8, as the CSV reader expects.
+    ///
+    /// UTF-8 files are streamed as they are. Files in another encoding are transcoded as they are
+    /// streamed, so a large file is never held in memory whole, and characters the encoding can't
+    /// represent are replaced with `\u{fffd}`.
+    ///
+    /// # Parameters
+    ///
+    /// * `file` - The opened file.
+    /// * `encoding` - The encoding of the file, from the `IMPORT_ENCODING` setting.
+    ///
+    /// # Returns
+    ///
+    /// A reader over the UTF-8 content.
+    fn utf8_reader(file: File, encoding: &'static Encoding) -> Box<dyn Read> {
+        if encoding == UTF_8 {
+            return Box::new(BufReader::new(file));
+        }
+        Box::new(
+            DecodeReaderBytesBuilder::new()
+                .encoding(Some(encoding))
+                .build(file),
+        )
+    }
+    /// Checks whether a row read through `utf8_reader` had characters its encoding can't
+    /// represent, which were replaced with `\u{fffd}`.
+    ///
+    /// # Parameters
+    ///
+    /// * `record` - The row.
+    /// * `encoding` - The encoding of the file, from the `IMPORT_ENCODING` setting.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a field holds a replacement character transcoding put there. UTF-8 files are
+    /// not transcoded, and rows with invalid UTF-8 fail to read instead.
+    pub(crate) fn has_replaced_characters(
+        record: &StringRecord,
+        encoding: &'static Encoding,
+    ) -> bool {
+        encoding != UTF_8
+            && record
+                .iter()
+                .any(|field| field.contains(char::REPLACEMENT_CHARACTER))
+    }
+    /// Opens a CSV file for import and reads its header row.
+    ///
+    /// Rows with a different number of fields than the header are let through, so short rows can
+    /// be reported instead of failing the whole read.
+    ///
+    /// # Parameters
+    ///
+    /// * `file_name` - The path of the CSV file.
+    /// * `encoding` - The encoding of the file, from the `IMPORT_ENCODING` setting.
+    ///
+    /// # Returns
+    ///
+    /// The reader positioned after the header row, and where the contact fields are in its rows
+    /// and the `IMPORT_RULES` applied to them, or an error message.
+    pub(crate) fn open_csv_import(
+        file_name: &str,
+        encoding: &'static Encoding,
+    ) -> Result<(ImportReader, CsvColumns), String> {
+        let file = File::open(file_name).map_err(|err| format!("Error opening file: {}", err))?;
+        let input = Self::utf8_reader(file, encoding);
+        let mut reader = ReaderBuilder::new().flexible(true).from_reader(input);
+        let header_row = reader
+            .headers()
+            .map_err(|err| format!("Error reading header row: {}", err))?;
+        let rules = ImportRule::load_configured()?;
+        let columns = CsvColumns::from_header(header_row, rules);
+        Ok((reader, columns))
+    }
+    /// Loads contacts from a CSV file into the phone book.
+    ///
+    /// The file is streamed rather than loaded whole, so importing a very large file doesn't
+    /// take more memory than a small one: it is read once to check the rows and show a preview,
+    /// and once more to save the contacts in batches of `IMPORT_BATCH_SIZE`, one transaction per
+    /// batch. Pressing Ctrl-C during the import rolls back the batch in progress, so only whole
+    /// batches are ever saved.
+    ///
+    /// # Parameters
     ///
     /// * `self` - A mutable reference to the `PhoneBook` instance.
     ///
     /// # Functionality
     ///
-    /// 1. Prompts the user to enter the name of the CSV file to load contacts from.
-    /// 2. Opens the CSV file. If the file cannot be opened, an error message is printed and the function returns.
-    /// 3. Creates a CSV reader.
-    /// 4. Reads the header row of the CSV file. If the header row cannot be read, an error message is printed and the function returns.
-    /// 5. Gets the indices of the required columns based on the header.
-    /// 6. Iterates through the CSV records and creates `Contact` instances.
-    /// 7. Adds each created `Contact` instance to the `contacts` vector of the `PhoneBook` instance.
-    /// 8. Prints a success message indicating that the contacts have been loaded successfully from the CSV file.
+    /// 1. Prompts the user to enter the name of the CSV file to load contacts from, expanding `~`
+    ///    and environment variables.
+    /// 2. Opens the CSV file. If the file cannot be opened, an error message is printed and the
+    ///    function returns.
+    /// 3. Creates a CSV reader, transcoding the file to UTF-8 if the `IMPORT_ENCODING` setting
+    ///    names another encoding.
+    /// 4. Reads the header row of the CSV file. If the header row cannot be read, an error message
+    ///    is printed and the function returns.
+    /// 5. Gets the indices of the required columns based on the header, asking which one to use
+    ///    when several columns have the same name. Without first and last name columns, a single
+    ///    name column is split with `split_full_name`.
+    /// 6. Iterates through the CSV records, applying the rules of the `IMPORT_RULES` file,
+    ///    reporting the rows that can't be imported and keeping only the first few contacts for
+    ///    the preview. The skipped rows are written with the reason for each to
+    ///    `<file>.errors.csv`, to be fixed and imported again.
+    /// 7. Shows the preview and asks the user to confirm the import.
+    /// 8. Reads the file again and saves the contacts in batches, showing a progress bar in a
+    ///    terminal.
+    /// 9. Prints a success message indicating that the contacts have been loaded successfully from
+    ///    the CSV file.
     pub(crate) fn load_contacts_from_csv(&mut self) {
-        let file_name = Self::get_input("Enter the name of the CSV file to load contacts from: ");
-        // Open the CSV file
-        let file = match File::open(&file_name) {
-            Ok(file) => file,
+        let encoding = match settings::import_encoding() {
+            Ok(encoding) => encoding,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        Self::print_cancel_hint();
+        let Ok(file_name) =
+            Self::get_input_or_cancel("Enter the name of the CSV file to load contacts from: ")
+        else {
+            println!("Import cancelled.");
+            return;
+        };
+        let file_name = match Self::expand_path(&file_name) {
+            Ok(file_name) => file_name,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let (mut reader, mut columns) = match Self::open_csv_import(&file_name, encoding) {
+            Ok(opened) => opened,
             Err(err) => {
-                println!("Error opening file: {}", err);
+                println!("{}", err);
                 return;
             }
         };
-        // Create a CSV reader
-        let mut reader = ReaderBuilder::new().from_reader(BufReader::new(file));
-        // Read the header row
-        let header_row = match reader.headers() {
-            Ok(header_row) => header_row,
+        if columns.choose_duplicate_columns().is_err() {
+            println!("Import cancelled.");
+            return;
+        }
+        if columns.full_name.is_some() {
+            println!("Splitting the name column into first and last names.");
+        }
+        if !columns.rules.is_empty() {
+            println!("Applying {} import rule(s).", columns.rules.len());
+        }
+        let header = match reader.headers() {
+            Ok(header) => header.clone(),
             Err(err) => {
                 println!("Error reading header row: {}", err);
                 return;
             }
         };
-        // Get the indices of the required columns based on the header
-        let first_name_index = header_row.iter().position(|header| header == "first_name");
-        let last_name_index = header_row.iter().position(|header| header == "last_name");
-        let email_index = header_row.iter().position(|header| header == "email");
-        let address_index = header_row.iter().position(|header| header == "address");
-        let phone_number_index = header_row.iter().position(|header| header == "phone");
-        // Iterate through the CSV records and create Contact instances
+        // Check every row, keeping only the contacts shown in the preview
+        let mut errors = ImportErrors::new(&file_name, header);
+        let mut preview = Vec::new();
+        let mut total = 0;
+        let mut replaced = false;
         for record in reader.records() {
-            match record {
-                Ok(record) => {
-                    let mut contact = Contact::default();
-                    if let Some(first_name_index) = first_name_index {
-                        contact.first_name = record[first_name_index].to_string();
-                    }
-                    if let Some(last_name_index) = last_name_index {
-                        contact.last_name = record[last_name_index].to_string();
-                    }
-                    if let Some(email_index) = email_index {
-                        contact.email = record[email_index].to_string();
-                    }
-                    if let Some(address_index) = address_index {
-                        contact.address = record[address_index].to_string();
-                    }
-                    if let Some(phone_number_index) = phone_number_index {
-                        contact.phone = record[phone_number_index].to_string();
-                    }
-                    Self::add_contact(contact)
-                }
+            let record = match record {
+                Ok(record) => record,
                 Err(err) => {
                     println!("Error reading record: {}", err);
+                    let line = err.position().map_or(0, |position| position.line());
+                    errors.add(line, &StringRecord::new(), &err.to_string());
+                    continue;
+                }
+            };
+            replaced |= Self::has_replaced_characters(&record, encoding);
+            match columns.parse_record(&record) {
+                Ok(contact) => {
+                    if preview.len() < IMPORT_PREVIEW_ROWS {
+                        preview.push(contact);
+                    }
+                    total += 1;
+                }
+                Err(reason) => {
+                    let line = record.position().map_or(0, |position| position.line());
+                    println!("Skipping line {}: {}", line, reason);
+                    errors.add(line, &record, &reason);
                 }
             }
         }
+        if replaced {
+            println!(
+                "Warning: the file contains bytes that aren't valid {}; they were replaced.",
+                encoding.name()
+            );
+        }
+        if errors.count() > 0 {
+            println!(
+                "Skipped {} row(s) that could not be imported.",
+                errors.count()
+            );
+            match errors.finish() {
+                Ok(path) => println!(
+                    "The skipped rows were written to '{}' with the reason for each. Fix them there and import that file.",
+                    path
+                ),
+                Err(err) => println!("{}", err),
+            }
+        }
+        if total == 0 {
+            println!("No contacts found in file '{}'.", file_name);
+            return;
+        }
+        // Let the user check the column mapping before anything is written
+        self.print_import_preview(&preview, total);
+        let confirm = Self::get_input(&format!("Import {} contacts? (y/n): ", total));
+        if confirm.to_lowercase() != "y" {
+            println!("Import cancelled.");
+            return;
+        }
+        let mut reader = match Self::open_csv_import(&file_name, encoding) {
+            Ok((reader, _)) => reader,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        // Ctrl-C rolls back the batch in progress, and exits once the interruption is reported
+        let _busy = BusyGuard::enter();
+        let progress = Self::import_progress_bar(total);
+        let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+        let mut imported = 0;
+        // Rows skipped above are skipped again, without being reported twice
+        let contacts = reader
+            .records()
+            .filter_map(|record| columns.parse(record).ok());
+        for contact in contacts {
+            batch.push(contact);
+            if batch.len() < IMPORT_BATCH_SIZE {
+                continue;
+            }
+            if let Err(err) = Self::insert_contacts(&batch, "import") {
+                progress.finish_and_clear();
+                Self::report_import_failure(&err, imported);
+                return;
+            }
+            imported += batch.len();
+            batch.clear();
+            progress.set_position(imported as u64);
+        }
+        if let Err(err) = Self::insert_contacts(&batch, "import") {
+            progress.finish_and_clear();
+            Self::report_import_failure(&err, imported);
+            return;
+        }
+        progress.finish_and_clear();
         println!("Contacts loaded successfully from file '{}'.", file_name);
     }
 }
+
+/// Define where the contact fields are found in the rows of an imported CSV file.
+pub(crate) struct CsvColumns {
+    /// Number of columns in the header row.
+    header_len: usize,
+    /// The contact fields found in the header, with the index of their column.
+    fields: Vec<(&'static str, usize)>,
+    /// The contact fields named by several headers, with the indexes of all their columns. The
+    /// first is used unless `choose_duplicate_columns` picks another.
+    pub(crate) duplicates: Vec<(&'static str, Vec<usize>)>,
+    /// Index of the single column holding whole names, when the file has no first or last name
+    /// column.
+    pub(crate) full_name: Option<usize>,
+    /// The rules transforming the fields of each row, from the `IMPORT_RULES` setting.
+    pub(crate) rules: Vec<ImportRule>,
+}
+
+impl CsvColumns {
+    /// Maps the headers of a CSV file to contact fields.
+    ///
+    /// # Parameters
+    ///
+    /// * `header_row` - The header row of the file.
+    /// * `rules` - The rules transforming the fields of each row.
+    ///
+    /// # Returns
+    ///
+    /// The columns of the contact fields.
+    fn from_header(header_row: &StringRecord, rules: Vec<ImportRule>) -> Self {
+        let mut fields = Vec::new();
+        let mut duplicates = Vec::new();
+        for &(field, _) in FIELDS {
+            let indexes: Vec<usize> = header_row
+                .iter()
+                .enumerate()
+                .filter(|&(_, header)| PhoneBook::normalize_header(header) == field)
+                .map(|(index, _)| index)
+                .collect();
+            if let Some(&first) = indexes.first() {
+                fields.push((field, first));
+            }
+            if indexes.len() > 1 {
+                duplicates.push((field, indexes));
+            }
+        }
+        // A file with a single name column has its names split into first and last names
+        let full_name = if fields
+            .iter()
+            .any(|&(field, _)| field == "first_name" || field == "last_name")
+        {
+            None
+        } else {
+            header_row.iter().position(|header| {
+                FULL_NAME_HEADERS
+                    .contains(&PhoneBook::normalize_header(header).to_lowercase().as_str())
+            })
+        };
+        Self {
+            header_len: header_row.len(),
+            fields,
+            duplicates,
+            full_name,
+            rules,
+        }
+    }
+    /// Describes the fields named by several headers, and the column used for each.
+    ///
+    /// # Returns
+    ///
+    /// One warning per duplicated field, with columns numbered from 1.
+    pub(crate) fn duplicate_warnings(&self) -> Vec<String> {
+        self.duplicates
+            .iter()
+            .map(|(field, indexes)| {
+                let used = self
+                    .fields
+                    .iter()
+                    .find(|&&(name, _)| name == *field)
+                    .map_or(indexes[0], |&(_, index)| index);
+                format!(
+                    "Warning: {} columns are named '{}' (columns {}); using column {}.",
+                    indexes.len(),
+                    field,
+                    column_numbers(indexes),
+                    used + 1
+                )
+            })
+            .collect()
+    }
+    /// Asks the user which column to import for each field named by several headers.
+    ///
+    /// Leaving the answer empty keeps the first column. Other answers must be the number of one
+    /// of the columns, and are prompted for again otherwise.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` once every duplicated field has a column, or `Err(Cancelled)` if the user typed
+    /// the cancel token.
+    pub(crate) fn choose_duplicate_columns(&mut self) -> Result<(), Cancelled> {
+        for (field, indexes) in &self.duplicates {
+            println!(
+                "Warning: {} columns are named '{}' (columns {}).",
+                indexes.len(),
+                field,
+                column_numbers(indexes)
+            );
+            let chosen = loop {
+                let input = PhoneBook::get_input_or_cancel(&format!(
+                    "Which column should be imported as '{}'? [{}]: ",
+                    field,
+                    indexes[0] + 1
+                ))?;
+                if input.is_empty() {
+                    break indexes[0];
+                }
+                match input.parse::<usize>() {
+                    Ok(number) if indexes.contains(&number.wrapping_sub(1)) => break number - 1,
+                    _ => println!("Enter one of columns {}.", column_numbers(indexes)),
+                }
+            };
+            if let Some(entry) = self.fields.iter_mut().find(|(name, _)| name == field) {
+                entry.1 = chosen;
+            }
+        }
+        Ok(())
+    }
+    /// Builds a contact from a CSV row, applies the import rules, empties placeholder values such
+    /// as `N/A` and checks it.
+    ///
+    /// # Parameters
+    ///
+    /// * `record` - The row, or the error raised while reading it.
+    ///
+    /// # Returns
+    ///
+    /// The contact, or a message explaining why the row is skipped.
+    pub(crate) fn parse(&self, record: csv::Result<StringRecord>) -> Result<Contact, String> {
+        let record = record.map_err(|err| format!("Error reading record: {}", err))?;
+        self.parse_record(&record).map_err(|reason| {
+            let line = record.position().map_or(0, |position| position.line());
+            format!("Skipping line {}: {}", line, reason)
+        })
+    }
+    /// Builds a contact from a CSV row like `parse`, for a row read successfully.
+    ///
+    /// # Parameters
+    ///
+    /// * `record` - The row.
+    ///
+    /// # Returns
+    ///
+    /// The contact, or the reason the row is skipped.
+    pub(crate) fn parse_record(&self, record: &StringRecord) -> Result<Contact, String> {
+        let mut contact = Contact::default();
+        let mut complete = true;
+        for &(field, index) in &self.fields {
+            match record.get(index) {
+                Some(value) => contact.set_field(field, value.to_string()),
+                None => complete = false,
+            }
+        }
+        if let Some(index) = self.full_name {
+            match record.get(index) {
+                Some(full_name) => {
+                    let (first_name, last_name) = split_full_name(full_name);
+                    contact.set_field("first_name", first_name);
+                    contact.set_field("last_name", last_name);
+                }
+                None => complete = false,
+            }
+        }
+        if !complete {
+            return Err(format!(
+                "expected {} columns, found {}.",
+                self.header_len,
+                record.len()
+            ));
+        }
+        apply_import_rules(&self.rules, &mut contact);
+        contact.clear_placeholders();
+        contact.normalize_name_case();
+        contact.validate()?;
+        Ok(contact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use diesel::connection::SimpleConnection;
+
+    use super::*;
+    use crate::test_support::{contact, open_book};
+
+    #[test]
+    fn every_menu_entry_runs_the_listed_operation() {
+        let menu = PhoneBook::operations_menu();
+        let entries: Vec<(String, String)> = menu
+            .row_iter()
+            .map(|row| {
+                let cells: Vec<String> = row.cell_iter().map(|cell| cell.content()).collect();
+                (cells[0].clone(), cells[1].clone())
+            })
+            .collect();
+        assert_eq!(entries.len(), OPERATIONS.len());
+        for ((option, description), &(key, listed, handler)) in entries.iter().zip(OPERATIONS) {
+            assert_eq!(option, &format!("{} | {}", key, key.to_lowercase()));
+            assert_eq!(description, listed);
+            // `start` uppercases the input, so both spellings shown must reach the handler
+            for typed in option.split(" | ") {
+                let found = PhoneBook::find_operation(&typed.to_uppercase())
+                    .unwrap_or_else(|| panic!("'{}' is listed without a handler", typed));
+                assert_eq!(
+                    found as usize, handler as usize,
+                    "'{}' runs another handler",
+                    typed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn timestamps_are_listed_only_with_the_option() {
+        let mut book = open_book();
+        book.add(contact("John", "Doe", "5551234567", "")).unwrap();
+        let contacts = book.list(None).unwrap();
+        let header = |book: &PhoneBook| -> Vec<String> {
+            let table = book.contacts_table(&contacts, 0);
+            let header = table.header().unwrap();
+            header.cell_iter().map(|cell| cell.content()).collect()
+        };
+        assert!(!header(&book).contains(&"Created".to_string()));
+        assert!(!book.compact_listing(&contacts, 0).contains(':'));
+
+        book.options.show_timestamps = true;
+        let header = header(&book);
+        assert_eq!(header[header.len() - 2..], ["Created", "Updated"]);
+        let created = format_timestamp(&contacts[0].created_at);
+        assert!(book.compact_listing(&contacts, 0).ends_with(&created));
+    }
+
+    #[test]
+    fn random_contact_of_a_single_contact_book_is_that_contact() {
+        let mut book = open_book();
+        assert!(PhoneBook::get_random_contact().unwrap().is_none());
+        let contact_id = book.add(contact("John", "Doe", "5551234567", "")).unwrap();
+        for _ in 0..10 {
+            let random = PhoneBook::get_random_contact().unwrap().unwrap();
+            assert_eq!(random.id, Some(contact_id));
+        }
+    }
+
+    #[test]
+    fn secondary_email_finds_the_contact_once() {
+        let mut book = open_book();
+        let contact_id = book
+            .add(contact("John", "Doe", "5551234567", "john@example.com"))
+            .unwrap();
+        book.add(contact("Jane", "Roe", "5551234568", "jane@example.com"))
+            .unwrap();
+        let emails = [
+            ("work".to_string(), "jdoe@acme.example".to_string()),
+            ("old".to_string(), "jdoe@oldmail.example".to_string()),
+        ];
+        ContactEmail::replace_for(&mut establish_connection(), contact_id, &emails).unwrap();
+        for query in ["oldmail", "jdoe", "john", "acme"] {
+            let found = PhoneBook::search_contacts(query).unwrap();
+            let ids: Vec<Option<i32>> = found.iter().map(|contact| contact.id).collect();
+            assert_eq!(ids, [Some(contact_id)], "searching '{}'", query);
+        }
+    }
+
+    #[test]
+    fn import_progress_is_drawn_only_when_shown() {
+        assert!(PhoneBook::progress_bar(1200, false).is_hidden());
+        let progress = PhoneBook::progress_bar(1200, true);
+        assert_eq!(progress.length(), Some(1200));
+        // Keep the test output clean while checking the bar counts the saved contacts
+        progress.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        progress.set_position(1000);
+        assert_eq!(progress.position(), 1000);
+    }
+
+    #[test]
+    fn unknown_keys_have_no_handler() {
+        assert!(PhoneBook::find_operation("").is_none());
+        assert!(PhoneBook::find_operation("ZZ").is_none());
+        assert!(PhoneBook::find_operation("c").is_none());
+    }
+
+    #[test]
+    fn duplicate_or_lowercase_keys_are_rejected() {
+        let handler: Handler = PhoneBook::exit;
+        assert!(has_unique_uppercase_keys(&[
+            ("A", "", handler),
+            ("AB", "", handler)
+        ]));
+        assert!(!has_unique_uppercase_keys(&[
+            ("A", "", handler),
+            ("A", "", handler)
+        ]));
+        assert!(!has_unique_uppercase_keys(&[("a", "", handler)]));
+    }
+
+    #[test]
+    fn search_goes_through_the_full_text_index() {
+        let mut book = open_book();
+        let mut found = contact("John", "Doe", "5551234567", "john@example.com");
+        found.company = "Acme Corporation".to_string();
+        let contact_id = book.add(found).unwrap();
+        book.add(contact("Jane", "Roe", "5559876543", "jane@example.com"))
+            .unwrap();
+        let mut connection = establish_connection();
+        let fts_query = PhoneBook::fts_query("acme corp").unwrap();
+        let found_contacts = PhoneBook::search_contacts_fts(&mut connection, &fts_query).unwrap();
+        assert_eq!(found_contacts.len(), 1);
+        assert_eq!(found_contacts[0].id(), Some(contact_id));
+    }
+
+    #[test]
+    fn search_falls_back_to_a_substring_scan_without_the_index() {
+        let mut book = open_book();
+        let mut found = contact("John", "Doe", "5551234567", "john@example.com");
+        found.website = "https://john.example.org".to_string();
+        found.company = "Acme".to_string();
+        let contact_id = book.add(found).unwrap();
+        let mut connection = establish_connection();
+        connection
+            .batch_execute(
+                "DROP TRIGGER emails_fts_delete; DROP TRIGGER emails_fts_update;
+                 DROP TRIGGER emails_fts_insert; DROP TRIGGER contacts_fts_delete;
+                 DROP TRIGGER contacts_fts_update; DROP TRIGGER contacts_fts_insert;
+                 DROP TABLE contacts_fts;",
+            )
+            .unwrap();
+        for query in ["example.org", "acme"] {
+            let found_contacts = PhoneBook::search_contacts_in(&mut connection, query).unwrap();
+            assert_eq!(found_contacts.len(), 1, "{}", query);
+            assert_eq!(found_contacts[0].id(), Some(contact_id));
+        }
+        // The index is rebuilt with the existing contacts once it is available again
+        crate::migrations::create_search_index(&mut connection).unwrap();
+        let fts_query = PhoneBook::fts_query("acme").unwrap();
+        let found_contacts = PhoneBook::search_contacts_fts(&mut connection, &fts_query).unwrap();
+        assert_eq!(found_contacts.len(), 1);
+    }
+
+    #[test]
+    fn tilde_expands_to_the_home_directory() {
+        let home = env::var("HOME").unwrap();
+        assert_eq!(
+            PhoneBook::expand_path("~/contacts.csv").unwrap(),
+            format!("{}/contacts.csv", home)
+        );
+        assert_eq!(
+            PhoneBook::expand_path("/tmp/contacts.csv").unwrap(),
+            "/tmp/contacts.csv"
+        );
+        assert!(PhoneBook::expand_path("$PHONE_BOOK_UNDEFINED_VARIABLE/contacts.csv").is_err());
+    }
+
+    #[test]
+    fn prefix_search_matches_the_start_of_a_name_only() {
+        let mut book = open_book();
+        book.add(contact("John", "Doe", "5551234567", "")).unwrap();
+        book.add(contact("Bojo", "Smith", "5559876543", ""))
+            .unwrap();
+        book.add(contact("Mary", "Jones", "5550001111", ""))
+            .unwrap();
+        let found = PhoneBook::search_contacts_by_name_prefix("jo").unwrap();
+        let mut names: Vec<&str> = found.iter().map(|c| c.first_name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["John", "Mary"]);
+        assert!(PhoneBook::search_contacts_by_name_prefix("%")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn interrupted_import_batch_is_rolled_back() {
+        let book = open_book();
+        let contacts = [
+            contact("John", "Doe", "5551234567", ""),
+            contact("Jane", "Roe", "5559876543", ""),
+            contact("Mary", "Major", "5550001111", ""),
+        ];
+        PhoneBook::insert_contacts(&contacts[..1], "import").unwrap();
+        // Held so the interrupted batch doesn't exit the test process once it is rolled back
+        let busy = BusyGuard::enter();
+        shutdown::set_requested(true);
+        let interrupted = PhoneBook::insert_contacts(&contacts[1..], "import");
+        shutdown::set_requested(false);
+        drop(busy);
+        assert_eq!(interrupted, Err(diesel::result::Error::RollbackTransaction));
+        let names: Vec<String> = book
+            .list(None)
+            .unwrap()
+            .into_iter()
+            .map(|contact| contact.first_name)
+            .collect();
+        assert_eq!(names, ["John"]);
+    }
+}
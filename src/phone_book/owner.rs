@@ -0,0 +1,127 @@
+use diesel::prelude::*;
+
+use crate::connection::establish_connection;
+use crate::phone_book::contact::{Contact, FIELDS};
+use crate::phone_book::phone_book::PhoneBook;
+use crate::settings;
+
+/// Prefix of the keys under which the owner profile is stored in the `settings` table.
+const OWNER_KEY_PREFIX: &str = "owner.";
+
+impl PhoneBook {
+    /// Prompts the user for their own profile and saves it as the phone book's owner.
+    ///
+    /// The owner profile identifies who the phone book belongs to, e.g. when sharing exports.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn set_owner(&mut self) {
+        Self::print_cancel_hint();
+        let mut values = Vec::with_capacity(FIELDS.len());
+        for &(field, label) in FIELDS {
+            let required = field == "first_name";
+            let prompt = format!(
+                "Enter your {} ({}): ",
+                label.to_lowercase(),
+                if required { "required" } else { "optional" }
+            );
+            let Ok(value) = Self::get_input_or_cancel(&prompt) else {
+                println!("Owner profile update cancelled.");
+                return;
+            };
+            if required && value.is_empty() {
+                println!("{} is required. Owner profile update cancelled.", label);
+                return;
+            }
+            values.push(value);
+        }
+        let owner = Self::contact_from_values(values);
+        if let Err(err) = owner.validate() {
+            println!("{} Owner profile update cancelled.", err);
+            return;
+        }
+        match Self::save_owner(&owner) {
+            Ok(()) => println!("Owner profile saved successfully!"),
+            Err(err) => println!("Error saving owner profile: {}", err),
+        }
+    }
+    /// Displays the owner profile.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the owner profile to the console.
+    pub(crate) fn show_owner(&mut self) {
+        match Self::get_owner() {
+            Ok(Some(owner)) => owner.print_contact(),
+            Ok(None) => println!("No owner profile set. Set one with SO."),
+            Err(err) => println!("Error fetching owner profile: {}", err),
+        }
+    }
+    /// Saves the owner profile to the `settings` table, one key per field, in a transaction.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The owner's details.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the profile was saved.
+    pub fn save_owner(owner: &Contact) -> QueryResult<()> {
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            for &(field, _) in FIELDS {
+                let key = format!("{}{}", OWNER_KEY_PREFIX, field);
+                settings::set_stored(connection, &key, owner.field(field))?;
+            }
+            Ok(())
+        })
+    }
+    /// Loads the owner profile from the `settings` table.
+    ///
+    /// # Returns
+    ///
+    /// The owner's details, or `None` if no owner profile has been set.
+    pub fn get_owner() -> QueryResult<Option<Contact>> {
+        let mut connection = establish_connection();
+        let mut owner = Contact::default();
+        for &(field, _) in FIELDS {
+            let key = format!("{}{}", OWNER_KEY_PREFIX, field);
+            match settings::get_stored(&mut connection, &key)? {
+                Some(value) => owner.set_field(field, value),
+                // Fields added after the profile was saved are left empty
+                None if field != "first_name" => {}
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(owner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contact, open_book};
+
+    #[test]
+    fn owner_profile_is_read_back() {
+        let _book = open_book();
+        assert!(PhoneBook::get_owner().unwrap().is_none());
+        let mut owner = contact("Ada", "Lovelace", "5551234567", "ada@example.com");
+        owner.company = "Analytical Engines".to_string();
+        PhoneBook::save_owner(&owner).unwrap();
+
+        let saved = PhoneBook::get_owner().unwrap().unwrap();
+        for &(field, _) in FIELDS {
+            assert_eq!(saved.field(field), owner.field(field), "{}", field);
+        }
+    }
+}
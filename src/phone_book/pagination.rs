@@ -0,0 +1,101 @@
+use chrono::NaiveDateTime;
+
+use crate::phone_book::contact::Contact;
+use crate::phone_book::phone_book::PhoneBook;
+use crate::settings;
+
+/// Define where the user left a paged listing, so listing again in the same order resumes there.
+pub(crate) struct ListPosition {
+    /// Index of the first contact of the page shown last.
+    offset: usize,
+    /// Number of contacts listed and latest update among them. When either changes, contacts
+    /// were added, updated or deleted since, and the listing starts over from the first page.
+    fingerprint: (usize, Option<NaiveDateTime>),
+}
+
+impl ListPosition {
+    /// Computes the fingerprint of a listing.
+    ///
+    /// # Parameters
+    ///
+    /// * `contacts` - The listed contacts.
+    ///
+    /// # Returns
+    ///
+    /// The number of contacts and the latest time one of them was updated.
+    fn fingerprint(contacts: &[Contact]) -> (usize, Option<NaiveDateTime>) {
+        (
+            contacts.len(),
+            contacts.iter().map(|contact| contact.updated_at).max(),
+        )
+    }
+}
+
+impl PhoneBook {
+    /// Prints the last listed contacts a page at a time, letting the user move to the next or
+    /// previous page.
+    ///
+    /// The page the user stops on is remembered for the session, per order, so listing again in
+    /// the same order resumes there unless the contacts changed meanwhile. Pages hold as many
+    /// contacts as the `PAGE_SIZE` setting, and listings fitting on a single page are printed
+    /// whole.
+    ///
+    /// # Parameters
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance, whose `contacts` are
+    ///   printed.
+    /// * `order` - The key of the order the contacts are listed in, e.g. `last_asc`.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the pages to the console.
+    pub(crate) fn print_contact_pages(&mut self, order: &str) {
+        // The setting is validated at startup, so fall back to the default if it has since changed.
+        let page_size = settings::page_size().unwrap_or(settings::DEFAULT_PAGE_SIZE);
+        let total = self.contacts.len();
+        if total <= page_size {
+            self.list_positions.remove(order);
+            self.print_contacts(&self.contacts);
+            return;
+        }
+        let pages = total.div_ceil(page_size);
+        let fingerprint = ListPosition::fingerprint(&self.contacts);
+        let mut offset = match self.list_positions.get(order) {
+            Some(position) if position.fingerprint == fingerprint && position.offset > 0 => {
+                println!(
+                    "Resuming at page {} of {}.",
+                    position.offset / page_size + 1,
+                    pages
+                );
+                position.offset
+            }
+            _ => 0,
+        };
+        loop {
+            let end = (offset + page_size).min(total);
+            println!(
+                "{}",
+                self.contacts_listing(&self.contacts[offset..end], offset)
+            );
+            self.list_positions.insert(
+                order.to_string(),
+                ListPosition {
+                    offset,
+                    fingerprint,
+                },
+            );
+            let choice = Self::get_input(&format!(
+                "Page {} of {}: (n)ext, (p)revious, or Enter to stop: ",
+                offset / page_size + 1,
+                pages
+            ));
+            match choice.to_lowercase().as_str() {
+                "n" if end < total => offset = end,
+                "p" if offset > 0 => offset = offset.saturating_sub(page_size),
+                "n" => println!("This is the last page."),
+                "p" => println!("This is the first page."),
+                _ => break,
+            }
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use std::fs;
+
+use chrono::Local;
+use printpdf::{
+    BuiltinFont, Error as PdfError, IndirectFontRef, Line, Mm, PdfDocument, PdfLayerReference,
+    Point,
+};
+
+use crate::phone_book::contact::{Contact, FIELDS};
+use crate::phone_book::phone_book::PhoneBook;
+use crate::settings;
+
+/// Width of the pages, A4 in landscape so more columns fit, in millimeters.
+const PAGE_WIDTH: f32 = 297.0;
+
+/// Height of the pages, in millimeters.
+const PAGE_HEIGHT: f32 = 210.0;
+
+/// Blank space around the content of each page, in millimeters.
+const MARGIN: f32 = 15.0;
+
+/// Font size of the table, in points.
+const FONT_SIZE: f32 = 9.0;
+
+/// Height of a table row, in millimeters.
+const ROW_HEIGHT: f32 = 6.0;
+
+/// Average width of a Helvetica character relative to the font size, used to cut text that
+/// wouldn't fit its column.
+const AVERAGE_CHAR_WIDTH: f32 = 0.5;
+
+/// Millimeters per typographic point.
+const MM_PER_POINT: f32 = 0.3528;
+
+/// Writes the rows of the contacts table, a page at a time.
+struct PdfTable<'a> {
+    /// The layer of the current page.
+    layer: PdfLayerReference,
+    /// Font of the cells.
+    regular: &'a IndirectFontRef,
+    /// Font of the header row.
+    bold: &'a IndirectFontRef,
+    /// The header labels.
+    header: Vec<&'a str>,
+    /// Width of each column, in millimeters.
+    column_width: f32,
+    /// Baseline of the next row, from the bottom of the page, in millimeters.
+    y: f32,
+}
+
+impl PdfTable<'_> {
+    /// Writes a row of cells, cutting the text too long for its column.
+    ///
+    /// # Arguments
+    ///
+    /// * `cells` - The text of each cell.
+    /// * `bold` - Whether to write them in bold, as in the header.
+    fn write_row(&mut self, cells: &[&str], bold: bool) {
+        let font = if bold { self.bold } else { self.regular };
+        let max_chars =
+            (self.column_width / (FONT_SIZE * MM_PER_POINT * AVERAGE_CHAR_WIDTH)) as usize - 1;
+        for (column, cell) in cells.iter().enumerate() {
+            let x = MARGIN + column as f32 * self.column_width;
+            self.layer
+                .use_text(fit(cell, max_chars), FONT_SIZE, Mm(x), Mm(self.y), font);
+        }
+        self.y -= ROW_HEIGHT;
+    }
+    /// Writes the header row with a rule under it.
+    fn write_header(&mut self) {
+        let header = self.header.clone();
+        self.write_row(&header, true);
+        let rule_y = self.y + ROW_HEIGHT - 1.5;
+        self.layer.add_line(Line {
+            points: vec![
+                (Point::new(Mm(MARGIN), Mm(rule_y)), false),
+                (Point::new(Mm(PAGE_WIDTH - MARGIN), Mm(rule_y)), false),
+            ],
+            is_closed: false,
+        });
+    }
+}
+
+/// Cuts a text to a number of characters, ending it with `...` when it is cut.
+///
+/// # Arguments
+///
+/// * `text` - The text.
+/// * `max_chars` - The most characters to keep.
+///
+/// # Returns
+///
+/// The text, cut if needed.
+fn fit(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+    format!("{}...", kept)
+}
+
+impl PhoneBook {
+    /// Renders contacts as a PDF document: a title, the date it was generated, and a table with
+    /// a column per field whose header is repeated on every page.
+    ///
+    /// The document uses the standard Helvetica font, which covers Western European characters
+    /// only; other characters are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `contacts` - The contacts to write.
+    /// * `columns` - The fields to write, in order, e.g. from the `EXPORT_COLUMNS` setting.
+    /// * `generated` - When the document was generated, as shown under the title.
+    ///
+    /// # Returns
+    ///
+    /// The bytes of the PDF file, or the error raised while building it.
+    pub(crate) fn contacts_to_pdf(
+        contacts: &[Contact],
+        columns: &[String],
+        generated: &str,
+    ) -> Result<Vec<u8>, PdfError> {
+        let header = columns
+            .iter()
+            .map(|field| {
+                FIELDS
+                    .iter()
+                    .find(|&&(name, _)| name == field)
+                    .map_or(field.as_str(), |&(_, label)| label)
+            })
+            .collect();
+        let rows: Vec<Vec<&str>> = contacts
+            .iter()
+            .map(|contact| columns.iter().map(|field| contact.field(field)).collect())
+            .collect();
+        Self::table_to_pdf(header, &rows, generated)
+    }
+    /// Renders a table as a PDF document: a title, the date it was generated, and the table
+    /// with its header repeated on every page and columns of equal width.
+    ///
+    /// # Arguments
+    ///
+    /// * `header` - The label of each column.
+    /// * `rows` - The cells of each row, a contact per row.
+    /// * `generated` - When the document was generated, as shown under the title.
+    ///
+    /// # Returns
+    ///
+    /// The bytes of the PDF file, or the error raised while building it.
+    pub(crate) fn table_to_pdf(
+        header: Vec<&str>,
+        rows: &[Vec<&str>],
+        generated: &str,
+    ) -> Result<Vec<u8>, PdfError> {
+        let (document, page, layer) =
+            PdfDocument::new("Phone Book", Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Contacts");
+        let regular = document.add_builtin_font(BuiltinFont::Helvetica)?;
+        let bold = document.add_builtin_font(BuiltinFont::HelveticaBold)?;
+        let layer = document.get_page(page).get_layer(layer);
+        let mut y = PAGE_HEIGHT - MARGIN - 5.0;
+        layer.use_text("Phone Book", 18.0, Mm(MARGIN), Mm(y), &bold);
+        y -= 7.0;
+        layer.use_text(
+            format!("Generated on {} - {} contact(s)", generated, rows.len()),
+            FONT_SIZE,
+            Mm(MARGIN),
+            Mm(y),
+            &regular,
+        );
+        let mut table = PdfTable {
+            layer,
+            regular: &regular,
+            bold: &bold,
+            column_width: (PAGE_WIDTH - 2.0 * MARGIN) / header.len().max(1) as f32,
+            header,
+            y: y - 10.0,
+        };
+        table.write_header();
+        for row in rows {
+            if table.y < MARGIN {
+                let (page, layer) = document.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Contacts");
+                table.layer = document.get_page(page).get_layer(layer);
+                table.y = PAGE_HEIGHT - MARGIN - 5.0;
+                table.write_header();
+            }
+            table.write_row(row, false);
+        }
+        document.save_to_bytes()
+    }
+    /// Exports every contact to a PDF document, with the columns of the `EXPORT_COLUMNS`
+    /// setting, to share a formatted list.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn export_pdf(&mut self) {
+        let columns = match settings::export_columns() {
+            Ok(columns) => columns,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        Self::print_cancel_hint();
+        let Ok(file_name) =
+            Self::get_input_or_cancel("Enter the name of the PDF file to export contacts to: ")
+        else {
+            println!("Export cancelled.");
+            return;
+        };
+        let file_name = match Self::expand_path(&file_name) {
+            Ok(file_name) => file_name,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let contacts = match Self::get_contacts("") {
+            Ok(contacts) => contacts,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        let date_format =
+            settings::date_format().unwrap_or_else(|_| settings::DEFAULT_DATE_FORMAT.to_string());
+        let generated = Local::now().format(&date_format).to_string();
+        let pdf = match Self::contacts_to_pdf(&contacts, &columns, &generated) {
+            Ok(pdf) => pdf,
+            Err(err) => {
+                println!("Error generating PDF: {}", err);
+                return;
+            }
+        };
+        match fs::write(&file_name, pdf) {
+            Ok(()) => println!("Exported {} contact(s) to {}.", contacts.len(), file_name),
+            Err(err) => println!("Error writing file: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::Document;
+
+    use super::*;
+    use crate::test_support::{contact, open_book};
+
+    #[test]
+    fn pdf_holds_the_title_date_and_contacts() {
+        let _book = open_book();
+        let columns = vec!["first_name".to_string(), "phone".to_string()];
+        let contacts = [
+            contact("John", "Doe", "5551234567", ""),
+            contact("Jane", "Roe", "5559876543", ""),
+        ];
+        let bytes = PhoneBook::contacts_to_pdf(&contacts, &columns, "2025-04-05").unwrap();
+        assert!(bytes.starts_with(b"%PDF"));
+        let document = Document::load_mem(&bytes).unwrap();
+        assert_eq!(document.get_pages().len(), 1);
+        let text = document.extract_text(&[1]).unwrap();
+        for expected in [
+            "Phone Book",
+            "Generated on 2025-04-05 - 2 contact(s)",
+            "First name",
+            "John",
+            "(555) 987-6543",
+        ] {
+            assert!(text.contains(expected), "'{}' not in:\n{}", expected, text);
+        }
+    }
+}
@@ -1,9 +1,76 @@
+use std::collections::HashMap;
+use std::io::{self, IsTerminal};
+
+use crate::connection;
 use crate::phone_book::contact::Contact;
+use crate::phone_book::pagination::ListPosition;
+use crate::settings;
+use crate::shutdown;
+/**
+ * Define the command-line options accepted by the phone book.
+ */
+#[derive(Default)]
+pub struct Options {
+    /// Show when each contact was created and last updated in listings (`--show-timestamps`).
+    pub show_timestamps: bool,
+    /// List contacts one per line instead of in a bordered table (`--compact`).
+    pub compact: bool,
+    /// Print the SQL of every query run to stderr (`--verbose`).
+    pub verbose: bool,
+    /// Skip the startup dashboard, even if `SHOW_DASHBOARD` is set (`--quiet`).
+    pub quiet: bool,
+    /// Path of the database to use instead of `DATABASE_URL` (`--database <path>`).
+    pub database: Option<String>,
+}
+/**
+ * Options struct implementation.
+ */
+impl Options {
+    /// Parses the options from the command-line arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The command-line arguments, excluding the program name.
+    ///
+    /// # Returns
+    ///
+    /// The parsed `Options`, or an error message naming the first unrecognised argument or the
+    /// `--database` option missing its path.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--show-timestamps" => options.show_timestamps = true,
+                "--compact" => options.compact = true,
+                "--verbose" => options.verbose = true,
+                "--quiet" => options.quiet = true,
+                "--database" => match args.next() {
+                    Some(path) if !path.is_empty() => options.database = Some(path),
+                    _ => return Err("Missing path after --database".to_string()),
+                },
+                _ => match arg.strip_prefix("--database=") {
+                    Some(path) if !path.is_empty() => options.database = Some(path.to_string()),
+                    Some(_) => return Err("Missing path after --database".to_string()),
+                    None => return Err(format!("Unknown option: {}", arg)),
+                },
+            }
+        }
+        Ok(options)
+    }
+}
 /**
  * Define a PhoneBook struct with a field for a vector of Contact structs.
  */
 pub struct PhoneBook {
     pub contacts: Vec<Contact>,
+    pub(crate) options: Options,
+    pub(crate) running: bool,
+    /// Where the user left each paged listing, by order, for the rest of the session.
+    pub(crate) list_positions: HashMap<String, ListPosition>,
+    /// The database opened with `PhoneBook::open`, `None` for the one of `--database` or
+    /// `DATABASE_URL`.
+    pub(crate) database_url: Option<String>,
 }
 /**
  * Implement the Default trait for the PhoneBook struct.
@@ -12,17 +79,24 @@ impl Default for PhoneBook {
     /// Creates a new `PhoneBook` instance with default values.
     ///
     /// This function implements the `Default` trait for the `PhoneBook` struct.
-    /// It initializes a new `PhoneBook` with an empty contacts list and an empty message.
+    /// It initializes a new `PhoneBook` with an empty contacts list.
     ///
     /// # Returns
     ///
     /// A new `PhoneBook` instance with:
     /// - `contacts`: An empty vector of `Contact` structs.
-    /// - `msg`: An empty `String` for storing messages.
+    /// - `options`: The default `Options`.
+    /// - `running`: `true` until the user chooses to exit.
+    /// - `list_positions`: No remembered listing pages.
+    /// - `database_url`: `None`, for the database of `--database` or `DATABASE_URL`.
     fn default() -> Self {
         println!("Welcome to the Phone Book!");
         Self {
             contacts: Vec::new(),
+            options: Options::default(),
+            running: true,
+            list_positions: HashMap::new(),
+            database_url: None,
         }
     }
 }
@@ -32,38 +106,71 @@ impl Default for PhoneBook {
 impl PhoneBook {
     /// Starts the phone book application and handles user interactions.
     ///
-    /// This function initializes a new `PhoneBook` instance, displays the available operations,
-    /// and processes user input to perform various operations on the phone book.
+    /// This function opens the database given with `--database`, or else by `DATABASE_URL`,
+    /// validates the settings, installs the Ctrl-C handler, initializes a new `PhoneBook`
+    /// instance, shows the dashboard if enabled, and processes user input, dispatching each
+    /// command through the operations table until the user chooses to exit.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The command-line options to run with.
     ///
     /// # Examples
     ///
-    /// ```
-    /// use phone_book_management_cli_rust::phone_book::PhoneBook;
+    /// ```no_run
+    /// use phone_book_management_cli_rust::phone_book::{Options, PhoneBook};
     ///
-    /// PhoneBook::start();
+    /// PhoneBook::start(Options::default());
     /// ```
-    pub fn start() {
-        let mut phone_book = Self::default();
-        loop {
+    pub fn start(options: Options) {
+        if let Some(database) = &options.database {
+            connection::use_database(database);
+        }
+        // Opening the database first also migrates it, and reports a bad path without a panic
+        if let Err(err) = connection::try_establish_connection() {
+            println!("{}", err);
+            return;
+        }
+        if let Err(err) = settings::validate() {
+            println!("{}", err);
+            return;
+        }
+        if options.verbose {
+            if let Err(err) = connection::log_queries() {
+                println!("Could not turn on query logging: {}", err);
+            }
+        }
+        if let Err(err) = shutdown::install_handler() {
+            println!("Could not install the Ctrl-C handler: {}", err);
+        }
+        let mut phone_book = Self {
+            options,
+            ..Self::default()
+        };
+        match Self::seed_if_empty() {
+            Ok(Some((file_name, seeded))) => {
+                println!("Seeded {} contact(s) from '{}'.", seeded, file_name)
+            }
+            Ok(None) => {}
+            Err(err) => println!("Error seeding the phone book: {}", err),
+        }
+        if let Some(warning) = Self::database_info()
+            .ok()
+            .and_then(|info| info.size_warning())
+        {
+            println!("{}", warning);
+        }
+        if !phone_book.options.quiet
+            && io::stdin().is_terminal()
+            && settings::show_dashboard().unwrap_or(false)
+        {
+            Self::print_dashboard();
+        }
+        while phone_book.running {
             let operation = Self::get_input("Enter an operation or ? for help: ").to_uppercase();
-            match operation.as_str() {
-                "C" => phone_book.create_contact(),
-                "Q" => phone_book.search_contact(),
-                "F" => phone_book.load_contacts_from_csv(),
-                "U" => phone_book.update_contact(),
-                "D" => phone_book.delete_contact(),
-                "E" => {
-                    let confirmation = Self::get_input("Are you sure you want to exit? (y/n): ");
-                    if confirmation == "y" {
-                        println!("Exiting the phone book...");
-                        break;
-                    }
-                }
-                "L" => phone_book.list_contacts_in_order(""),
-                "A" => phone_book.list_contacts_in_order("asc"),
-                "Z" => phone_book.list_contacts_in_order("desc"),
-                "?" => phone_book.show_operations(),
-                _ => println!("Invalid operation: {}", operation),
+            match Self::find_operation(&operation) {
+                Some(handler) => handler(&mut phone_book),
+                None => println!("Invalid operation: {}", operation),
             }
         }
     }
@@ -0,0 +1,92 @@
+use comfy_table::Cell;
+
+use crate::phone_book::contact::styled_table;
+use crate::phone_book::phone_book::PhoneBook;
+use crate::settings;
+
+impl PhoneBook {
+    /// Lets the user change the settings stored in the database, so preferences survive across
+    /// runs without editing `.env`.
+    ///
+    /// The new value is validated before it is kept; an invalid value is rolled back. Variables
+    /// set in the environment still take precedence over stored values.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn configure(&mut self) {
+        Self::print_settings();
+        Self::print_cancel_hint();
+        let Ok(name) = Self::get_input_or_cancel("Enter the setting to change: ") else {
+            println!("Configuration cancelled.");
+            return;
+        };
+        let name = name.to_uppercase();
+        if !settings::CONFIGURABLE
+            .iter()
+            .any(|&(setting, _)| setting == name)
+        {
+            println!("Unknown setting: {}", name);
+            return;
+        }
+        let Ok(value) = Self::get_input_or_cancel(&format!(
+            "Enter the new value of {} (leave empty to reset to the default): ",
+            name
+        )) else {
+            println!("Configuration cancelled.");
+            return;
+        };
+        let previous = settings::stored(&name);
+        let new_value = Some(value.as_str()).filter(|value| !value.is_empty());
+        if let Err(err) = settings::store(&name, new_value) {
+            println!("Error saving setting: {}", err);
+            return;
+        }
+        if let Err(err) = settings::validate() {
+            println!("{} The previous value was kept.", err);
+            if let Err(err) = settings::store(&name, previous.as_deref()) {
+                println!("Error restoring setting: {}", err);
+            }
+            return;
+        }
+        if settings::env_var(&name).is_some() {
+            println!(
+                "{} is set in the environment, which overrides the stored value.",
+                name
+            );
+        }
+        println!("Setting saved successfully!");
+    }
+    /// Prints the configurable settings with their current value and where it comes from.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the table to the console.
+    fn print_settings() {
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("Setting").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Value").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Source").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Description").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        for &(name, description) in settings::CONFIGURABLE {
+            let (value, source) = match (settings::env_var(name), settings::stored(name)) {
+                (Some(value), _) => (value, "environment"),
+                (None, Some(value)) => (value, "stored"),
+                (None, None) => (String::new(), "default"),
+            };
+            table.add_row(vec![
+                Cell::new(name),
+                Cell::new(value),
+                Cell::new(source),
+                Cell::new(description),
+            ]);
+        }
+        println!("{}", table);
+    }
+}
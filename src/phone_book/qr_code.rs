@@ -0,0 +1,117 @@
+use image::Luma;
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+
+use crate::phone_book::contact::Contact;
+use crate::phone_book::phone_book::PhoneBook;
+
+/// Width and height, in pixels, of each module (square) of a QR code saved as PNG.
+const PNG_MODULE_SIZE: u32 = 8;
+
+impl PhoneBook {
+    /// Shows a contact's vCard as a QR code, which a phone camera can scan to add the contact.
+    ///
+    /// The QR code is drawn in the terminal with Unicode blocks, or saved as a PNG image if the
+    /// user enters a file name.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the QR code or the outcome to the
+    /// console.
+    pub(crate) fn show_qr_code(&mut self) {
+        let Some(contact) =
+            self.prompt_listed_contact("Enter the index of the contact to share as a QR code: ")
+        else {
+            return;
+        };
+        let Ok(file_name) = Self::get_input_or_cancel(
+            "Enter the name of the PNG file to save (leave empty to show it in the terminal): ",
+        ) else {
+            println!("Cancelled.");
+            return;
+        };
+        let code = match Self::vcard_qr_code(&contact) {
+            Ok(code) => code,
+            Err(err) => {
+                println!("Error generating QR code: {}", err);
+                return;
+            }
+        };
+        if file_name.is_empty() {
+            // Light on dark is what most scanners expect from a dark terminal background.
+            let rendered = code
+                .render::<Dense1x2>()
+                .dark_color(Dense1x2::Light)
+                .light_color(Dense1x2::Dark)
+                .build();
+            println!("{}", rendered);
+            return;
+        }
+        let file_name = match Self::expand_path(&file_name) {
+            Ok(file_name) => file_name,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        match Self::save_qr_code_png(&code, &file_name) {
+            Ok(()) => println!("QR code saved to {}.", file_name),
+            Err(err) => println!("Error saving QR code: {}", err),
+        }
+    }
+    /// Encodes a contact's vCard as a QR code.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - The contact to encode.
+    ///
+    /// # Returns
+    ///
+    /// The QR code, or an error if the vCard is too long to fit in one.
+    pub(crate) fn vcard_qr_code(contact: &Contact) -> Result<QrCode, qrcode::types::QrError> {
+        // No sender, so the code stays small enough to scan easily
+        QrCode::new(contact.to_vcard(None).as_bytes())
+    }
+    /// Saves a QR code as a black-on-white PNG image.
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - The QR code to save.
+    /// * `file_name` - The path of the PNG file to write.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the image was written.
+    pub(crate) fn save_qr_code_png(code: &QrCode, file_name: &str) -> image::ImageResult<()> {
+        code.render::<Luma<u8>>()
+            .module_dimensions(PNG_MODULE_SIZE, PNG_MODULE_SIZE)
+            .build()
+            .save_with_format(file_name, image::ImageFormat::Png)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_support::{contact, open_book, temp_path};
+
+    #[test]
+    fn vcard_qr_code_is_saved_as_a_png_image() {
+        let _book = open_book();
+        let code = PhoneBook::vcard_qr_code(&contact("John", "Doe", "5551234567", "")).unwrap();
+        let path = temp_path("png");
+        let file_name = path.to_str().unwrap();
+        PhoneBook::save_qr_code_png(&code, file_name).unwrap();
+        let image = image::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let size = code.width() as u32 * PNG_MODULE_SIZE;
+        assert!(image.width() > size);
+        assert_eq!(image.width(), image.height());
+    }
+}
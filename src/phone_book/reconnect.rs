@@ -0,0 +1,201 @@
+use chrono::{Local, NaiveDate};
+use comfy_table::Cell;
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_types::Date;
+
+use crate::connection::establish_connection;
+use crate::phone_book::audit::{AuditEntry, ContactSnapshot};
+use crate::phone_book::contact::{styled_table, Contact};
+use crate::phone_book::phone_book::PhoneBook;
+use crate::schema::contacts;
+use crate::shutdown::BusyGuard;
+
+/// Number of days without contact after which someone is listed by RC when the user doesn't
+/// enter another number.
+const DEFAULT_RECONNECT_DAYS: u32 = 30;
+
+impl PhoneBook {
+    /// Records that the user got in touch with a contact today.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn mark_contacted(&mut self) {
+        let Some(contact) =
+            self.prompt_listed_contact("Enter the index of the contact you talked to: ")
+        else {
+            return;
+        };
+        let today = Local::now().date_naive();
+        match Self::set_last_contacted(&contact, today) {
+            Ok(()) => println!(
+                "Marked {} {} as contacted on {}.",
+                contact.first_name, contact.last_name, today
+            ),
+            Err(err) => println!("Error updating contact: {}", err),
+        }
+    }
+    /// Saves the day a contact was last contacted, recording the change in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `contact` - The contact.
+    /// * `day` - The day the user got in touch with them.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the day was saved.
+    pub fn set_last_contacted(contact: &Contact, day: NaiveDate) -> QueryResult<()> {
+        let Some(contact_id) = contact.id else {
+            return Err(diesel::result::Error::NotFound);
+        };
+        let _busy = BusyGuard::enter();
+        let mut connection = establish_connection();
+        connection.transaction(|connection| {
+            let before = ContactSnapshot::load(connection, contact_id)?;
+            diesel::update(contacts::table.filter(contacts::id.eq(contact_id)))
+                .set(contacts::last_contacted.eq(day))
+                .execute(connection)?;
+            AuditEntry::record(
+                connection,
+                "mark contacted",
+                Some(contact_id),
+                before.as_ref(),
+            )
+        })
+    }
+    /// Lists the contacts the user hasn't been in touch with for a number of days, longest first,
+    /// so they know who to reconnect with.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the table to the console.
+    pub(crate) fn list_reconnect(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        let input = Self::get_input(&format!(
+            "List contacts not contacted for how many days? [{}]: ",
+            DEFAULT_RECONNECT_DAYS
+        ));
+        let days = if input.is_empty() {
+            DEFAULT_RECONNECT_DAYS
+        } else {
+            match input.parse::<u32>() {
+                Ok(days) => days,
+                Err(_) => {
+                    println!("Invalid number of days!");
+                    return;
+                }
+            }
+        };
+        let contacts = match Self::not_contacted_since(days) {
+            Ok(contacts) => contacts,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        if contacts.is_empty() {
+            println!(
+                "You've been in touch with everyone in the last {} days.",
+                days
+            );
+            return;
+        }
+        let today = Local::now().date_naive();
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("Id").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Name").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Phone Number").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Last Contacted").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        for contact in &contacts {
+            let last_contacted = match contact.last_contacted {
+                Some(day) => format!("{} ({} days ago)", day, (today - day).num_days()),
+                None => "never".to_string(),
+            };
+            table.add_row(vec![
+                Cell::new(contact.id.map_or(String::new(), |id| id.to_string())),
+                Cell::new(format!("{} {}", contact.first_name, contact.last_name).trim()),
+                Cell::new(&contact.phone),
+                Cell::new(last_contacted),
+            ]);
+        }
+        println!("{}", table);
+        println!(
+            "{} contact(s) not contacted in the last {} days. Mark one as contacted with MC.",
+            contacts.len(),
+            days
+        );
+    }
+    /// Finds the contacts not contacted for a number of days, including those never contacted.
+    ///
+    /// # Arguments
+    ///
+    /// * `days` - The number of days.
+    ///
+    /// # Returns
+    ///
+    /// The contacts, never contacted first and then from the longest ago.
+    pub fn not_contacted_since(days: u32) -> QueryResult<Vec<Contact>> {
+        let mut connection = establish_connection();
+        // Days are recorded in local time, so the cutoff is computed in local time too
+        let cutoff = sql::<Date>(&format!("date('now', 'localtime', '-{} days')", days));
+        contacts::table
+            .filter(
+                contacts::last_contacted
+                    .is_null()
+                    .or(contacts::last_contacted.le(cutoff.nullable())),
+            )
+            .order((
+                contacts::last_contacted.asc(),
+                contacts::first_name.asc(),
+                contacts::last_name.asc(),
+            ))
+            .load::<Contact>(&mut connection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Days;
+
+    use super::*;
+    use crate::test_support::{contact, open_book};
+
+    #[test]
+    fn only_contacts_not_contacted_for_the_days_are_listed() {
+        let mut book = open_book();
+        let today = Local::now().date_naive();
+        for (first_name, days_ago) in [("Old", Some(45)), ("Recent", Some(3)), ("Never", None)] {
+            let id = book
+                .add(contact(first_name, "Doe", "5551234567", ""))
+                .unwrap();
+            if let Some(days_ago) = days_ago {
+                let saved = book.get(id).unwrap().unwrap();
+                PhoneBook::set_last_contacted(&saved, today - Days::new(days_ago)).unwrap();
+            }
+        }
+        let names = |days| -> Vec<String> {
+            PhoneBook::not_contacted_since(days)
+                .unwrap()
+                .into_iter()
+                .map(|contact| contact.first_name)
+                .collect()
+        };
+        assert_eq!(names(30), ["Never", "Old"]);
+        assert_eq!(names(3), ["Never", "Old", "Recent"]);
+        assert_eq!(names(60), ["Never"]);
+    }
+}
@@ -0,0 +1,182 @@
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+
+use crate::connection::establish_connection;
+use crate::phone_book::contact::Contact;
+use crate::phone_book::phone_book::PhoneBook;
+use crate::schema::{contacts, relationships};
+
+/// Define a Relationship struct linking a contact to another one, e.g. a spouse or a manager.
+///
+/// Relationships are directional: the label describes what `related_contact_id` is to
+/// `contact_id`.
+#[derive(Insertable, Queryable, Clone)]
+#[diesel(table_name = relationships)]
+pub struct Relationship {
+    pub(crate) id: Option<i32>, // Diesel uses Option for nullable integer fields.
+    pub(crate) contact_id: i32,
+    pub(crate) related_contact_id: i32,
+    pub(crate) label: String,
+}
+/// Relationship struct implementation.
+impl Relationship {
+    /// Creates a new `Relationship` between two contacts.
+    ///
+    /// # Parameters
+    ///
+    /// * `contact_id`: The id of the contact the relationship belongs to.
+    /// * `related_contact_id`: The id of the related contact.
+    /// * `label`: What the related contact is to the contact, e.g. "Spouse".
+    ///
+    /// # Returns
+    ///
+    /// A new, not yet saved, `Relationship` instance.
+    pub fn new(contact_id: i32, related_contact_id: i32, label: String) -> Self {
+        Self {
+            id: None,
+            contact_id,
+            related_contact_id,
+            label,
+        }
+    }
+    /// Loads the relationships of a contact along with the related contacts.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    /// * `contact_id`: The id of the contact whose relationships to load.
+    ///
+    /// # Returns
+    ///
+    /// The `(label, related contact)` pairs, in the order they were added.
+    pub fn load_for(
+        connection: &mut SqliteConnection,
+        contact_id: i32,
+    ) -> QueryResult<Vec<(String, Contact)>> {
+        relationships::table
+            .inner_join(
+                contacts::table.on(contacts::id.eq(relationships::related_contact_id.nullable())),
+            )
+            .filter(relationships::contact_id.eq(contact_id))
+            .order(relationships::id.asc())
+            .select((relationships::label, contacts::all_columns))
+            .load::<(String, Contact)>(connection)
+    }
+    /// Formats a relationship for the detail view, e.g. `Spouse: Jane Doe (#3)`.
+    ///
+    /// # Parameters
+    ///
+    /// * `label`: The relationship label.
+    /// * `related`: The related contact.
+    ///
+    /// # Returns
+    ///
+    /// The formatted relationship.
+    pub fn describe(label: &str, related: &Contact) -> String {
+        let name = format!("{} {}", related.first_name, related.last_name);
+        format!(
+            "{}: {} (#{})",
+            label,
+            name.trim(),
+            related.id.unwrap_or_default()
+        )
+    }
+}
+
+impl PhoneBook {
+    /// Links two contacts from the last listing with a relationship, e.g. a spouse or a manager.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn link_contacts(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        Self::print_cancel_hint();
+        let Ok(index) =
+            self.prompt_contact_index("Enter the index of the contact to add a relationship to: ")
+        else {
+            println!("Relationship creation cancelled.");
+            return;
+        };
+        let Ok(related_index) =
+            self.prompt_contact_index("Enter the index of the related contact: ")
+        else {
+            println!("Relationship creation cancelled.");
+            return;
+        };
+        let (Some(contact_id), Some(related_contact_id)) = (
+            self.contacts[index - 1].id,
+            self.contacts[related_index - 1].id,
+        ) else {
+            println!("Invalid contact index!");
+            return;
+        };
+        if contact_id == related_contact_id {
+            println!("A contact can't be related to itself.");
+            return;
+        }
+        let label = match Self::get_input_or_cancel(
+            "Enter what the related contact is to the contact, e.g. Spouse or Manager: ",
+        ) {
+            Ok(label) if !label.is_empty() => label,
+            Ok(_) => {
+                println!("A relationship label is required. Relationship creation cancelled.");
+                return;
+            }
+            Err(_) => {
+                println!("Relationship creation cancelled.");
+                return;
+            }
+        };
+        let mut connection = establish_connection();
+        match diesel::insert_into(relationships::table)
+            .values(&Relationship::new(contact_id, related_contact_id, label))
+            .execute(&mut connection)
+        {
+            Ok(_) => println!("Relationship added successfully!"),
+            Err(err) => println!("Error saving relationship: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contact, open_book};
+
+    #[test]
+    fn relationship_is_read_back_and_removed_with_the_contact() {
+        let mut book = open_book();
+        let john = book.add(contact("John", "Doe", "5551234567", "")).unwrap();
+        let jane = book.add(contact("Jane", "Doe", "5551234568", "")).unwrap();
+        let mut connection = establish_connection();
+        diesel::insert_into(relationships::table)
+            .values(&Relationship::new(john, jane, "Spouse".to_string()))
+            .execute(&mut connection)
+            .unwrap();
+
+        let relationships = Relationship::load_for(&mut connection, john).unwrap();
+        assert_eq!(relationships.len(), 1);
+        let (label, related) = &relationships[0];
+        assert_eq!(
+            Relationship::describe(label, related),
+            format!("Spouse: Jane Doe (#{})", jane)
+        );
+        assert!(Relationship::load_for(&mut connection, jane)
+            .unwrap()
+            .is_empty());
+
+        assert!(book.delete(jane).unwrap());
+        let remaining: i64 = relationships::table
+            .count()
+            .get_result(&mut connection)
+            .unwrap();
+        assert_eq!(remaining, 0);
+    }
+}
@@ -0,0 +1,422 @@
+use std::collections::BTreeMap;
+
+use chrono::{Duration, Utc};
+use comfy_table::Cell;
+use diesel::dsl::{count, sql};
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+
+use crate::connection::establish_connection;
+use crate::phone_book::contact::{styled_table, Contact};
+use crate::phone_book::phone_book::PhoneBook;
+use crate::schema::contacts;
+
+/// Number of days counted as recent in the dashboard.
+const RECENT_DAYS: i64 = 7;
+
+/// Overview of the phone book shown at startup.
+pub struct Dashboard {
+    /// The number of contacts.
+    pub total: i64,
+    /// The number of contacts created in the last `RECENT_DAYS` days.
+    pub added_recently: i64,
+    /// The number of contacts without a phone number or an email address.
+    pub incomplete: i64,
+}
+
+/// Bucket used in reports for contacts whose phone number has no recognisable US area code.
+const OTHER_AREA_CODE: &str = "Other";
+
+/// Bucket used in reports for contacts without a company.
+const NO_COMPANY: &str = "No company";
+
+impl PhoneBook {
+    /// Prints the number of contacts per phone area code and optionally lists the contacts for one
+    /// of them.
+    ///
+    /// Contacts whose phone number isn't a US number are counted in a separate "Other" bucket,
+    /// shown after the area codes.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the report to the console.
+    pub(crate) fn report_by_area_code(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        let contacts = match Self::get_contacts("") {
+            Ok(contacts) => contacts,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        if contacts.is_empty() {
+            println!("No contacts found.");
+            return;
+        }
+        let (mut groups, other) = Self::group_by_area_code(contacts);
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("Area Code").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Contacts").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        for (area_code, contacts) in &groups {
+            table.add_row(vec![Cell::new(area_code), Cell::new(contacts.len())]);
+        }
+        if !other.is_empty() {
+            table.add_row(vec![Cell::new(OTHER_AREA_CODE), Cell::new(other.len())]);
+        }
+        println!("{}", table);
+
+        let area_code =
+            Self::get_input("Enter an area code to list its contacts (leave empty to skip): ");
+        if area_code.is_empty() {
+            return;
+        }
+        if area_code.eq_ignore_ascii_case(OTHER_AREA_CODE) {
+            self.print_contacts(&other);
+        } else {
+            self.print_contacts(&groups.remove(&area_code).unwrap_or_default());
+        }
+    }
+    /// Groups contacts by the area code of their phone number.
+    ///
+    /// # Arguments
+    ///
+    /// * `contacts` - The contacts to group.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the contacts keyed by area code, in ascending order, and the contacts whose
+    /// phone number has no recognisable area code.
+    pub(crate) fn group_by_area_code(
+        contacts: Vec<Contact>,
+    ) -> (BTreeMap<String, Vec<Contact>>, Vec<Contact>) {
+        let mut groups: BTreeMap<String, Vec<Contact>> = BTreeMap::new();
+        let mut other = Vec::new();
+        for contact in contacts {
+            match contact.area_code() {
+                Some(area_code) => groups.entry(area_code).or_default().push(contact),
+                None => other.push(contact),
+            }
+        }
+        (groups, other)
+    }
+    /// Prints the number of contacts per company and optionally lists the contacts of one of
+    /// them, e.g. everyone at Acme.
+    ///
+    /// Companies are grouped ignoring case. Contacts without a company are counted in a separate
+    /// "No company" bucket, shown after the companies.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the report to the console.
+    pub(crate) fn report_by_company(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        let contacts = match Self::get_contacts("company_asc") {
+            Ok(contacts) => contacts,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        let (groups, no_company) = Self::group_by_company(contacts);
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("Company").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Contacts").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        for contacts in groups.values() {
+            table.add_row(vec![
+                Cell::new(&contacts[0].company),
+                Cell::new(contacts.len()),
+            ]);
+        }
+        if !no_company.is_empty() {
+            table.add_row(vec![Cell::new(NO_COMPANY), Cell::new(no_company.len())]);
+        }
+        println!("{}", table);
+
+        let company =
+            Self::get_input("Enter a company to list its contacts (leave empty to skip): ");
+        if company.is_empty() {
+            return;
+        }
+        if company.eq_ignore_ascii_case(NO_COMPANY) {
+            self.print_contacts(&no_company);
+            return;
+        }
+        match Self::list_by_company(&company) {
+            Ok(contacts) => self.print_contacts(&contacts),
+            Err(err) => println!("Error fetching contacts from the database: {}", err),
+        }
+    }
+    /// Groups contacts by company, ignoring case.
+    ///
+    /// # Arguments
+    ///
+    /// * `contacts` - The contacts to group.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the contacts keyed by lowercased company, in ascending order, and the contacts
+    /// without a company. Each group keeps the order of `contacts`.
+    pub(crate) fn group_by_company(
+        contacts: Vec<Contact>,
+    ) -> (BTreeMap<String, Vec<Contact>>, Vec<Contact>) {
+        let mut groups: BTreeMap<String, Vec<Contact>> = BTreeMap::new();
+        let mut no_company = Vec::new();
+        for contact in contacts {
+            if contact.company.is_empty() {
+                no_company.push(contact);
+            } else {
+                groups
+                    .entry(contact.company.to_lowercase())
+                    .or_default()
+                    .push(contact);
+            }
+        }
+        (groups, no_company)
+    }
+    /// Finds the contacts of a company, ignoring case: "acme" finds the contacts at Acme and
+    /// ACME, but not at Acme Corp.
+    ///
+    /// # Arguments
+    ///
+    /// * `company` - The name of the company.
+    ///
+    /// # Returns
+    ///
+    /// The contacts, sorted by last and first name.
+    pub fn list_by_company(company: &str) -> QueryResult<Vec<Contact>> {
+        let mut connection = establish_connection();
+        // LIKE without wildcards compares the whole value, ignoring case
+        contacts::table
+            .filter(
+                contacts::company
+                    .like(Self::escape_like(company.trim()))
+                    .escape('\\'),
+            )
+            .order((contacts::last_name.asc(), contacts::first_name.asc()))
+            .load::<Contact>(&mut connection)
+    }
+    /// Prints the phone numbers shared by contacts with different names, for review.
+    ///
+    /// A shared number may be intentional, such as a household landline, or a data entry error.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the report to the console.
+    pub(crate) fn report_shared_phones(&mut self) {
+        let shared = match Self::shared_phone_numbers() {
+            Ok(shared) => shared,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        if shared.is_empty() {
+            println!("No phone number is shared by contacts with different names.");
+            return;
+        }
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("Phone Number").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Contacts").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        for (phone, contacts) in &shared {
+            let names: Vec<String> = contacts
+                .iter()
+                .map(|contact| {
+                    let name = format!("{} {}", contact.first_name, contact.last_name);
+                    format!("{} (#{})", name.trim(), contact.id.unwrap_or_default())
+                })
+                .collect();
+            table.add_row(vec![Cell::new(phone), Cell::new(names.join("\n"))]);
+        }
+        println!("{}", table);
+    }
+    /// Finds the phone numbers stored for more than one distinct name, ignoring case.
+    ///
+    /// # Returns
+    ///
+    /// The shared phone numbers in ascending order, each with the contacts using it.
+    pub fn shared_phone_numbers() -> QueryResult<Vec<(String, Vec<Contact>)>> {
+        let mut connection = establish_connection();
+        let phones = contacts::table
+            .filter(contacts::phone.ne(""))
+            .group_by(contacts::phone)
+            .having(
+                count(sql::<Text>("LOWER(first_name || ' ' || last_name)"))
+                    .aggregate_distinct()
+                    .gt(1),
+            )
+            .select(contacts::phone)
+            .order(contacts::phone.asc())
+            .load::<String>(&mut connection)?;
+        let contacts = contacts::table
+            .filter(contacts::phone.eq_any(&phones))
+            .order(contacts::id.asc())
+            .load::<Contact>(&mut connection)?;
+        let mut shared: BTreeMap<String, Vec<Contact>> = BTreeMap::new();
+        for contact in contacts {
+            shared
+                .entry(contact.phone.clone())
+                .or_default()
+                .push(contact);
+        }
+        Ok(shared.into_iter().collect())
+    }
+    /// Prints an overview of the phone book: how many contacts it has, how many were added
+    /// recently and how many are incomplete.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the dashboard to the console.
+    pub(crate) fn print_dashboard() {
+        let dashboard = match Self::dashboard() {
+            Ok(dashboard) => dashboard,
+            Err(err) => {
+                println!("Error computing the dashboard: {}", err);
+                return;
+            }
+        };
+        let mut table = styled_table();
+        table
+            .set_header(vec![
+                Cell::new("Contacts").add_attribute(comfy_table::Attribute::Bold),
+                Cell::new(format!("Added in the last {} days", RECENT_DAYS))
+                    .add_attribute(comfy_table::Attribute::Bold),
+                Cell::new("Missing a phone or email").add_attribute(comfy_table::Attribute::Bold),
+            ])
+            .add_row(vec![
+                Cell::new(dashboard.total),
+                Cell::new(dashboard.added_recently),
+                Cell::new(dashboard.incomplete),
+            ]);
+        println!("{}", table);
+    }
+    /// Counts the contacts shown in the dashboard, with one aggregate query per count.
+    ///
+    /// # Returns
+    ///
+    /// The counts.
+    pub fn dashboard() -> QueryResult<Dashboard> {
+        let mut connection = establish_connection();
+        let since = Utc::now().naive_utc() - Duration::days(RECENT_DAYS);
+        Ok(Dashboard {
+            total: contacts::table.count().get_result(&mut connection)?,
+            added_recently: contacts::table
+                .filter(contacts::created_at.ge(since))
+                .count()
+                .get_result(&mut connection)?,
+            incomplete: contacts::table
+                .filter(contacts::phone.eq("").or(contacts::email.eq("")))
+                .count()
+                .get_result(&mut connection)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contact, open_book};
+
+    #[test]
+    fn contacts_are_grouped_by_area_code() {
+        let _book = open_book();
+        let contacts = vec![
+            contact("Ann", "Lee", "2065550101", ""),
+            contact("Bob", "Ray", "4155550102", ""),
+            contact("Cid", "Moe", "(206) 555-0103", ""),
+            contact("Dee", "Kay", "+44 20 7946 0958", ""),
+        ];
+        let (groups, other) = PhoneBook::group_by_area_code(contacts);
+        let names = |contacts: &[Contact]| -> Vec<String> {
+            contacts.iter().map(|c| c.first_name.clone()).collect()
+        };
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["206", "415"]);
+        assert_eq!(names(&groups["206"]), ["Ann", "Cid"]);
+        assert_eq!(names(&groups["415"]), ["Bob"]);
+        assert_eq!(names(&other), ["Dee"]);
+    }
+
+    #[test]
+    fn phone_shared_by_different_names_is_reported() {
+        let mut book = open_book();
+        book.add(contact("John", "Doe", "5551234567", "")).unwrap();
+        book.add(contact("Jane", "Doe", "5551234567", "")).unwrap();
+        book.add(contact("Mary", "Major", "5559876543", ""))
+            .unwrap();
+        book.add(contact("mary", "major", "5559876543", ""))
+            .unwrap();
+        let shared = PhoneBook::shared_phone_numbers().unwrap();
+        assert_eq!(shared.len(), 1);
+        let (phone, contacts) = &shared[0];
+        assert_eq!(phone, "(555) 123-4567");
+        let names: Vec<&str> = contacts.iter().map(|c| c.first_name.as_str()).collect();
+        assert_eq!(names, ["John", "Jane"]);
+    }
+
+    #[test]
+    fn dashboard_counts_the_inserted_contacts() {
+        let mut book = open_book();
+        book.add(contact("John", "Doe", "5551234567", "john@example.com"))
+            .unwrap();
+        book.add(contact("Jane", "Roe", "5559876543", "")).unwrap();
+        let mut old = contact("Mary", "Major", "5550001111", "mary@example.com");
+        old.created_at -= chrono::Duration::days(RECENT_DAYS + 1);
+        book.add(old).unwrap();
+        let dashboard = PhoneBook::dashboard().unwrap();
+        assert_eq!(dashboard.total, 3);
+        assert_eq!(dashboard.added_recently, 2);
+        assert_eq!(dashboard.incomplete, 1);
+    }
+
+    #[test]
+    fn contacts_are_grouped_by_company_ignoring_case() {
+        let mut book = open_book();
+        for (first_name, company) in [
+            ("Ann", "Acme"),
+            ("Bob", "Globex"),
+            ("Cid", "ACME"),
+            ("Dee", ""),
+            ("Eve", "Acme Corp"),
+        ] {
+            let mut new_contact = contact(first_name, "Doe", "5551234567", "");
+            new_contact.set_field("company", company.to_string());
+            book.add(new_contact).unwrap();
+        }
+        let names = |contacts: &[Contact]| -> Vec<String> {
+            contacts.iter().map(|c| c.first_name.clone()).collect()
+        };
+        let (groups, no_company) = PhoneBook::group_by_company(book.list(None).unwrap());
+        assert_eq!(
+            groups.keys().collect::<Vec<_>>(),
+            ["acme", "acme corp", "globex"]
+        );
+        assert_eq!(names(&groups["acme"]), ["Ann", "Cid"]);
+        assert_eq!(names(&groups["globex"]), ["Bob"]);
+        assert_eq!(names(&no_company), ["Dee"]);
+        assert_eq!(
+            names(&PhoneBook::list_by_company(" acme ").unwrap()),
+            ["Ann", "Cid"]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use chrono::{NaiveDateTime, Utc};
+use comfy_table::Cell;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+
+use crate::connection::establish_connection;
+use crate::phone_book::contact::{sanitize_text, styled_table};
+use crate::phone_book::phone_book::PhoneBook;
+use crate::schema::saved_searches;
+
+/// Define a SavedSearch struct for advanced search criteria kept under a name, so frequent
+/// searches can be run again without retyping them.
+#[derive(Insertable, Queryable, Clone)]
+#[diesel(table_name = saved_searches)]
+pub struct SavedSearch {
+    pub(crate) name: String,
+    /// The criteria as typed, parsed again by `PhoneBook::parse_criteria` on every run.
+    pub(crate) criteria: String,
+    pub(crate) created_at: NaiveDateTime,
+}
+/// SavedSearch struct implementation.
+impl SavedSearch {
+    /// Saves criteria under a name, replacing any search saved under the same name.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    /// * `name`: The name of the search, matched ignoring case.
+    /// * `criteria`: The advanced search criteria.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the search was saved.
+    pub fn save(connection: &mut SqliteConnection, name: &str, criteria: &str) -> QueryResult<()> {
+        diesel::replace_into(saved_searches::table)
+            .values(Self {
+                name: name.to_string(),
+                criteria: criteria.to_string(),
+                created_at: Utc::now().naive_utc(),
+            })
+            .execute(connection)?;
+        Ok(())
+    }
+    /// Loads the search saved under a name.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    /// * `name`: The name of the search, matched ignoring case.
+    ///
+    /// # Returns
+    ///
+    /// The saved search, or `None` if no search has that name.
+    pub fn find(connection: &mut SqliteConnection, name: &str) -> QueryResult<Option<Self>> {
+        saved_searches::table
+            .filter(saved_searches::name.eq(name))
+            .first::<Self>(connection)
+            .optional()
+    }
+    /// Loads every saved search, sorted by name.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    ///
+    /// # Returns
+    ///
+    /// The saved searches.
+    pub fn all(connection: &mut SqliteConnection) -> QueryResult<Vec<Self>> {
+        saved_searches::table
+            .order(saved_searches::name.asc())
+            .load::<Self>(connection)
+    }
+}
+
+impl PhoneBook {
+    /// Saves advanced search criteria under a name, so the search can be run again with RS.
+    ///
+    /// The criteria are checked before they are saved, and the number of contacts they currently
+    /// match is shown.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn save_search(&mut self) {
+        Self::print_cancel_hint();
+        let Ok(criteria) =
+            Self::get_input_or_cancel("Enter the search criteria to save (field:value ...): ")
+        else {
+            println!("Saving the search cancelled.");
+            return;
+        };
+        let parsed = match Self::parse_criteria(&criteria) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let Ok(name) = Self::get_input_or_cancel("Enter a name for this search: ") else {
+            println!("Saving the search cancelled.");
+            return;
+        };
+        let name = sanitize_text(&name);
+        if name.is_empty() {
+            println!("The name can't be empty!");
+            return;
+        }
+        let mut connection = establish_connection();
+        match SavedSearch::find(&mut connection, &name) {
+            Ok(Some(existing)) => {
+                let confirm = Self::get_input(&format!(
+                    "A search named '{}' already exists ({}). Replace it? (y/n): ",
+                    existing.name, existing.criteria
+                ));
+                if confirm.to_lowercase() != "y" {
+                    println!("Saving the search cancelled.");
+                    return;
+                }
+            }
+            Ok(None) => {}
+            Err(err) => {
+                println!("Error fetching saved searches: {}", err);
+                return;
+            }
+        }
+        if let Err(err) = SavedSearch::save(&mut connection, &name, &criteria) {
+            println!("Error saving search: {}", err);
+            return;
+        }
+        match Self::search_contacts_by_criteria(&parsed) {
+            Ok(found_contacts) => println!(
+                "Search '{}' saved. It currently matches {} contact(s).",
+                name,
+                found_contacts.len()
+            ),
+            Err(_) => println!("Search '{}' saved.", name),
+        }
+    }
+    /// Runs a saved search, chosen by name.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the search results to the console.
+    pub(crate) fn run_saved_search(&mut self) {
+        let mut connection = establish_connection();
+        match SavedSearch::all(&mut connection) {
+            Ok(searches) if searches.is_empty() => {
+                println!("No saved searches. Save one with SS.");
+                return;
+            }
+            Ok(searches) => {
+                let names: Vec<String> = searches.into_iter().map(|search| search.name).collect();
+                println!("Saved searches: {}", names.join(", "));
+            }
+            Err(err) => {
+                println!("Error fetching saved searches: {}", err);
+                return;
+            }
+        }
+        let name = Self::get_input("Enter the name of the search to run: ");
+        let search = match SavedSearch::find(&mut connection, &name) {
+            Ok(Some(search)) => search,
+            Ok(None) => {
+                println!("No saved search named '{}'.", name);
+                return;
+            }
+            Err(err) => {
+                println!("Error fetching saved searches: {}", err);
+                return;
+            }
+        };
+        println!("Running '{}': {}", search.name, search.criteria);
+        match Self::parse_criteria(&search.criteria) {
+            Ok(criteria) => self.print_criteria_matches(&criteria),
+            Err(err) => println!("{}", err),
+        }
+    }
+    /// Lists the saved searches with their criteria.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the table to the console.
+    pub(crate) fn list_saved_searches(&mut self) {
+        let mut connection = establish_connection();
+        let searches = match SavedSearch::all(&mut connection) {
+            Ok(searches) => searches,
+            Err(err) => {
+                println!("Error fetching saved searches: {}", err);
+                return;
+            }
+        };
+        if searches.is_empty() {
+            println!("No saved searches. Save one with SS.");
+            return;
+        }
+        let mut table = styled_table();
+        table.set_header(vec![
+            Cell::new("Name").add_attribute(comfy_table::Attribute::Bold),
+            Cell::new("Criteria").add_attribute(comfy_table::Attribute::Bold),
+        ]);
+        for search in searches {
+            table.add_row(vec![Cell::new(search.name), Cell::new(search.criteria)]);
+        }
+        println!("{}", table);
+    }
+}
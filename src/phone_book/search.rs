@@ -0,0 +1,572 @@
+use std::io;
+use std::io::Write;
+
+use crossterm::cursor::MoveTo;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use diesel::dsl::sql;
+use diesel::prelude::*;
+use diesel::sql_query;
+use diesel::sql_types::{Integer, Text};
+use diesel::sqlite::SqliteConnection;
+
+use crate::connection::establish_connection;
+use crate::phone_book::contact::{Contact, FIELDS};
+use crate::phone_book::phone_book::PhoneBook;
+use crate::schema::{contacts, tags};
+
+/// Maximum number of matches shown while the search query is being typed.
+const LIVE_SEARCH_ROWS: usize = 8;
+
+/// Prompt shown above the live search results.
+const LIVE_SEARCH_PROMPT: &str = "Search (Enter to confirm, Esc to cancel): ";
+
+/// Pseudo-field of the advanced search matching contacts carrying a tag.
+const TAG_CRITERION: &str = "tag";
+
+/// A `field:value` condition of the advanced search.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Criterion {
+    /// A contact field from `FIELDS`, or `tag`.
+    pub field: String,
+    /// The text the field must contain, or the tag the contact must carry.
+    pub value: String,
+}
+
+/// A row of the `contacts_fts` full-text index matching a search.
+#[derive(QueryableByName)]
+struct FtsMatch {
+    #[diesel(sql_type = Integer)]
+    rowid: i32,
+}
+
+impl PhoneBook {
+    /// Builds an FTS5 query from user input: every word must match the start of a word in one
+    /// of the indexed fields.
+    ///
+    /// Words are quoted, so FTS5 operators and punctuation in the input are matched literally.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The text entered by the user.
+    ///
+    /// # Returns
+    ///
+    /// The FTS5 query, or `None` if the input has a word without any letter or digit, which the
+    /// full-text index can't match.
+    pub(crate) fn fts_query(query: &str) -> Option<String> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        if words.is_empty()
+            || !words
+                .iter()
+                .all(|word| word.chars().any(char::is_alphanumeric))
+        {
+            return None;
+        }
+        let terms: Vec<String> = words
+            .iter()
+            .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+            .collect();
+        Some(terms.join(" "))
+    }
+    /// Finds the contacts matching a query through the `contacts_fts` full-text index.
+    ///
+    /// # Arguments
+    ///
+    /// * `connection` - The database connection to use.
+    /// * `fts_query` - The FTS5 query, as built by `fts_query`.
+    ///
+    /// # Returns
+    ///
+    /// The matching contacts, best matches first.
+    pub(crate) fn search_contacts_fts(
+        connection: &mut SqliteConnection,
+        fts_query: &str,
+    ) -> QueryResult<Vec<Contact>> {
+        let ids: Vec<i32> = sql_query(
+            "SELECT rowid FROM contacts_fts WHERE contacts_fts MATCH ? ORDER BY rank, rowid",
+        )
+        .bind::<Text, _>(fts_query)
+        .load::<FtsMatch>(connection)?
+        .into_iter()
+        .map(|found| found.rowid)
+        .collect();
+        let mut found_contacts = contacts::table
+            .filter(contacts::id.eq_any(&ids))
+            .load::<Contact>(connection)?;
+        found_contacts.sort_by_key(|contact| {
+            ids.iter()
+                .position(|&id| Some(id) == contact.id)
+                .unwrap_or(usize::MAX)
+        });
+        Ok(found_contacts)
+    }
+    /// Reads a search query key by key, showing the matches after every keystroke.
+    ///
+    /// The live results are drawn on the terminal's alternate screen, so the regular output is
+    /// left untouched once the query is confirmed.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - A reference to the `PhoneBook` instance.
+    ///
+    /// # Returns
+    ///
+    /// The confirmed query, `None` if the user cancelled with Esc or Ctrl-C, or the error raised
+    /// by the terminal.
+    pub(crate) fn read_search_as_you_type(&self) -> io::Result<Option<String>> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        let result = execute!(stdout, EnterAlternateScreen).and_then(|()| self.live_search_loop());
+        // Restore the terminal even if reading keys failed
+        let restored = execute!(stdout, LeaveAlternateScreen);
+        terminal::disable_raw_mode()?;
+        restored?;
+        result
+    }
+    /// Updates the query from key presses until it is confirmed or cancelled.
+    fn live_search_loop(&self) -> io::Result<Option<String>> {
+        let mut connection = establish_connection();
+        let mut query = String::new();
+        loop {
+            self.render_live_search(&mut connection, &query)?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Enter => return Ok(Some(query)),
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return Ok(None)
+                }
+                KeyCode::Backspace => {
+                    query.pop();
+                }
+                KeyCode::Char(c) => query.push(c),
+                _ => {}
+            }
+        }
+    }
+    /// Redraws the prompt and the first matches of the query.
+    fn render_live_search(&self, connection: &mut SqliteConnection, query: &str) -> io::Result<()> {
+        let mut stdout = io::stdout();
+        queue!(stdout, Clear(ClearType::All), MoveTo(0, 0))?;
+        let mut output = format!("{}{}\n", LIVE_SEARCH_PROMPT, query);
+        if !query.trim().is_empty() {
+            match Self::search_contacts_in(connection, query) {
+                Ok(found_contacts) if found_contacts.is_empty() => {
+                    output.push_str("No contacts found matching the search query.\n");
+                }
+                Ok(found_contacts) => {
+                    let shown = &found_contacts[..found_contacts.len().min(LIVE_SEARCH_ROWS)];
+                    output.push_str(&format!("{}\n", self.contacts_listing(shown, 0)));
+                    if found_contacts.len() > shown.len() {
+                        output.push_str(&format!(
+                            "... and {} more.\n",
+                            found_contacts.len() - shown.len()
+                        ));
+                    }
+                }
+                Err(err) => output.push_str(&format!("Error searching for contacts: {}\n", err)),
+            }
+        }
+        // Raw mode doesn't return the cursor to the start of the line on a line feed
+        write!(stdout, "{}", output.replace('\n', "\r\n"))?;
+        let column = (LIVE_SEARCH_PROMPT.chars().count() + query.chars().count()) as u16;
+        queue!(stdout, MoveTo(column, 0))?;
+        stdout.flush()
+    }
+    /// Searches for contacts whose first or last name starts with the entered text.
+    ///
+    /// This is more precise than the regular search when the user knows how a name begins:
+    /// "Jo" finds "John" and "Jones", but not "Bojo".
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the search results to the console.
+    pub(crate) fn search_name_prefix(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        let prefix = Self::get_input("Enter the beginning of a first or last name: ");
+        let found_contacts = match Self::search_contacts_by_name_prefix(&prefix) {
+            Ok(found_contacts) => found_contacts,
+            Err(err) => {
+                println!("Error searching for contacts: {}", err);
+                return;
+            }
+        };
+        if found_contacts.is_empty() {
+            println!("No contacts found with a name starting with '{}'.", prefix);
+        } else {
+            self.print_contacts(&found_contacts);
+            Self::offer_csv_copy(&found_contacts);
+        }
+    }
+    /// Finds the contacts whose first or last name starts with a prefix, ignoring case.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The beginning of the name.
+    ///
+    /// # Returns
+    ///
+    /// The matching contacts, sorted by first and last name.
+    pub fn search_contacts_by_name_prefix(prefix: &str) -> QueryResult<Vec<Contact>> {
+        let mut connection = establish_connection();
+        let pattern = format!("{}%", Self::escape_like(prefix));
+        contacts::table
+            .filter(
+                contacts::first_name
+                    .like(&pattern)
+                    .escape('\\')
+                    .or(contacts::last_name.like(&pattern).escape('\\')),
+            )
+            .order((contacts::first_name.asc(), contacts::last_name.asc()))
+            .load::<Contact>(&mut connection)
+    }
+    /// Finds who a phone number belongs to, however the number is formatted.
+    ///
+    /// Unlike the regular search, only whole numbers match, so "555" doesn't list every contact
+    /// with a 555 in their number.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the matching contacts to the console.
+    pub(crate) fn look_up_phone(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        let phone = Self::get_input("Enter the phone number to look up: ");
+        match Self::find_contacts_by_phone(&phone) {
+            Ok(found_contacts) if found_contacts.is_empty() => {
+                println!("No contact has the phone number {}.", phone);
+            }
+            Ok(found_contacts) => self.print_contacts(&found_contacts),
+            Err(err) => println!("Error looking up the phone number: {}", err),
+        }
+    }
+    /// Finds the contacts whose phone number has the same digits as the given one.
+    ///
+    /// The lookup goes through the indexed `phone_digits` column. A 10-digit number also matches
+    /// the same number stored with the US country code `1`, and the other way round.
+    ///
+    /// # Arguments
+    ///
+    /// * `phone` - The phone number, in any format.
+    ///
+    /// # Returns
+    ///
+    /// The matching contacts, in creation order.
+    pub fn find_contacts_by_phone(phone: &str) -> QueryResult<Vec<Contact>> {
+        let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+        if digits.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut candidates = vec![digits.clone()];
+        match digits.len() {
+            10 => candidates.push(format!("1{}", digits)),
+            11 if digits.starts_with('1') => candidates.push(digits[1..].to_string()),
+            _ => {}
+        }
+        let mut connection = establish_connection();
+        // `phone_digits` is a generated column, which `PRAGMA table_info` and so the schema omit
+        contacts::table
+            .filter(sql::<Text>("phone_digits").eq_any(candidates))
+            .order(contacts::id.asc())
+            .load::<Contact>(&mut connection)
+    }
+    /// Finds the contacts with the given first name, last name and email, ignoring case and
+    /// surrounding whitespace, as deduplication by name and email compares them.
+    ///
+    /// # Arguments
+    ///
+    /// * `first_name` - The first name.
+    /// * `last_name` - The last name.
+    /// * `email` - The email address. Without one, nothing matches.
+    ///
+    /// # Returns
+    ///
+    /// The matching contacts, in creation order.
+    pub fn find_contacts_by_name_and_email(
+        first_name: &str,
+        last_name: &str,
+        email: &str,
+    ) -> QueryResult<Vec<Contact>> {
+        let email = email.trim();
+        if email.is_empty() {
+            return Ok(Vec::new());
+        }
+        let key = |first_name: &str, last_name: &str, email: &str| {
+            format!(
+                "{} {} <{}>",
+                first_name.trim(),
+                last_name.trim(),
+                email.trim()
+            )
+            .to_lowercase()
+        };
+        let wanted = key(first_name, last_name, email);
+        let mut connection = establish_connection();
+        // LIKE narrows down the candidates ignoring ASCII case, and the names are compared here
+        let candidates = contacts::table
+            .filter(contacts::email.like(Self::escape_like(email)).escape('\\'))
+            .order(contacts::id.asc())
+            .load::<Contact>(&mut connection)?;
+        Ok(candidates
+            .into_iter()
+            .filter(|contact| {
+                key(&contact.first_name, &contact.last_name, &contact.email) == wanted
+            })
+            .collect())
+    }
+    /// Searches for the contacts matching several `field:value` criteria at once, e.g.
+    /// `last_name:Doe address:Seattle tag:work`.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the search results to the console.
+    pub(crate) fn advanced_search(&mut self) {
+        if Self::report_if_empty() {
+            return;
+        }
+        let fields: Vec<&str> = FIELDS.iter().map(|&(field, _)| field).collect();
+        println!(
+            "Criteria are field:value pairs, all of which must match. Fields: {}, {}.",
+            fields.join(", "),
+            TAG_CRITERION
+        );
+        let input = Self::get_input("Enter the search criteria: ");
+        let criteria = match Self::parse_criteria(&input) {
+            Ok(criteria) => criteria,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        self.print_criteria_matches(&criteria);
+    }
+    /// Runs an advanced search and prints its results.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - A reference to the `PhoneBook` instance.
+    /// * `criteria` - The criteria the contacts must all match.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the search results to the console.
+    pub(crate) fn print_criteria_matches(&self, criteria: &[Criterion]) {
+        match Self::search_contacts_by_criteria(criteria) {
+            Ok(found_contacts) if found_contacts.is_empty() => {
+                println!("No contacts found matching all the criteria.");
+            }
+            Ok(found_contacts) => {
+                self.print_contacts(&found_contacts);
+                Self::offer_csv_copy(&found_contacts);
+            }
+            Err(err) => println!("Error searching for contacts: {}", err),
+        }
+    }
+    /// Parses advanced search criteria: whitespace-separated `field:value` (or `field=value`)
+    /// pairs. Values with spaces are written in double quotes, e.g. `address:"Main St"`, and the
+    /// word `AND` between pairs is allowed.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The criteria as typed by the user.
+    ///
+    /// # Returns
+    ///
+    /// The criteria, or an error message naming the first malformed pair or unknown field.
+    pub fn parse_criteria(input: &str) -> Result<Vec<Criterion>, String> {
+        let mut criteria = Vec::new();
+        for token in Self::split_criteria(input) {
+            if token.eq_ignore_ascii_case("and") {
+                continue;
+            }
+            let Some((field, value)) = token.split_once([':', '=']) else {
+                return Err(format!(
+                    "Invalid criterion '{}': expected field:value.",
+                    token
+                ));
+            };
+            let field = field.trim().to_lowercase();
+            if field != TAG_CRITERION && !FIELDS.iter().any(|&(name, _)| name == field) {
+                return Err(format!("Unknown field '{}' in the search criteria.", field));
+            }
+            if value.trim().is_empty() {
+                return Err(format!(
+                    "Missing value for '{}' in the search criteria.",
+                    field
+                ));
+            }
+            criteria.push(Criterion {
+                field,
+                value: value.trim().to_string(),
+            });
+        }
+        if criteria.is_empty() {
+            return Err("No search criteria entered.".to_string());
+        }
+        Ok(criteria)
+    }
+    /// Splits criteria on whitespace, keeping double-quoted text together without its quotes.
+    fn split_criteria(input: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut token = String::new();
+        let mut quoted = false;
+        for c in input.chars() {
+            match c {
+                '"' => quoted = !quoted,
+                c if c.is_whitespace() && !quoted => {
+                    if !token.is_empty() {
+                        tokens.push(std::mem::take(&mut token));
+                    }
+                }
+                c => token.push(c),
+            }
+        }
+        if !token.is_empty() {
+            tokens.push(token);
+        }
+        tokens
+    }
+    /// Finds the contacts matching all the criteria, ignoring case. A field criterion matches
+    /// contacts whose field contains the value; a `tag` criterion matches contacts carrying that
+    /// exact tag.
+    ///
+    /// # Arguments
+    ///
+    /// * `criteria` - The criteria, as parsed by `parse_criteria`.
+    ///
+    /// # Returns
+    ///
+    /// The matching contacts, in creation order.
+    pub fn search_contacts_by_criteria(criteria: &[Criterion]) -> QueryResult<Vec<Contact>> {
+        let mut connection = establish_connection();
+        let mut query = contacts::table.into_boxed();
+        for criterion in criteria {
+            let pattern = format!("%{}%", Self::escape_like(&criterion.value));
+            query = match criterion.field.as_str() {
+                "first_name" => query.filter(contacts::first_name.like(pattern).escape('\\')),
+                "last_name" => query.filter(contacts::last_name.like(pattern).escape('\\')),
+                "phone" => query.filter(contacts::phone.like(pattern).escape('\\')),
+                "email" => query.filter(contacts::email.like(pattern).escape('\\')),
+                "address" => query.filter(contacts::address.like(pattern).escape('\\')),
+                "website" => query.filter(contacts::website.like(pattern).escape('\\')),
+                TAG_CRITERION => query.filter(
+                    contacts::id.eq_any(
+                        tags::table
+                            .filter(
+                                tags::name
+                                    .like(Self::escape_like(&criterion.value))
+                                    .escape('\\'),
+                            )
+                            .select(tags::contact_id.nullable()),
+                    ),
+                ),
+                // parse_criteria only lets known fields through
+                _ => query,
+            };
+        }
+        query
+            .order(contacts::id.asc())
+            .load::<Contact>(&mut connection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contact, open_book};
+
+    #[test]
+    fn formatted_phone_is_found_by_its_digits() {
+        let mut book = open_book();
+        let contact_id = book
+            .add(contact("John", "Doe", "555-123-4567", ""))
+            .unwrap();
+        book.add(contact("Jane", "Roe", "555-987-6543", ""))
+            .unwrap();
+        for phone in ["5551234567", "+1 555 123 4567", "(555) 123-4567"] {
+            let found = PhoneBook::find_contacts_by_phone(phone).unwrap();
+            assert_eq!(found.len(), 1, "{}", phone);
+            assert_eq!(found[0].id, Some(contact_id));
+        }
+        assert!(PhoneBook::find_contacts_by_phone("5551234")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn combined_criteria_narrow_the_results() {
+        let mut book = open_book();
+        for (first_name, last_name, address) in [
+            ("John", "Doe", "1 Pike St, Seattle"),
+            ("Jane", "Doe", "2 Main St, Portland"),
+            ("Mary", "Major", "3 Pine St, Seattle"),
+        ] {
+            let mut new_contact = contact(first_name, last_name, "5551234567", "");
+            new_contact.set_field("address", address.to_string());
+            book.add(new_contact).unwrap();
+        }
+        let first_names = |input: &str| -> Vec<String> {
+            let criteria = PhoneBook::parse_criteria(input).unwrap();
+            PhoneBook::search_contacts_by_criteria(&criteria)
+                .unwrap()
+                .into_iter()
+                .map(|found| found.first_name)
+                .collect()
+        };
+        assert_eq!(first_names("last_name:Doe"), ["John", "Jane"]);
+        assert_eq!(first_names("address:seattle"), ["John", "Mary"]);
+        assert_eq!(first_names("last_name:Doe address:Seattle"), ["John"]);
+        assert!(first_names("last_name:Major address:Portland").is_empty());
+        assert!(PhoneBook::parse_criteria("nickname:Jo").is_err());
+    }
+
+    #[derive(QueryableByName)]
+    struct QueryPlanStep {
+        #[diesel(sql_type = Text)]
+        detail: String,
+    }
+
+    #[test]
+    fn phone_lookups_use_the_indexes() {
+        let _book = open_book();
+        let mut connection = establish_connection();
+        let plan = |connection: &mut SqliteConnection, condition: &str| -> String {
+            sql_query(format!(
+                "EXPLAIN QUERY PLAN SELECT id FROM contacts WHERE {}",
+                condition
+            ))
+            .load::<QueryPlanStep>(connection)
+            .unwrap()
+            .into_iter()
+            .map(|step| step.detail)
+            .collect()
+        };
+        assert!(
+            plan(&mut connection, "phone = '(555) 123-4567'").contains("INDEX contacts_phone (")
+        );
+        assert!(plan(&mut connection, "phone_digits = '5551234567'")
+            .contains("INDEX contacts_phone_digits ("));
+    }
+}
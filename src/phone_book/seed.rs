@@ -0,0 +1,50 @@
+use chrono::Utc;
+use diesel::prelude::*;
+
+use crate::connection::establish_connection;
+use crate::phone_book::phone_book::PhoneBook;
+use crate::schema::contacts;
+use crate::settings;
+
+/// Key stored in the `settings` table once the phone book has been seeded, so deleting every
+/// contact later doesn't bring the seed contacts back.
+const SEEDED_KEY: &str = "seeded_at";
+
+impl PhoneBook {
+    /// Adds the contacts of the `SEED_FILE` setting to a new phone book, so a fresh install can
+    /// start populated.
+    ///
+    /// Nothing is done if the setting is unset, if the phone book already has contacts, or if it
+    /// was seeded before. The file is read like a file to merge: a phone's JSON export if its
+    /// name ends in `.json`, otherwise a CSV file, with the `IMPORT_RULES` applied.
+    ///
+    /// # Returns
+    ///
+    /// The path of the file and the number of contacts added, `None` if the phone book wasn't
+    /// seeded, or an error message if the file couldn't be read or the contacts saved.
+    pub fn seed_if_empty() -> Result<Option<(String, usize)>, String> {
+        let Some(file_name) = settings::seed_file() else {
+            return Ok(None);
+        };
+        let mut connection = establish_connection();
+        let seeded =
+            settings::get_stored(&mut connection, SEEDED_KEY).map_err(|err| err.to_string())?;
+        let count: i64 = contacts::table
+            .count()
+            .get_result(&mut connection)
+            .map_err(|err| err.to_string())?;
+        if seeded.is_some() || count > 0 {
+            return Ok(None);
+        }
+        let file_name = Self::expand_path(&file_name)?;
+        let contacts = Self::read_contacts_file(&file_name)?;
+        Self::insert_contacts(&contacts, "seed").map_err(|err| err.to_string())?;
+        settings::set_stored(
+            &mut connection,
+            SEEDED_KEY,
+            &Utc::now().naive_utc().to_string(),
+        )
+        .map_err(|err| err.to_string())?;
+        Ok(Some((file_name, contacts.len())))
+    }
+}
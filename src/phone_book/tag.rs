@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+
+use crate::connection::establish_connection;
+use crate::phone_book::contact::{sanitize_text, Contact};
+use crate::phone_book::phone_book::PhoneBook;
+use crate::schema::{contacts, tags};
+
+/// Define how the contacts listed by several tags must carry them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagMatch {
+    /// Contacts carrying at least one of the tags, e.g. work OR family.
+    Any,
+    /// Contacts carrying every tag, e.g. work AND important.
+    All,
+}
+
+/// Define a Tag struct for the labels used to group contacts, e.g. "work" or "family".
+#[derive(Insertable, Queryable, Clone)]
+#[diesel(table_name = tags)]
+pub struct Tag {
+    pub(crate) id: Option<i32>, // Diesel uses Option for nullable integer fields.
+    pub(crate) contact_id: i32,
+    pub(crate) name: String,
+}
+/// Tag struct implementation.
+impl Tag {
+    /// Creates a new `Tag` for the given contact.
+    ///
+    /// # Parameters
+    ///
+    /// * `contact_id`: The id of the tagged contact.
+    /// * `name`: The tag.
+    ///
+    /// # Returns
+    ///
+    /// A new, not yet saved, `Tag` instance.
+    pub fn new(contact_id: i32, name: String) -> Self {
+        Self {
+            id: None,
+            contact_id,
+            name,
+        }
+    }
+    /// Loads the tags of a contact, in the order they were added.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    /// * `contact_id`: The id of the contact whose tags to load.
+    ///
+    /// # Returns
+    ///
+    /// The contact's tags.
+    pub fn load_for(
+        connection: &mut SqliteConnection,
+        contact_id: i32,
+    ) -> QueryResult<Vec<String>> {
+        tags::table
+            .filter(tags::contact_id.eq(contact_id))
+            .order(tags::id.asc())
+            .select(tags::name)
+            .load::<String>(connection)
+    }
+    /// Loads every distinct tag in use, sorted alphabetically.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    ///
+    /// # Returns
+    ///
+    /// The tags in use.
+    pub fn all_names(connection: &mut SqliteConnection) -> QueryResult<Vec<String>> {
+        tags::table
+            .select(tags::name)
+            .distinct()
+            .order(tags::name.asc())
+            .load::<String>(connection)
+    }
+    /// Replaces the tags of a contact with the given ones, in a transaction.
+    ///
+    /// # Parameters
+    ///
+    /// * `connection`: The database connection to use.
+    /// * `contact_id`: The id of the contact whose tags to replace.
+    /// * `names`: The new tags.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the tags were saved.
+    pub fn replace_for(
+        connection: &mut SqliteConnection,
+        contact_id: i32,
+        names: &[String],
+    ) -> QueryResult<()> {
+        connection.transaction(|connection| {
+            diesel::delete(tags::table.filter(tags::contact_id.eq(contact_id)))
+                .execute(connection)?;
+            let rows: Vec<Tag> = names
+                .iter()
+                .map(|name| Self::new(contact_id, name.clone()))
+                .collect();
+            diesel::insert_into(tags::table)
+                .values(&rows)
+                .execute(connection)?;
+            Ok(())
+        })
+    }
+    /// Completes a typed tag against the existing ones, so users reuse "work" instead of
+    /// creating "Work" or "wor".
+    ///
+    /// Control characters are replaced by spaces. A tag matching an existing one
+    /// case-insensitively takes the existing casing. Otherwise, if it is the prefix of exactly one
+    /// existing tag, that tag is used. Anything else is a new tag.
+    ///
+    /// # Parameters
+    ///
+    /// * `input`: The tag as typed by the user.
+    /// * `existing`: The tags already in use.
+    ///
+    /// # Returns
+    ///
+    /// The canonical tag to store.
+    pub fn resolve(input: &str, existing: &[String]) -> String {
+        let input = sanitize_text(input);
+        let input = input.as_str();
+        let lowercase = input.to_lowercase();
+        if let Some(tag) = existing.iter().find(|tag| tag.to_lowercase() == lowercase) {
+            return tag.clone();
+        }
+        let mut candidates = existing
+            .iter()
+            .filter(|tag| tag.to_lowercase().starts_with(&lowercase));
+        match (candidates.next(), candidates.next()) {
+            (Some(tag), None) => tag.clone(),
+            _ => input.to_string(),
+        }
+    }
+}
+
+impl PhoneBook {
+    /// Lists the contacts carrying any or all of several tags, as the user chooses, e.g. the
+    /// contacts tagged both "work" and "important".
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. The contacts found become the last listing.
+    pub(crate) fn list_contacts_by_tags(&mut self) {
+        let mut connection = establish_connection();
+        let existing = match Tag::all_names(&mut connection) {
+            Ok(existing) => existing,
+            Err(err) => {
+                println!("Error fetching tags: {}", err);
+                return;
+            }
+        };
+        if existing.is_empty() {
+            println!("No contacts are tagged yet.");
+            return;
+        }
+        println!("Existing tags: {}", existing.join(", "));
+        Self::print_cancel_hint();
+        let Ok(input) = Self::get_input_or_cancel("Enter the tags to list, comma-separated: ")
+        else {
+            println!("Listing cancelled.");
+            return;
+        };
+        let mut names: Vec<String> = Vec::new();
+        for typed in input
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+        {
+            let name = Tag::resolve(typed, &existing);
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        if names.is_empty() {
+            println!("No tags entered.");
+            return;
+        }
+        let mode = if names.len() == 1 {
+            TagMatch::Any
+        } else {
+            let Ok(choice) = Self::get_input_or_cancel(
+                "List contacts with (1) any of the tags or (2) all of them? (default 1): ",
+            ) else {
+                println!("Listing cancelled.");
+                return;
+            };
+            match choice.as_str() {
+                "" | "1" => TagMatch::Any,
+                "2" => TagMatch::All,
+                _ => {
+                    println!("Invalid choice!");
+                    return;
+                }
+            }
+        };
+        let contacts = match Self::contacts_with_tags(&names, mode) {
+            Ok(contacts) => contacts,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        let joined = names.join(match mode {
+            TagMatch::Any => " or ",
+            TagMatch::All => " and ",
+        });
+        if contacts.is_empty() {
+            println!("No contacts are tagged {}.", joined);
+            return;
+        }
+        println!("{} contact(s) tagged {}:", contacts.len(), joined);
+        self.contacts = contacts;
+        self.print_contact_pages("tags");
+    }
+    /// Finds the contacts carrying any or all of several tags.
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - The tags, compared exactly.
+    /// * `mode` - Whether a contact needs any of the tags or all of them.
+    ///
+    /// # Returns
+    ///
+    /// The matching contacts, sorted by first and last name.
+    pub fn contacts_with_tags(names: &[String], mode: TagMatch) -> QueryResult<Vec<Contact>> {
+        let mut connection = establish_connection();
+        let rows = tags::table
+            .filter(tags::name.eq_any(names))
+            .select((tags::contact_id, tags::name))
+            .load::<(i32, String)>(&mut connection)?;
+        let mut carried: HashMap<i32, Vec<String>> = HashMap::new();
+        for (contact_id, name) in rows {
+            let tags = carried.entry(contact_id).or_default();
+            if !tags.contains(&name) {
+                tags.push(name);
+            }
+        }
+        let contact_ids: Vec<i32> = carried
+            .into_iter()
+            .filter(|(_, tags)| mode == TagMatch::Any || tags.len() == names.len())
+            .map(|(contact_id, _)| contact_id)
+            .collect();
+        contacts::table
+            .filter(contacts::id.eq_any(contact_ids))
+            .order((contacts::first_name.asc(), contacts::last_name.asc()))
+            .load::<Contact>(&mut connection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{contact, open_book};
+
+    #[test]
+    fn typed_tags_resolve_to_the_existing_casing() {
+        let existing = vec![
+            "Work".to_string(),
+            "family".to_string(),
+            "friends".to_string(),
+        ];
+        assert_eq!(Tag::resolve("wor", &existing), "Work");
+        assert_eq!(Tag::resolve("WORK", &existing), "Work");
+        assert_eq!(Tag::resolve("fam", &existing), "family");
+        // A prefix of several tags, or of none, is kept as typed
+        assert_eq!(Tag::resolve("f", &existing), "f");
+        assert_eq!(Tag::resolve("gym", &existing), "gym");
+    }
+
+    #[test]
+    fn all_mode_needs_every_tag_while_any_mode_needs_one() {
+        let _book = open_book();
+        for (first_name, tags) in [
+            ("Ann", &["work", "important"][..]),
+            ("Bob", &["work"][..]),
+            ("Cid", &["important", "family"][..]),
+            ("Dan", &["family"][..]),
+        ] {
+            let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+            PhoneBook::insert_contact_with(
+                &contact(first_name, "Doe", "5551234567", ""),
+                &[],
+                &tags,
+                "create",
+            )
+            .unwrap();
+        }
+        let names = ["work".to_string(), "important".to_string()];
+        let first_names = |mode| -> Vec<String> {
+            PhoneBook::contacts_with_tags(&names, mode)
+                .unwrap()
+                .into_iter()
+                .map(|contact| contact.first_name)
+                .collect()
+        };
+        assert_eq!(first_names(TagMatch::All), ["Ann"]);
+        assert_eq!(first_names(TagMatch::Any), ["Ann", "Bob", "Cid"]);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::phone_book::contact::Contact;
+use crate::phone_book::export::write_export;
+use crate::phone_book::phone_book::PhoneBook;
+
+/// Escapes a value for a vCard property, as required by RFC 6350.
+///
+/// # Parameters
+///
+/// * `value`: The raw value.
+///
+/// # Returns
+///
+/// The value with backslashes, commas, semicolons and line breaks escaped.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ',' => escaped.push_str("\\,"),
+            ';' => escaped.push_str("\\;"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// vCard serialization of a contact.
+impl Contact {
+    /// Writes the phone number and extension as an RFC 3966 tel URI, e.g.
+    /// `tel:+15551234567;ext=123`.
+    ///
+    /// Numbers entered with a `+` keep their country code, and US numbers get the country code
+    /// `1`, as 10-digit numbers are standardized to the US format.
+    ///
+    /// # Returns
+    ///
+    /// The URI, with the extension if the contact has one.
+    fn tel_uri(&self) -> String {
+        let digits: String = self.phone.chars().filter(char::is_ascii_digit).collect();
+        let number = match self.area_code() {
+            _ if self.phone.trim_start().starts_with('+') => format!("+{}", digits),
+            Some(_) if digits.len() == 10 => format!("+1{}", digits),
+            Some(_) => format!("+{}", digits),
+            // Without a known country code, the number can only be written as dialed locally
+            None => digits,
+        };
+        if self.extension.is_empty() {
+            format!("tel:{}", number)
+        } else {
+            format!("tel:{};ext={}", number, self.extension)
+        }
+    }
+    /// Serializes the contact as a vCard 3.0 card, which phones and mail clients can import.
+    ///
+    /// Empty fields are left out, except for the name properties the format requires. The
+    /// sender, the owner profile set with SO, is written as the card's `SOURCE`, a `mailto:`
+    /// URI of their email, and as `X-SENDER`, their name.
+    ///
+    /// # Parameters
+    ///
+    /// * `sender`: The owner of the phone book sharing the card, `None` if no owner profile is set
+    ///   or the card shouldn't name one.
+    ///
+    /// # Returns
+    ///
+    /// The card, with CRLF line endings.
+    pub(crate) fn to_vcard(&self, sender: Option<&Contact>) -> String {
+        let full_name = format!("{} {}", self.first_name, self.last_name);
+        let mut lines = vec![
+            "BEGIN:VCARD".to_string(),
+            "VERSION:3.0".to_string(),
+            format!(
+                "N:{};{};;;",
+                escape(&self.last_name),
+                escape(&self.first_name)
+            ),
+            format!("FN:{}", escape(full_name.trim())),
+        ];
+        if !self.company.is_empty() {
+            lines.push(format!("ORG:{}", escape(&self.company)));
+        }
+        if !self.phone.is_empty() {
+            if self.extension.is_empty() {
+                lines.push(format!("TEL;TYPE=VOICE:{}", escape(&self.phone)));
+            } else {
+                // Only a tel URI can carry the extension where phones dial it after connecting
+                lines.push(format!("TEL;TYPE=VOICE;VALUE=uri:{}", self.tel_uri()));
+            }
+        }
+        if !self.email.is_empty() {
+            lines.push(format!("EMAIL;TYPE=INTERNET:{}", escape(&self.email)));
+        }
+        if !self.address.is_empty() {
+            lines.push(format!("ADR:;;{};;;;", escape(&self.address)));
+        }
+        if !self.website.is_empty() {
+            lines.push(format!("URL:{}", self.website));
+        }
+        if let Some(sender) = sender {
+            if !sender.email.is_empty() {
+                lines.push(format!("SOURCE:mailto:{}", sender.email));
+            }
+            let sender_name = format!("{} {}", sender.first_name, sender.last_name);
+            lines.push(format!("X-SENDER:{}", escape(sender_name.trim())));
+        }
+        lines.push("END:VCARD".to_string());
+        lines.join("\r\n") + "\r\n"
+    }
+}
+
+impl PhoneBook {
+    /// Exports every contact to a vCard (`.vcf`) file, which phones and mail clients can import.
+    /// A file name ending in `.gz` is written gzip-compressed. The cards name the owner profile,
+    /// if set, as their sender.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the outcome to the console.
+    pub(crate) fn export_vcard(&mut self) {
+        Self::print_cancel_hint();
+        let Ok(file_name) = Self::get_input_or_cancel(
+            "Enter the name of the vCard file to export contacts to (.gz to compress): ",
+        ) else {
+            println!("Export cancelled.");
+            return;
+        };
+        let file_name = match Self::expand_path(&file_name) {
+            Ok(file_name) => file_name,
+            Err(err) => {
+                println!("{}", err);
+                return;
+            }
+        };
+        let contacts = match Self::get_contacts("") {
+            Ok(contacts) => contacts,
+            Err(err) => {
+                println!("Error fetching contacts from the database: {}", err);
+                return;
+            }
+        };
+        let Some(sender) = Self::vcard_sender() else {
+            return;
+        };
+        let cards: String = contacts
+            .iter()
+            .map(|contact| contact.to_vcard(sender.as_ref()))
+            .collect();
+        match write_export(&file_name, &cards) {
+            Ok(()) => println!("Exported {} contact(s) to {}.", contacts.len(), file_name),
+            Err(err) => println!("Error writing file: {}", err),
+        }
+    }
+    /// Prints the vCard of one listed contact, chosen by index, so it can be shared or piped elsewhere.
+    ///
+    /// # Arguments
+    ///
+    /// * `&mut self` - A mutable reference to the `PhoneBook` instance.
+    ///
+    /// # Return
+    ///
+    /// This function does not return any value. It prints the vCard to the console.
+    pub(crate) fn print_vcard(&mut self) {
+        let Some(contact) = self.prompt_listed_contact("Enter the index of the contact to print: ")
+        else {
+            return;
+        };
+        let Some(sender) = Self::vcard_sender() else {
+            return;
+        };
+        print!("{}", contact.to_vcard(sender.as_ref()));
+    }
+    /// Loads the owner profile to name as the sender of shared vCards, reporting any problem to
+    /// the user.
+    ///
+    /// # Returns
+    ///
+    /// The owner profile, `Some(None)` if none is set, or `None` if it couldn't be loaded.
+    pub(crate) fn vcard_sender() -> Option<Option<Contact>> {
+        match Self::get_owner() {
+            Ok(owner) => Some(owner),
+            Err(err) => {
+                println!("Error fetching owner profile: {}", err);
+                None
+            }
+        }
+    }
+    /// Prompts for the index of a contact in the last listing and fetches its current details,
+    /// reporting any problem to the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `&self` - A reference to the `PhoneBook` instance, whose `contacts` are the last listing.
+    /// * `prompt` - The prompt to display.
+    ///
+    /// # Returns
+    ///
+    /// The contact, or `None` if the user cancelled, nothing is listed or the contact no longer
+    /// exists.
+    pub(crate) fn prompt_listed_contact(&self, prompt: &str) -> Option<Contact> {
+        if Self::report_if_empty() {
+            return None;
+        }
+        Self::print_cancel_hint();
+        let Ok(index) = self.prompt_contact_index(prompt) else {
+            println!("Cancelled.");
+            return None;
+        };
+        let Some(contact_id) = self.contacts[index - 1].id else {
+            println!("Invalid contact index!");
+            return None;
+        };
+        match Self::get_contact(contact_id) {
+            Ok(Some(contact)) => Some(contact),
+            Ok(None) => {
+                println!(
+                    "Contact #{} no longer exists. List the contacts again.",
+                    index
+                );
+                None
+            }
+            Err(err) => {
+                println!("Error fetching contact: {}", err);
+                None
+            }
+        }
+    }
+}
@@ -1,5 +1,16 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    audit_log (id) {
+        id -> Nullable<Integer>,
+        created_at -> Timestamp,
+        operation -> Text,
+        contact_id -> Nullable<Integer>,
+        before -> Nullable<Text>,
+        after -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     contacts (id) {
         id -> Nullable<Integer>,
@@ -8,5 +19,66 @@ diesel::table! {
         email -> Text,
         address -> Text,
         phone -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+        website -> Text,
+        extension -> Text,
+        last_contacted -> Nullable<Date>,
+        company -> Text,
+        preferred_method -> Text,
+    }
+}
+
+diesel::table! {
+    emails (id) {
+        id -> Nullable<Integer>,
+        contact_id -> Integer,
+        label -> Text,
+        address -> Text,
+    }
+}
+
+diesel::table! {
+    relationships (id) {
+        id -> Nullable<Integer>,
+        contact_id -> Integer,
+        related_contact_id -> Integer,
+        label -> Text,
     }
 }
+
+diesel::table! {
+    saved_searches (name) {
+        name -> Text,
+        criteria -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    settings (key) {
+        key -> Text,
+        value -> Text,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Nullable<Integer>,
+        contact_id -> Integer,
+        name -> Text,
+    }
+}
+
+diesel::joinable!(emails -> contacts (contact_id));
+diesel::joinable!(tags -> contacts (contact_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    audit_log,
+    contacts,
+    emails,
+    relationships,
+    saved_searches,
+    settings,
+    tags,
+);
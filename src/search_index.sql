@@ -0,0 +1,86 @@
+-- Full-text index of the searchable contact text, one row per contact with the contact id as rowid.
+-- The phone number is also indexed as bare digits, and the additional emails as a single column.
+CREATE VIRTUAL TABLE IF NOT EXISTS contacts_fts USING fts5
+(
+    first_name,
+    last_name,
+    email,
+    address,
+    phone,
+    phone_digits,
+    website,
+    company,
+    emails
+);
+
+CREATE TRIGGER IF NOT EXISTS contacts_fts_insert
+    AFTER INSERT
+    ON contacts
+BEGIN
+    INSERT INTO contacts_fts (rowid, first_name, last_name, email, address, phone, phone_digits, website, company, emails)
+    VALUES (new.id,
+            new.first_name,
+            new.last_name,
+            new.email,
+            new.address,
+            new.phone,
+            REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(new.phone, '(', ''), ')', ''), '-', ''), ' ', ''), '.', ''), '+', ''),
+            new.website,
+            new.company,
+            '');
+END;
+
+CREATE TRIGGER IF NOT EXISTS contacts_fts_update
+    AFTER UPDATE
+    ON contacts
+BEGIN
+    DELETE FROM contacts_fts WHERE rowid = old.id;
+    INSERT INTO contacts_fts (rowid, first_name, last_name, email, address, phone, phone_digits, website, company, emails)
+    VALUES (new.id,
+            new.first_name,
+            new.last_name,
+            new.email,
+            new.address,
+            new.phone,
+            REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(new.phone, '(', ''), ')', ''), '-', ''), ' ', ''), '.', ''), '+', ''),
+            new.website,
+            new.company,
+            COALESCE((SELECT GROUP_CONCAT(address, ' ') FROM emails WHERE contact_id = new.id), ''));
+END;
+
+CREATE TRIGGER IF NOT EXISTS contacts_fts_delete
+    AFTER DELETE
+    ON contacts
+BEGIN
+    DELETE FROM contacts_fts WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS emails_fts_insert
+    AFTER INSERT
+    ON emails
+BEGIN
+    UPDATE contacts_fts
+    SET emails = COALESCE((SELECT GROUP_CONCAT(address, ' ') FROM emails WHERE contact_id = new.contact_id), '')
+    WHERE rowid = new.contact_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS emails_fts_update
+    AFTER UPDATE
+    ON emails
+BEGIN
+    UPDATE contacts_fts
+    SET emails = COALESCE((SELECT GROUP_CONCAT(address, ' ') FROM emails WHERE contact_id = old.contact_id), '')
+    WHERE rowid = old.contact_id;
+    UPDATE contacts_fts
+    SET emails = COALESCE((SELECT GROUP_CONCAT(address, ' ') FROM emails WHERE contact_id = new.contact_id), '')
+    WHERE rowid = new.contact_id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS emails_fts_delete
+    AFTER DELETE
+    ON emails
+BEGIN
+    UPDATE contacts_fts
+    SET emails = COALESCE((SELECT GROUP_CONCAT(address, ' ') FROM emails WHERE contact_id = old.contact_id), '')
+    WHERE rowid = old.contact_id;
+END;
@@ -0,0 +1,1006 @@
+use chrono::format::{Item, StrftimeItems};
+use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use comfy_table::presets::{ASCII_FULL, ASCII_MARKDOWN, NOTHING, UTF8_FULL};
+use crossterm::style::Color;
+use csv::QuoteStyle;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use dotenv::dotenv;
+use encoding_rs::{Encoding, UTF_8};
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+
+use crate::connection::{self, establish_connection};
+use crate::phone_book::contact::FIELDS;
+use crate::schema::settings;
+
+/// Define the settings that can be stored in the database, with a description of each.
+///
+/// A stored value is used unless the same variable is set in the environment or `.env`, which
+/// always takes precedence.
+pub const CONFIGURABLE: &[(&str, &str)] = &[
+    (
+        "REQUIRED_FIELDS",
+        "Comma-separated fields required at create/update",
+    ),
+    (
+        "PROMPT_ORDER",
+        "Comma-separated fields prompted for at create/update, in order (all fields if unset)",
+    ),
+    (
+        "MAX_FIELD_LENGTHS",
+        "Comma-separated field=length limits, e.g. first_name=64",
+    ),
+    (
+        "STANDARDIZE_PHONE",
+        "Format 10-digit phone numbers as (XXX) XXX-XXXX (true/false)",
+    ),
+    (
+        "TITLE_CASE_NAMES",
+        "Title-case names on entry and import, e.g. john MCDONALD to John McDonald (true/false)",
+    ),
+    (
+        "EXPORT_COLUMNS",
+        "Comma-separated fields exported to CSV, in order",
+    ),
+    (
+        "DATE_FORMAT",
+        "strftime format of displayed timestamps, e.g. %d/%m/%Y %H:%M",
+    ),
+    (
+        "DEFAULT_SORT",
+        "Order of the L listing, e.g. last_asc (creation order if unset)",
+    ),
+    (
+        "PAGE_SIZE",
+        "Number of contacts shown per page of a listing (20 if unset)",
+    ),
+    (
+        "TABLE_STYLE",
+        "Borders of tables: rounded, square, ascii, markdown or none (rounded if unset)",
+    ),
+    (
+        "EMPTY_PLACEHOLDER",
+        "Text shown in listings for empty fields, e.g. — (blank if unset)",
+    ),
+    (
+        "SHOW_DASHBOARD",
+        "Show contact counts at interactive startup (true/false)",
+    ),
+    (
+        "CSV_DELIMITER",
+        "Delimiter of exported CSV: comma, semicolon or tab",
+    ),
+    (
+        "CSV_QUOTING",
+        "Quoting of exported CSV fields: minimal, always or non_numeric",
+    ),
+    (
+        "PHONE_REGION",
+        "Two-letter region of phone numbers, e.g. US, setting the minimum digits",
+    ),
+    (
+        "MIN_PHONE_DIGITS",
+        "Minimum digits of a complete phone number, overriding PHONE_REGION",
+    ),
+    (
+        "AUTO_BACKUP",
+        "Back up the database before deduplication, bulk updates and reverts (true/false)",
+    ),
+    (
+        "IMPORT_ENCODING",
+        "Character encoding of imported CSV files, e.g. windows-1252 (UTF-8 if unset)",
+    ),
+    (
+        "PLACEHOLDER_VALUES",
+        "Comma-separated values treated as empty, e.g. N/A,none (common placeholders if unset)",
+    ),
+    (
+        "IMPORT_RULES",
+        "Path of a JSON file of transformations applied to imported fields (none if unset)",
+    ),
+    (
+        "PROMPT_PREFIX",
+        "Text shown before every prompt, e.g. >> (none if unset)",
+    ),
+    (
+        "PROMPT_COLOR",
+        "Color of prompts, e.g. cyan or dark_yellow (terminal default if unset)",
+    ),
+    (
+        "COLUMN_WIDTHS",
+        "Comma-separated field=width limits of listed values, e.g. address=30 (none if unset)",
+    ),
+    (
+        "SEED_FILE",
+        "CSV or JSON file of contacts added at startup to a new, empty phone book (none if unset)",
+    ),
+    (
+        "DATABASE_SIZE_WARNING_MB",
+        "Database size in MB above which a warning suggests archiving (1024 if unset, 0 for none)",
+    ),
+];
+
+/// Values of the `PLACEHOLDER_VALUES` setting when it is unset: what imports commonly carry in
+/// place of a missing phone number or email.
+const DEFAULT_PLACEHOLDER_VALUES: &[&str] = &[
+    "n/a",
+    "na",
+    "none",
+    "null",
+    "nil",
+    "-",
+    "unknown",
+    "000-000-0000",
+    "noemail@none.com",
+    "none@none.com",
+    "noemail@noemail.com",
+];
+
+/// Cache of the values in the `settings` table of each database, by path, loaded on first use.
+static STORED: Mutex<Option<HashMap<String, HashMap<String, String>>>> = Mutex::new(None);
+
+/// Fields that must be filled in when `REQUIRED_FIELDS` isn't set.
+const DEFAULT_REQUIRED_FIELDS: &str = "first_name,phone";
+
+/// Format of displayed timestamps when `DATE_FORMAT` isn't set.
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Number of contacts shown per page of a listing when `PAGE_SIZE` isn't set.
+pub const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Orders `DEFAULT_SORT` accepts, as `<field>_<direction>`.
+pub const SORT_KEYS: &[&str] = &[
+    "first_asc",
+    "first_desc",
+    "last_asc",
+    "last_desc",
+    "created_asc",
+    "created_desc",
+    "phone_asc",
+    "phone_desc",
+    "company_asc",
+    "company_desc",
+];
+
+/// SQLite pragmas applied to every connection, in order, as `(pragma, default value, allowed
+/// values)`, where no allowed values means any number of milliseconds. Each can be overridden
+/// with the `SQLITE_<PRAGMA>` environment variable.
+pub const CONNECTION_PRAGMAS: &[(&str, &str, &[&str])] = &[
+    // Set first, so a locked database is waited for while the other pragmas are applied too
+    ("busy_timeout", "5000", &[]),
+    (
+        "journal_mode",
+        "WAL",
+        &["DELETE", "TRUNCATE", "PERSIST", "MEMORY", "WAL", "OFF"],
+    ),
+    ("synchronous", "NORMAL", &["OFF", "NORMAL", "FULL", "EXTRA"]),
+    ("foreign_keys", "ON", &["ON", "OFF"]),
+];
+
+/// Size of the database in megabytes above which a warning is shown when
+/// `DATABASE_SIZE_WARNING_MB` isn't set.
+const DEFAULT_SIZE_WARNING_MB: u64 = 1024;
+
+/// Minimum number of digits of a complete phone number when neither `MIN_PHONE_DIGITS` nor a
+/// known `PHONE_REGION` is set.
+pub const DEFAULT_MIN_PHONE_DIGITS: usize = 7;
+
+/// Minimum number of digits of a complete phone number, as dialed within the region, for the
+/// regions whose numbers don't fit the default: longer fixed-length plans and small countries
+/// with short numbers.
+const REGION_MIN_PHONE_DIGITS: &[(&str, usize)] = &[
+    ("US", 10),
+    ("CA", 10),
+    ("FR", 10),
+    ("GB", 10),
+    ("IN", 10),
+    ("AU", 9),
+    ("DE", 5),
+    ("AT", 5),
+    ("FK", 5),
+    ("SB", 5),
+    ("TV", 5),
+    ("NU", 4),
+];
+
+/// Maximum number of characters per field when `MAX_FIELD_LENGTHS` doesn't override it.
+const DEFAULT_MAX_FIELD_LENGTHS: &[(&str, usize)] = &[
+    ("first_name", 256),
+    ("last_name", 256),
+    ("phone", 64),
+    ("extension", 10),
+    ("company", 256),
+    ("email", 254),
+    ("address", 512),
+    ("website", 2048),
+];
+
+/// Reads a setting from the environment, loading `.env` first, and falls back to the value
+/// stored in the `settings` table.
+///
+/// # Returns
+///
+/// The trimmed value of the setting, or `None` if it is unset or blank.
+fn var(name: &str) -> Option<String> {
+    env_var(name)
+        .or_else(|| stored(name))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Reads a setting from the environment only, loading `.env` first.
+///
+/// # Returns
+///
+/// The value of the variable, or `None` if it is unset.
+pub fn env_var(name: &str) -> Option<String> {
+    dotenv().ok();
+    env::var(name).ok()
+}
+
+/// Reads a setting from the `settings` table, through the cache.
+///
+/// # Returns
+///
+/// The stored value, or `None` if nothing is stored under `name`.
+pub fn stored(name: &str) -> Option<String> {
+    let database_url = connection::database_url().unwrap_or_default();
+    let mut cache = STORED.lock().unwrap_or_else(|err| err.into_inner());
+    let values = cache
+        .get_or_insert_with(HashMap::new)
+        .entry(database_url)
+        .or_insert_with(|| {
+            let mut connection = establish_connection();
+            settings::table
+                .load::<(String, String)>(&mut connection)
+                .map(|rows| rows.into_iter().collect())
+                .unwrap_or_default()
+        });
+    values.get(name).cloned()
+}
+
+/// Forgets the cached values of the `settings` table of the database in use, so they are read
+/// again, e.g. after `PhoneBook::open` opened a database another program may have changed.
+pub fn reload() {
+    let database_url = connection::database_url().unwrap_or_default();
+    let mut cache = STORED.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(databases) = cache.as_mut() {
+        databases.remove(&database_url);
+    }
+}
+
+/// Stores a setting in the `settings` table, or removes it so the default applies again.
+///
+/// # Arguments
+///
+/// * `name` - The name of the setting, one of `CONFIGURABLE`.
+/// * `value` - The new value, or `None` to remove the stored value.
+///
+/// # Returns
+///
+/// `Ok(())` if the setting was stored.
+pub fn store(name: &str, value: Option<&str>) -> QueryResult<()> {
+    let mut connection = establish_connection();
+    match value {
+        Some(value) => set_stored(&mut connection, name, value)?,
+        None => {
+            diesel::delete(settings::table.filter(settings::key.eq(name)))
+                .execute(&mut connection)?;
+        }
+    }
+    let database_url = connection::database_url().unwrap_or_default();
+    let mut cache = STORED.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some(values) = cache
+        .as_mut()
+        .and_then(|databases| databases.get_mut(&database_url))
+    {
+        match value {
+            Some(value) => values.insert(name.to_string(), value.to_string()),
+            None => values.remove(name),
+        };
+    }
+    Ok(())
+}
+
+/// Reads a boolean setting, accepting `true`/`false`, `yes`/`no`, `on`/`off` and `1`/`0`.
+///
+/// # Returns
+///
+/// The setting's value, `default` if it is unset, or an error if it isn't a boolean.
+fn bool_var(name: &str, default: bool) -> Result<bool, String> {
+    match var(name).map(|value| value.to_lowercase()).as_deref() {
+        None => Ok(default),
+        Some("true" | "yes" | "on" | "1") => Ok(true),
+        Some("false" | "no" | "off" | "0") => Ok(false),
+        Some(value) => Err(format!(
+            "Invalid {} setting: expected true or false, got '{}'.",
+            name, value
+        )),
+    }
+}
+
+/// Validates every setting, so configuration mistakes are reported once at startup rather than
+/// in the middle of an operation.
+///
+/// # Returns
+///
+/// `Ok(())` if all settings are valid, otherwise an error message naming the offending setting.
+pub fn validate() -> Result<(), String> {
+    required_fields()?;
+    prompt_order()?;
+    max_field_lengths()?;
+    column_widths()?;
+    standardize_phone()?;
+    title_case_names()?;
+    date_format()?;
+    export_columns()?;
+    default_sort()?;
+    page_size()?;
+    table_style()?;
+    show_dashboard()?;
+    auto_backup()?;
+    csv_delimiter()?;
+    csv_quoting()?;
+    import_encoding()?;
+    prompt_color()?;
+    min_phone_digits()?;
+    size_warning()?;
+    connection_pragmas()?;
+    Ok(())
+}
+
+/// Reads the `REQUIRED_FIELDS` setting: a comma-separated list of contact fields that must be
+/// filled in when creating or updating a contact. Defaults to `first_name,phone`.
+///
+/// # Returns
+///
+/// The required field names, or an error if the setting names a field contacts don't have.
+pub fn required_fields() -> Result<Vec<String>, String> {
+    let setting = var("REQUIRED_FIELDS").unwrap_or_else(|| DEFAULT_REQUIRED_FIELDS.to_string());
+    setting
+        .split(',')
+        .map(|field| field.trim().to_lowercase())
+        .filter(|field| !field.is_empty())
+        .map(|field| {
+            if FIELDS.iter().any(|&(name, _)| name == field) {
+                Ok(field)
+            } else {
+                Err(format!(
+                    "Invalid REQUIRED_FIELDS setting: unknown field '{}'.",
+                    field
+                ))
+            }
+        })
+        .collect()
+}
+
+/// Reads the `PROMPT_ORDER` setting: a comma-separated list of the contact fields prompted for
+/// when creating or updating a contact, in the order they are prompted for, e.g.
+/// `phone,first_name,last_name`. Defaults to every field.
+///
+/// # Returns
+///
+/// The prompted field names, or an error if the setting names a field contacts don't have,
+/// repeats one, or leaves out a field of `REQUIRED_FIELDS`.
+pub fn prompt_order() -> Result<Vec<String>, String> {
+    let Some(setting) = var("PROMPT_ORDER") else {
+        return Ok(FIELDS.iter().map(|&(name, _)| name.to_string()).collect());
+    };
+    let mut fields: Vec<String> = Vec::new();
+    for field in setting.split(',').map(|field| field.trim().to_lowercase()) {
+        if field.is_empty() {
+            continue;
+        }
+        if !FIELDS.iter().any(|&(name, _)| name == field) {
+            return Err(format!(
+                "Invalid PROMPT_ORDER setting: unknown field '{}'.",
+                field
+            ));
+        }
+        if fields.contains(&field) {
+            return Err(format!(
+                "Invalid PROMPT_ORDER setting: field '{}' is listed twice.",
+                field
+            ));
+        }
+        fields.push(field);
+    }
+    if let Some(missing) = required_fields()?
+        .into_iter()
+        .find(|field| !fields.contains(field))
+    {
+        return Err(format!(
+            "Invalid PROMPT_ORDER setting: required field '{}' is missing.",
+            missing
+        ));
+    }
+    if fields.is_empty() {
+        return Err("Invalid PROMPT_ORDER setting: no fields listed.".to_string());
+    }
+    Ok(fields)
+}
+
+/// Reads the `EXPORT_COLUMNS` setting: a comma-separated list of the contact fields written to
+/// CSV exports, in the order of the columns. Defaults to every field.
+///
+/// # Returns
+///
+/// The exported field names, or an error if the setting names a field contacts don't have or
+/// repeats one.
+pub fn export_columns() -> Result<Vec<String>, String> {
+    let Some(setting) = var("EXPORT_COLUMNS") else {
+        return Ok(FIELDS.iter().map(|&(name, _)| name.to_string()).collect());
+    };
+    let mut columns: Vec<String> = Vec::new();
+    for field in setting.split(',').map(|field| field.trim().to_lowercase()) {
+        if field.is_empty() {
+            continue;
+        }
+        if !FIELDS.iter().any(|&(name, _)| name == field) {
+            return Err(format!(
+                "Invalid EXPORT_COLUMNS setting: unknown field '{}'.",
+                field
+            ));
+        }
+        if columns.contains(&field) {
+            return Err(format!(
+                "Invalid EXPORT_COLUMNS setting: field '{}' is listed twice.",
+                field
+            ));
+        }
+        columns.push(field);
+    }
+    if columns.is_empty() {
+        return Err("Invalid EXPORT_COLUMNS setting: no fields listed.".to_string());
+    }
+    Ok(columns)
+}
+
+/// Reads the `MAX_FIELD_LENGTHS` setting: comma-separated `field=length` pairs overriding the
+/// default maximum number of characters of each contact field, e.g. `first_name=64,address=1024`.
+///
+/// # Returns
+///
+/// The maximum length of every contact field, or an error if the setting is malformed or names a
+/// field contacts don't have.
+pub fn max_field_lengths() -> Result<Vec<(String, usize)>, String> {
+    let mut lengths: Vec<(String, usize)> = DEFAULT_MAX_FIELD_LENGTHS
+        .iter()
+        .map(|&(field, length)| (field.to_string(), length))
+        .collect();
+    let Some(setting) = var("MAX_FIELD_LENGTHS") else {
+        return Ok(lengths);
+    };
+    for pair in setting.split(',').filter(|pair| !pair.trim().is_empty()) {
+        let invalid = || format!("Invalid MAX_FIELD_LENGTHS setting: '{}'.", pair.trim());
+        let (field, length) = pair.split_once('=').ok_or_else(invalid)?;
+        let field = field.trim().to_lowercase();
+        let length = length
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|&length| length > 0)
+            .ok_or_else(invalid)?;
+        match lengths.iter_mut().find(|(name, _)| *name == field) {
+            Some(entry) => entry.1 = length,
+            None => {
+                return Err(format!(
+                    "Invalid MAX_FIELD_LENGTHS setting: unknown field '{}'.",
+                    field
+                ))
+            }
+        }
+    }
+    Ok(lengths)
+}
+
+/// Reads the `COLUMN_WIDTHS` setting: comma-separated `field=width` pairs limiting how many
+/// characters of a field are shown in contact listings, e.g. `address=30,email=25`. Longer values
+/// are cut with an ellipsis; the detail view still shows them whole.
+///
+/// # Returns
+///
+/// The configured `(field, width)` pairs, empty if the setting is unset, or an error if the
+/// setting is malformed or names a field contacts don't have.
+pub fn column_widths() -> Result<Vec<(String, usize)>, String> {
+    let Some(setting) = var("COLUMN_WIDTHS") else {
+        return Ok(Vec::new());
+    };
+    let mut widths = Vec::new();
+    for pair in setting.split(',').filter(|pair| !pair.trim().is_empty()) {
+        let invalid = || format!("Invalid COLUMN_WIDTHS setting: '{}'.", pair.trim());
+        let (field, width) = pair.split_once('=').ok_or_else(invalid)?;
+        let field = field.trim().to_lowercase();
+        // One character is kept besides the ellipsis
+        let width = width
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|&width| width >= 2)
+            .ok_or_else(invalid)?;
+        if !FIELDS.iter().any(|&(name, _)| name == field) {
+            return Err(format!(
+                "Invalid COLUMN_WIDTHS setting: unknown field '{}'.",
+                field
+            ));
+        }
+        widths.push((field, width));
+    }
+    Ok(widths)
+}
+
+/// Reads the `STANDARDIZE_PHONE` setting: whether 10-digit phone numbers are rewritten as
+/// `(XXX) XXX-XXXX`. Defaults to `true`; when `false`, phone numbers are stored as entered.
+///
+/// # Returns
+///
+/// Whether to standardize phone numbers, or an error if the setting isn't a boolean.
+pub fn standardize_phone() -> Result<bool, String> {
+    bool_var("STANDARDIZE_PHONE", true)
+}
+
+/// Reads the `TITLE_CASE_NAMES` setting: whether first and last names are title-cased when
+/// contacts are entered or imported, e.g. "john MCDONALD" into "John McDonald". Defaults to
+/// `false`.
+///
+/// # Returns
+///
+/// Whether to title-case names, or an error if the setting isn't a boolean.
+pub fn title_case_names() -> Result<bool, String> {
+    bool_var("TITLE_CASE_NAMES", false)
+}
+
+/// Reads the minimum number of digits of a complete phone number: the `MIN_PHONE_DIGITS` setting
+/// if set, otherwise the minimum of the `PHONE_REGION` setting's region, e.g. 10 for `US` or 4 for
+/// `NU`. Defaults to 7, which also applies to regions without a known minimum.
+///
+/// # Returns
+///
+/// The minimum number of digits, or an error if `MIN_PHONE_DIGITS` isn't a positive number or
+/// `PHONE_REGION` isn't a two-letter code.
+pub fn min_phone_digits() -> Result<usize, String> {
+    if let Some(setting) = var("MIN_PHONE_DIGITS") {
+        return setting
+            .parse::<usize>()
+            .ok()
+            .filter(|&digits| digits > 0)
+            .ok_or_else(|| {
+                format!(
+                    "Invalid MIN_PHONE_DIGITS setting: '{}' is not a positive number.",
+                    setting
+                )
+            });
+    }
+    let Some(region) = var("PHONE_REGION").map(|region| region.to_uppercase()) else {
+        return Ok(DEFAULT_MIN_PHONE_DIGITS);
+    };
+    if region.len() != 2 || !region.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(format!(
+            "Invalid PHONE_REGION setting: '{}' (expected a two-letter code such as US).",
+            region
+        ));
+    }
+    Ok(REGION_MIN_PHONE_DIGITS
+        .iter()
+        .find(|&&(code, _)| code == region)
+        .map_or(DEFAULT_MIN_PHONE_DIGITS, |&(_, digits)| digits))
+}
+
+/// Reads the `DATABASE_SIZE_WARNING_MB` setting: the size of the database in megabytes above
+/// which the user is warned that the phone book is getting large and should be archived.
+/// Defaults to 1024; 0 turns the warning off.
+///
+/// # Returns
+///
+/// The size in bytes, `None` if the warning is turned off, or an error if the setting isn't a
+/// number.
+pub fn size_warning() -> Result<Option<u64>, String> {
+    let megabytes = match var("DATABASE_SIZE_WARNING_MB") {
+        None => DEFAULT_SIZE_WARNING_MB,
+        Some(setting) => setting.parse::<u64>().map_err(|_| {
+            format!(
+                "Invalid DATABASE_SIZE_WARNING_MB setting: '{}' is not a number.",
+                setting
+            )
+        })?,
+    };
+    Ok(Some(megabytes.saturating_mul(1024 * 1024)).filter(|&bytes| bytes > 0))
+}
+
+/// Reads the `SHOW_DASHBOARD` setting: whether an interactive session starts with an overview of
+/// the contact counts. Defaults to `false`.
+///
+/// # Returns
+///
+/// Whether to show the dashboard, or an error if the setting isn't a boolean.
+pub fn show_dashboard() -> Result<bool, String> {
+    bool_var("SHOW_DASHBOARD", false)
+}
+
+/// Reads the `AUTO_BACKUP` setting: whether the database is copied to a timestamped backup file
+/// before operations changing or removing many contacts at once. Defaults to `true`.
+///
+/// # Returns
+///
+/// Whether to back up, or an error if the setting isn't a boolean.
+pub fn auto_backup() -> Result<bool, String> {
+    bool_var("AUTO_BACKUP", true)
+}
+
+/// Reads the `CSV_DELIMITER` setting: the field delimiter of CSV exports, `comma`, `semicolon` (for
+/// locales where spreadsheets expect it) or `tab`. Defaults to `comma`.
+///
+/// # Returns
+///
+/// The delimiter byte, or an error if the setting isn't one of the known delimiters.
+pub fn csv_delimiter() -> Result<u8, String> {
+    match var("CSV_DELIMITER")
+        .map(|value| value.to_lowercase())
+        .as_deref()
+    {
+        None | Some("comma" | ",") => Ok(b','),
+        Some("semicolon" | ";") => Ok(b';'),
+        Some("tab") => Ok(b'\t'),
+        Some(value) => Err(format!(
+            "Invalid CSV_DELIMITER setting: '{}' (expected comma, semicolon or tab).",
+            value
+        )),
+    }
+}
+
+/// Reads the `CSV_QUOTING` setting: which fields of CSV exports are quoted, `minimal` (only those
+/// that need it), `always` or `non_numeric`. Defaults to `minimal`.
+///
+/// # Returns
+///
+/// The quote style, or an error if the setting isn't one of the known styles.
+pub fn csv_quoting() -> Result<QuoteStyle, String> {
+    match var("CSV_QUOTING")
+        .map(|value| value.to_lowercase())
+        .as_deref()
+    {
+        None | Some("minimal") => Ok(QuoteStyle::Necessary),
+        Some("always") => Ok(QuoteStyle::Always),
+        Some("non_numeric") => Ok(QuoteStyle::NonNumeric),
+        Some(value) => Err(format!(
+            "Invalid CSV_QUOTING setting: '{}' (expected minimal, always or non_numeric).",
+            value
+        )),
+    }
+}
+
+/// Reads the `TABLE_STYLE` setting: how the borders of tables are drawn, `rounded` (lines with
+/// rounded corners), `square`, `ascii` for terminals without Unicode, `markdown` to paste tables
+/// into documents, or `none`. Defaults to `rounded`.
+///
+/// # Returns
+///
+/// The comfy-table preset of the style and the modifier applied over it, if any, or an error if
+/// the setting isn't one of the known styles.
+pub fn table_style() -> Result<(&'static str, Option<&'static str>), String> {
+    match var("TABLE_STYLE")
+        .map(|value| value.to_lowercase())
+        .as_deref()
+    {
+        None | Some("rounded") => Ok((UTF8_FULL, Some(UTF8_ROUND_CORNERS))),
+        Some("square") => Ok((UTF8_FULL, None)),
+        Some("ascii") => Ok((ASCII_FULL, None)),
+        Some("markdown") => Ok((ASCII_MARKDOWN, None)),
+        Some("none") => Ok((NOTHING, None)),
+        Some(value) => Err(format!(
+            "Invalid TABLE_STYLE setting: '{}' (expected rounded, square, ascii, markdown or none).",
+            value
+        )),
+    }
+}
+
+/// Reads the `IMPORT_ENCODING` setting: the character encoding of imported CSV files, as a WHATWG
+/// label such as `windows-1252` or `latin1`, for files exported by legacy systems. Defaults to
+/// UTF-8.
+///
+/// # Returns
+///
+/// The encoding, or an error if the label isn't a known encoding.
+pub fn import_encoding() -> Result<&'static Encoding, String> {
+    let Some(label) = var("IMPORT_ENCODING") else {
+        return Ok(UTF_8);
+    };
+    Encoding::for_label(label.as_bytes()).ok_or_else(|| {
+        format!(
+            "Invalid IMPORT_ENCODING setting: unknown encoding '{}'.",
+            label
+        )
+    })
+}
+
+/// Reads the `PLACEHOLDER_VALUES` setting: a comma-separated list of values such as `N/A` that
+/// stand for a missing phone number, email, address or website. Imports store them as empty
+/// fields, and validation rejects them. Defaults to `DEFAULT_PLACEHOLDER_VALUES`.
+///
+/// # Returns
+///
+/// The placeholders, trimmed and lowercase.
+pub fn placeholder_values() -> Vec<String> {
+    match var("PLACEHOLDER_VALUES") {
+        Some(values) => values
+            .split(',')
+            .map(|value| value.trim().to_lowercase())
+            .filter(|value| !value.is_empty())
+            .collect(),
+        None => DEFAULT_PLACEHOLDER_VALUES
+            .iter()
+            .map(|value| value.to_string())
+            .collect(),
+    }
+}
+
+/// Reads the `IMPORT_RULES` setting: the path of a JSON file of rules transforming the fields of
+/// imported contacts, e.g. `[{"field": "address", "action": "uppercase"}]`. The file is read at
+/// each import, so it can be edited between imports.
+///
+/// # Returns
+///
+/// The path, or `None` if the setting is unset.
+pub fn import_rules_file() -> Option<String> {
+    var("IMPORT_RULES")
+}
+
+/// Reads the `SEED_FILE` setting: the path of a CSV file, or a phone's JSON export, whose
+/// contacts are added at startup to a new, empty phone book, e.g. for demos.
+///
+/// # Returns
+///
+/// The path, or `None` if the setting is unset.
+pub fn seed_file() -> Option<String> {
+    var("SEED_FILE")
+}
+
+/// Reads the `DATE_FORMAT` setting: the chrono `strftime` format used to display timestamps.
+/// Defaults to `%Y-%m-%d %H:%M`.
+///
+/// # Returns
+///
+/// The format, or an error if it contains an invalid or unsupported specifier.
+pub fn date_format() -> Result<String, String> {
+    let format = var("DATE_FORMAT").unwrap_or_else(|| DEFAULT_DATE_FORMAT.to_string());
+    if StrftimeItems::new(&format).any(|item| item == Item::Error) {
+        return Err(format!(
+            "Invalid DATE_FORMAT setting: '{}' is not a valid strftime format.",
+            format
+        ));
+    }
+    Ok(format)
+}
+
+/// Reads the `DEFAULT_SORT` setting: the order in which the plain listing shows contacts, one of
+/// `SORT_KEYS`. When unset, contacts are listed in creation order.
+///
+/// # Returns
+///
+/// The sort key, `None` if the setting is unset, or an error if it isn't a known sort key.
+pub fn default_sort() -> Result<Option<String>, String> {
+    let Some(sort) = var("DEFAULT_SORT").map(|sort| sort.to_lowercase()) else {
+        return Ok(None);
+    };
+    if !SORT_KEYS.contains(&sort.as_str()) {
+        return Err(format!(
+            "Invalid DEFAULT_SORT setting: '{}' (expected one of {}).",
+            sort,
+            SORT_KEYS.join(", ")
+        ));
+    }
+    Ok(Some(sort))
+}
+
+/// Reads the `PAGE_SIZE` setting: the number of contacts shown per page of a listing. Defaults
+/// to 20.
+///
+/// # Returns
+///
+/// The page size, or an error if the setting isn't a positive number.
+pub fn page_size() -> Result<usize, String> {
+    let Some(setting) = var("PAGE_SIZE") else {
+        return Ok(DEFAULT_PAGE_SIZE);
+    };
+    match setting.parse::<usize>() {
+        Ok(page_size) if page_size > 0 => Ok(page_size),
+        _ => Err(format!(
+            "Invalid PAGE_SIZE setting: '{}' is not a positive number.",
+            setting
+        )),
+    }
+}
+
+/// Reads the `EMPTY_PLACEHOLDER` setting: the text shown in contact listings in place of empty
+/// fields, e.g. `—`. Defaults to nothing, so empty fields are shown as blank cells.
+///
+/// # Returns
+///
+/// The placeholder, possibly empty.
+pub fn empty_placeholder() -> String {
+    var("EMPTY_PLACEHOLDER").unwrap_or_default()
+}
+
+/// Reads the `PROMPT_PREFIX` setting: text shown before every prompt, e.g. `>>`, so the prompts
+/// stand out from the output. Defaults to nothing.
+///
+/// # Returns
+///
+/// The prefix, possibly empty.
+pub fn prompt_prefix() -> String {
+    var("PROMPT_PREFIX").unwrap_or_default()
+}
+
+/// Reads the `PROMPT_COLOR` setting: the color prompts are shown in, a crossterm color name such
+/// as `cyan` or `dark_yellow`. Defaults to the terminal's own color.
+///
+/// # Returns
+///
+/// The color, `None` if the setting is unset, or an error if it isn't a known color.
+pub fn prompt_color() -> Result<Option<Color>, String> {
+    let Some(name) = var("PROMPT_COLOR") else {
+        return Ok(None);
+    };
+    Color::try_from(name.to_lowercase().as_str())
+        .map(Some)
+        .map_err(|_| {
+            format!(
+                "Invalid PROMPT_COLOR setting: unknown color '{}' (e.g. red, green, cyan or dark_blue).",
+                name
+            )
+        })
+}
+
+/// Reads the SQLite pragmas applied to every connection: `busy_timeout` (default 5000
+/// milliseconds, how long to wait for another connection to release a lock before failing with
+/// "database is locked"), `journal_mode` (default `WAL`), `synchronous` (default `NORMAL`) and
+/// `foreign_keys` (default `ON`), overridden by the `SQLITE_BUSY_TIMEOUT`,
+/// `SQLITE_JOURNAL_MODE`, `SQLITE_SYNCHRONOUS` and `SQLITE_FOREIGN_KEYS` environment variables.
+///
+/// These are read from the environment only, as reading stored settings needs a connection.
+///
+/// # Returns
+///
+/// The `(pragma, value)` pairs, or an error if a variable has a value SQLite doesn't accept.
+pub fn connection_pragmas() -> Result<Vec<(&'static str, String)>, String> {
+    CONNECTION_PRAGMAS
+        .iter()
+        .map(|&(pragma, default, allowed)| {
+            let name = format!("SQLITE_{}", pragma.to_uppercase());
+            let value = match env_var(&name).map(|value| value.trim().to_uppercase()) {
+                None => default.to_string(),
+                Some(value) if value.is_empty() => default.to_string(),
+                Some(value) if allowed.is_empty() => match value.parse::<u32>() {
+                    Ok(milliseconds) => milliseconds.to_string(),
+                    Err(_) => {
+                        return Err(format!(
+                            "Invalid {} setting: '{}' (expected a number of milliseconds).",
+                            name, value
+                        ))
+                    }
+                },
+                // Booleans are accepted in the same forms as the other settings
+                Some(value) if allowed == ["ON", "OFF"] => match value.as_str() {
+                    "ON" | "TRUE" | "YES" | "1" => "ON".to_string(),
+                    "OFF" | "FALSE" | "NO" | "0" => "OFF".to_string(),
+                    _ => return Err(format!("Invalid {} setting: '{}'.", name, value)),
+                },
+                Some(value) if allowed.contains(&value.as_str()) => value,
+                Some(value) => {
+                    return Err(format!(
+                        "Invalid {} setting: '{}' (expected one of {}).",
+                        name,
+                        value,
+                        allowed.join(", ")
+                    ))
+                }
+            };
+            Ok((pragma, value))
+        })
+        .collect()
+}
+
+/// Reads a value persisted in the `settings` table.
+///
+/// # Arguments
+///
+/// * `connection` - The database connection to use.
+/// * `key` - The key of the value.
+///
+/// # Returns
+///
+/// The stored value, or `None` if nothing is stored under `key`.
+pub fn get_stored(connection: &mut SqliteConnection, key: &str) -> QueryResult<Option<String>> {
+    settings::table
+        .filter(settings::key.eq(key))
+        .select(settings::value)
+        .first::<String>(connection)
+        .optional()
+}
+
+/// Persists a value in the `settings` table, replacing any value stored under the same key.
+///
+/// # Arguments
+///
+/// * `connection` - The database connection to use.
+/// * `key` - The key of the value.
+/// * `value` - The value to store.
+///
+/// # Returns
+///
+/// `Ok(())` if the value was stored.
+pub fn set_stored(connection: &mut SqliteConnection, key: &str, value: &str) -> QueryResult<()> {
+    diesel::replace_into(settings::table)
+        .values((settings::key.eq(key), settings::value.eq(value)))
+        .execute(connection)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phone_book::contact::styled_table;
+    use crate::phone_book::library::Error;
+    use crate::test_support::{contact, open_book, set};
+
+    #[test]
+    fn required_email_rejects_a_contact_without_one() {
+        let mut book = open_book();
+        set("REQUIRED_FIELDS", "first_name, email");
+        assert_eq!(required_fields().unwrap(), ["first_name", "email"]);
+        let err = book
+            .add(contact("John", "Doe", "5551234567", ""))
+            .unwrap_err();
+        assert_eq!(err, Error::InvalidContact("Email is required.".to_string()));
+        assert!(book
+            .add(contact("John", "Doe", "", "john@example.com"))
+            .is_ok());
+    }
+
+    #[test]
+    fn required_fields_must_be_contact_fields() {
+        let _book = open_book();
+        assert_eq!(required_fields().unwrap(), ["first_name", "phone"]);
+        set("REQUIRED_FIELDS", "first_name,fax");
+        assert_eq!(
+            required_fields().unwrap_err(),
+            "Invalid REQUIRED_FIELDS setting: unknown field 'fax'."
+        );
+    }
+
+    #[test]
+    fn stored_page_size_is_read_back() {
+        let _book = open_book();
+        assert_eq!(page_size().unwrap(), DEFAULT_PAGE_SIZE);
+        set("PAGE_SIZE", "5");
+        reload();
+        assert_eq!(stored("PAGE_SIZE").as_deref(), Some("5"));
+        assert_eq!(page_size().unwrap(), 5);
+        for invalid in ["0", "-3", "ten"] {
+            set("PAGE_SIZE", invalid);
+            assert!(validate().is_err(), "PAGE_SIZE={}", invalid);
+        }
+    }
+
+    #[test]
+    fn tables_are_drawn_in_the_configured_style() {
+        let _book = open_book();
+        let drawn = || {
+            let mut table = styled_table();
+            table.set_header(vec!["Name"]).add_row(vec!["John"]);
+            table.to_string()
+        };
+        assert!(drawn().starts_with('╭'));
+        set("TABLE_STYLE", "ascii");
+        assert!(drawn().starts_with("+------+"));
+        set("TABLE_STYLE", "Markdown");
+        assert_eq!(drawn(), "| Name |\n|------|\n| John |");
+        set("TABLE_STYLE", "dotted");
+        assert_eq!(
+            validate().unwrap_err(),
+            "Invalid TABLE_STYLE setting: 'dotted' (expected rounded, square, ascii, markdown or none)."
+        );
+    }
+}
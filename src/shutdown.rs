@@ -0,0 +1,81 @@
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Exit status of a process stopped by Ctrl-C, as shells report it.
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Whether the user pressed Ctrl-C.
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Number of writes in progress, which Ctrl-C lets finish before exiting.
+static BUSY: AtomicUsize = AtomicUsize::new(0);
+
+/// Marks a write in progress for as long as it is alive: Ctrl-C pressed meanwhile only exits once
+/// the last guard is dropped, so the write's transaction is committed or rolled back rather than
+/// cut short.
+pub struct BusyGuard;
+
+impl BusyGuard {
+    /// Marks the start of a write.
+    ///
+    /// # Returns
+    ///
+    /// The guard, to keep alive until the write is done.
+    pub fn enter() -> Self {
+        BUSY.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for BusyGuard {
+    /// Marks the end of the write, and exits if Ctrl-C was pressed during it.
+    fn drop(&mut self) {
+        if BUSY.fetch_sub(1, Ordering::SeqCst) == 1 && REQUESTED.load(Ordering::SeqCst) {
+            exit();
+        }
+    }
+}
+
+/// Checks whether the user pressed Ctrl-C, so a long write can stop early rather than run to
+/// the end.
+///
+/// # Returns
+///
+/// `true` once Ctrl-C was pressed.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Simulates Ctrl-C being pressed, or forgets it was, so tests can interrupt a write.
+///
+/// # Arguments
+///
+/// * `requested` - Whether Ctrl-C counts as pressed.
+#[cfg(test)]
+pub(crate) fn set_requested(requested: bool) {
+    REQUESTED.store(requested, Ordering::SeqCst);
+}
+
+/// Installs the Ctrl-C handler, which exits right away when idle, or as soon as the writes in
+/// progress are done.
+///
+/// # Returns
+///
+/// `Ok(())` if the handler was installed.
+pub fn install_handler() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(|| {
+        REQUESTED.store(true, Ordering::SeqCst);
+        if BUSY.load(Ordering::SeqCst) == 0 {
+            exit();
+        }
+        println!();
+        println!("Finishing the current operation before exiting...");
+    })
+}
+
+/// Says goodbye and exits with the status of an interrupted process.
+fn exit() -> ! {
+    println!();
+    println!("Exiting...");
+    process::exit(INTERRUPTED_EXIT_CODE);
+}
@@ -0,0 +1,100 @@
+//! Helpers shared by the unit tests.
+//!
+//! Connections use the database of the phone book last used on the thread, and settings may
+//! come from the environment, which every test shares, so every test touching them, even through
+//! `Contact::new`, opens its own temporary phone book with `open_book`, which also makes the tests
+//! wait for each other.
+
+use std::env;
+use std::fs;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::phone_book::{Contact, PhoneBook};
+use crate::settings;
+
+/// Held by the test using the database.
+static DATABASE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Number of the next temporary database, so no two tests share one.
+static NEXT_DATABASE: AtomicUsize = AtomicUsize::new(0);
+
+/// A phone book stored in a temporary database, deleted when it is dropped.
+pub(crate) struct TestBook {
+    phone_book: PhoneBook,
+    path: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Deref for TestBook {
+    type Target = PhoneBook;
+
+    fn deref(&self) -> &PhoneBook {
+        &self.phone_book
+    }
+}
+
+impl DerefMut for TestBook {
+    fn deref_mut(&mut self) -> &mut PhoneBook {
+        &mut self.phone_book
+    }
+}
+
+impl Drop for TestBook {
+    fn drop(&mut self) {
+        remove_database(&self.path);
+    }
+}
+
+/// Opens an empty phone book in a new temporary database, waiting for the other tests using a
+/// database to finish.
+pub(crate) fn open_book() -> TestBook {
+    let lock = DATABASE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let path = temp_path("sqlite");
+    remove_database(&path);
+    let phone_book = PhoneBook::open(path.to_str().expect("temporary paths are UTF-8"))
+        .expect("the test database opens");
+    TestBook {
+        phone_book,
+        path,
+        _lock: lock,
+    }
+}
+
+/// Returns a path in the temporary directory no other test uses, ending with `extension`.
+pub(crate) fn temp_path(extension: &str) -> PathBuf {
+    env::temp_dir().join(format!(
+        "phone-book-test-{}-{}.{}",
+        process::id(),
+        NEXT_DATABASE.fetch_add(1, Ordering::SeqCst),
+        extension
+    ))
+}
+
+/// Stores a setting in the test database.
+pub(crate) fn set(name: &str, value: &str) {
+    settings::store(name, Some(value)).expect("the setting is stored");
+}
+
+/// Builds a contact with the given names, phone number and email through `Contact::new`.
+pub(crate) fn contact(first_name: &str, last_name: &str, phone: &str, email: &str) -> Contact {
+    Contact::new(
+        first_name.to_string(),
+        last_name.to_string(),
+        email.to_string(),
+        String::new(),
+        phone.to_string(),
+    )
+}
+
+/// Deletes a database along with its write-ahead log.
+fn remove_database(path: &Path) {
+    for suffix in ["", "-wal", "-shm"] {
+        let mut file = path.as_os_str().to_owned();
+        file.push(suffix);
+        let _ = fs::remove_file(file);
+    }
+}
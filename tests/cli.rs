@@ -0,0 +1,952 @@
+//! Tests driving the interactive program with scripted input.
+
+use std::fs;
+use std::io::Read;
+use std::process::{Command, Stdio};
+
+use diesel::connection::SimpleConnection;
+use diesel::{Connection, SqliteConnection};
+
+mod common;
+
+use common::{after, temp_path, write_file, Book};
+
+#[test]
+fn create_fails_without_a_required_email() {
+    let book = Book::new().env("REQUIRED_FIELDS", "first_name,email");
+    let output = book.run("C\nJohn\nDoe\n\n5551234567\n\n\n");
+    assert!(output.contains("Enter email (required): "));
+    assert!(output.contains("Email is required. Contact creation cancelled."));
+    assert!(book.contacts().is_empty());
+}
+
+#[test]
+fn import_preview_shows_the_first_rows() {
+    let book = Book::new();
+    let mut csv = String::from("first_name,last_name,email,address,phone\n");
+    for number in 1..=7 {
+        csv.push_str(&format!(
+            "Name{0},Last{0},n{0}@example.com,{0} Main St,555000000{0}\n",
+            number
+        ));
+    }
+    let file = write_file("csv", csv.as_bytes());
+    let output = book.run(&format!("F\n{}\nn\n", file.path()));
+    assert!(output.contains("Preview of the first 5 of 7 contacts to import:"));
+    for number in 1..=5 {
+        assert!(output.contains(&format!("Name{}", number)));
+        assert!(output.contains(&format!("n{}@example.com", number)));
+    }
+    assert!(!output.contains("Name6"));
+    assert!(output.contains("Import cancelled."));
+    assert!(book.contacts().is_empty());
+}
+
+#[test]
+fn search_results_are_printed_as_csv_without_a_clipboard() {
+    let book = Book::new();
+    book.add(&[
+        ("John", "Doe", "5551230001", "john@example.com"),
+        ("Jane", "Doe", "5551230002", "jane@example.com"),
+        ("Bob", "Ray", "5551230003", "bob@example.com"),
+    ]);
+    let output = book.run("Q\nDoe\ny\n");
+    assert!(output.contains("Here is the CSV:"));
+    let csv = &output[output.find("Here is the CSV:").unwrap()..];
+    assert!(csv.contains("\nfirst_name,last_name,"));
+    assert!(csv.contains("\nJohn,Doe,"));
+    assert!(csv.contains("\nJane,Doe,"));
+    assert!(csv.contains("john@example.com"));
+    assert!(!csv.contains("Bob"));
+}
+
+#[test]
+fn created_contact_shows_its_labeled_emails() {
+    let book = Book::new();
+    let output = book.run(concat!(
+        "C\nJohn\nDoe\n\n5551234567\n\njohn@example.com\n\n\n\n",
+        "work\njohn@work.example\nhome\njohn@home.example\n\n\n",
+        "Q\nJohn\n\n",
+    ));
+    assert!(output.contains("Contact created successfully!"));
+    let details = after(&output, "One contact found:");
+    let work = details.find("john@work.example").unwrap();
+    let home = details.find("john@home.example").unwrap();
+    assert!(work < home);
+    assert!(details.contains("work"));
+    assert!(details.contains("home"));
+}
+
+#[test]
+fn large_import_completes_without_a_progress_bar_when_piped() {
+    let book = Book::new();
+    let mut csv = String::from("first_name,last_name,email,address,phone\n");
+    for number in 0..1234 {
+        csv.push_str(&format!("Name{0},Last{0},,,555{0:07}\n", number));
+    }
+    let file = write_file("csv", csv.as_bytes());
+    let output = book.run(&format!("F\n{}\ny\n", file.path()));
+    assert!(output.contains("Contacts loaded successfully"));
+    assert!(!output.contains("contacts (ETA"));
+    let contacts = book.contacts();
+    assert_eq!(contacts.len(), 1234);
+    assert_eq!(contacts[1233].field("first_name"), "Name1233");
+}
+
+#[test]
+fn cancel_token_aborts_create_without_saving() {
+    let book = Book::new();
+    let output = book.run("C\nJohn\nDoe\n:q\nF\n:q\n");
+    assert!(output.contains("(Type :q at any prompt to cancel.)"));
+    assert!(output.contains("Contact creation cancelled."));
+    assert!(output.contains("Import cancelled."));
+    assert!(book.contacts().is_empty());
+}
+
+#[test]
+fn linked_contacts_show_the_relationship_in_the_detail_view() {
+    let book = Book::new();
+    let ids = book.add(&[
+        ("John", "Doe", "5551234567", ""),
+        ("Jane", "Roe", "5551234568", ""),
+    ]);
+    let output = book.run("L\nRL\n1\n2\nSpouse\nQ\nJohn\n\n");
+    assert!(output.contains("Relationship added successfully!"));
+    let details = after(&output, "One contact found:");
+    assert!(details.contains(&format!("Spouse: Jane Roe (#{})", ids[1])));
+}
+
+#[test]
+fn configured_page_size_is_kept_across_runs() {
+    let book = Book::new();
+    let contacts: Vec<(String, String)> = (1..=7)
+        .map(|number| (format!("Name{}", number), format!("555000000{}", number)))
+        .collect();
+    let contacts: Vec<(&str, &str, &str, &str)> = contacts
+        .iter()
+        .map(|(name, phone)| (name.as_str(), "Doe", phone.as_str(), ""))
+        .collect();
+    book.add(&contacts);
+
+    let output = book.run("S\nPAGE_SIZE\n5\n");
+    assert!(output.contains("Setting saved successfully!"));
+    let output = book.run("L\n\n");
+    assert!(output.contains("Page 1 of 2"));
+    let page = after(&output, "Welcome to the Phone Book!");
+    assert!(page.contains("Name5"));
+    assert!(!page.contains("Name6"));
+
+    let output = book.run("S\nPAGE_SIZE\nnone\n");
+    assert!(output.contains("Invalid PAGE_SIZE setting: 'none' is not a positive number."));
+}
+
+#[test]
+fn update_keeps_the_tags_unless_asked_to_edit_them() {
+    let book = Book::new();
+    let create = "C\nJohn\nDoe\n\n5551234567\n\n\n\n\n\n\nWork, family\n";
+    let output = book.run(create);
+    assert!(output.contains("Contact created successfully!"));
+    // Update the phone number, keeping the tags
+    let output = book.run("L\nU\n1\nJohn\nDoe\n\n5559876543\n\n\n\n\n\nn\nn\n");
+    assert!(output.contains("Contact updated successfully!"));
+    let output = book.run("Q\nJohn\n\n");
+    let details = after(&output, "One contact found:");
+    assert!(details.contains("(555) 987-6543"));
+    assert!(details.contains("Tags: Work, family"));
+    // Edit the tags, completing a prefix to the existing tag
+    let output = book.run("L\nU\n1\nJohn\nDoe\n\n5559876543\n\n\n\n\n\nn\ny\nwor\n");
+    assert!(output.contains("Using existing tag 'Work' for 'wor'."));
+    let output = book.run("Q\nJohn\n\n");
+    let details = after(&output, "One contact found:");
+    assert!(details.contains("Tags: Work\n"));
+}
+
+#[test]
+fn update_keeps_details_left_empty_and_clears_them_with_a_dash() {
+    let book = Book::new();
+    book.run("C\nJohn\nDoe\nAcme\n5551234567\n\njohn@acme.com\n\n\n\n\n\n");
+    // Only the phone number changes and the company is cleared
+    let output = book.run("L\nU\n1\n\n\n-\n5559876543\n\n\n\n\n\nn\nn\n");
+    assert!(output.contains("Enter new first name (required, empty keeps 'John', - clears): "));
+    assert!(output.contains("Contact updated successfully!"));
+    let contact = &book.contacts()[0];
+    assert_eq!(contact.field("first_name"), "John");
+    assert_eq!(contact.field("last_name"), "Doe");
+    assert_eq!(contact.field("company"), "");
+    assert_eq!(contact.field("phone"), "(555) 987-6543");
+    assert_eq!(contact.field("email"), "john@acme.com");
+}
+
+#[test]
+fn short_csv_rows_are_reported_and_skipped() {
+    let book = Book::new();
+    let file = write_file(
+        "csv",
+        b"first_name,last_name,email,address,phone\n\
+          John,Doe,john@example.com,1 Main St,5551234567\n\
+          Short,Row\n\
+          Jane,Roe,jane@example.com,2 Main St,5551234568\n",
+    );
+    let output = book.run(&format!("F\n{}\ny\n", file.path()));
+    assert!(output.contains("Skipping line 3: expected 5 columns, found 2."));
+    assert!(output.contains("Contacts loaded successfully"));
+    let names: Vec<String> = book
+        .contacts()
+        .iter()
+        .map(|contact| contact.field("first_name").to_string())
+        .collect();
+    assert_eq!(names, ["John", "Jane"]);
+}
+
+#[test]
+fn byte_order_mark_before_the_header_is_ignored() {
+    let book = Book::new();
+    let file = write_file(
+        "csv",
+        b"\xef\xbb\xbffirst_name,last_name,email,address,phone\nJohn,Doe,,,5551234567\n",
+    );
+    let output = book.run(&format!("F\n{}\ny\n", file.path()));
+    assert!(output.contains("Contacts loaded successfully"));
+    let contacts = book.contacts();
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(contacts[0].field("first_name"), "John");
+    assert_eq!(contacts[0].field("last_name"), "Doe");
+}
+
+#[test]
+fn website_is_exported_in_the_vcard() {
+    let book = Book::new();
+    let output = book.run("C\nJohn\nDoe\n\n5551234567\n\n\n\nhttps://john.example\n\n\n\n");
+    assert!(output.contains("Contact created successfully!"));
+    let output = book.run("L\nV\n1\n");
+    assert!(output.contains("\r\nURL:https://john.example\r\n"));
+}
+
+#[test]
+fn piped_search_reads_the_whole_query_from_one_line() {
+    let book = Book::new();
+    book.add(&[
+        ("John", "Doe", "5551234567", "john@example.com"),
+        ("Jane", "Doe", "5559876543", "jane@example.com"),
+        ("Mary", "Major", "5550001111", "mary@example.com"),
+    ]);
+    let output = book.run("Q\nDoe\nn\n");
+    // The live view would switch to the alternate screen
+    assert!(!output.contains("\x1b[?1049h"));
+    let results = after(&output, "Enter a search query: ");
+    assert!(results.contains("John"));
+    assert!(results.contains("Jane"));
+    assert!(!results.contains("Mary"));
+}
+
+#[test]
+fn contacts_are_chosen_by_their_index_in_the_last_listing() {
+    let book = Book::new();
+    book.add(&[
+        ("John", "Doe", "5551234567", "john@example.com"),
+        ("Jane", "Roe", "5559876543", "jane@example.com"),
+    ]);
+    let output = book.run("V\n");
+    assert!(output.contains("No contacts are listed. List them first, e.g. with L"));
+    assert!(!output.contains("BEGIN:VCARD"));
+
+    let output = book.run("Q\nRoe\n\nV\n1\nMC\n3\n1\n");
+    assert!(after(&output, "BEGIN:VCARD").contains("\r\nFN:Jane Roe\r\n"));
+    assert!(output.contains("There is no contact #3: enter a number from 1 to 1"));
+    assert!(output.contains("Marked Jane Roe as contacted on"));
+}
+
+#[test]
+fn single_contact_is_printed_as_a_vcard() {
+    let book = Book::new();
+    book.add(&[("John", "Doe", "5551234567", "john@example.com")]);
+    let output = book.run("L\nV\n1\n");
+    let card = after(&output, "BEGIN:VCARD");
+    assert!(card.contains("\r\nFN:John Doe\r\n"));
+    assert!(card.contains("\r\nTEL;TYPE=VOICE:(555) 123-4567\r\n"));
+    assert!(card.contains("\r\nEND:VCARD\r\n"));
+}
+
+#[test]
+fn empty_book_is_reported_for_list_and_delete() {
+    let book = Book::new();
+    let message = "Your phone book is empty — add a contact with C.";
+    let output = book.run("L\n");
+    assert_eq!(output.matches(message).count(), 1);
+    let output = book.run("D\n");
+    assert_eq!(output.matches(message).count(), 1);
+    // The message goes away once a contact is added
+    book.add(&[("John", "Doe", "5551234567", "")]);
+    let output = book.run("L\n");
+    assert!(!output.contains(message));
+    assert!(output.contains("John"));
+}
+
+#[test]
+fn each_tag_is_exported_to_its_own_file() {
+    let book = Book::new();
+    book.run("C\nJohn\nDoe\n\n5551234567\n\n\n\n\n\n\nwork, family\n");
+    book.run("C\nJane\nRoe\n\n5559876543\n\n\n\n\n\n\nwork\n");
+    book.run("C\nMary\nMajor\n\n5550001111\n\n\n\n\n\n\n\n");
+    let directory = common::temp_path("tags");
+    let output = book.run(&format!("TX\n1\n{}\n", directory.display()));
+    let read = |name: &str| fs::read_to_string(directory.join(name)).unwrap();
+    let (work, family, untagged) = (read("work.csv"), read("family.csv"), read("untagged.csv"));
+    let files = fs::read_dir(&directory).unwrap().count();
+    fs::remove_dir_all(&directory).unwrap();
+    assert!(output.contains("Exported 2 contact(s) to"));
+    assert_eq!(files, 3);
+    assert!(work.contains("John,Doe") && work.contains("Jane,Roe"));
+    assert!(family.contains("John,Doe") && !family.contains("Jane"));
+    assert!(untagged.contains("Mary,Major") && !untagged.contains("John"));
+}
+
+#[test]
+fn flagged_contact_is_fixed_from_the_validation_report() {
+    let book = Book::new().env("REQUIRED_FIELDS", "first_name, phone, email");
+    book.add(&[("John", "Doe", "5551234567", "")]);
+    let output = book.run("VA\ny\nf\n\n\n\n\n\njohn@example.com\n\n\n\n");
+    assert!(output.contains("Email is required."));
+    assert!(output.contains("Contact saved."));
+    assert!(output.contains("Fixed 1 of 1 flagged contact(s)."));
+    assert_eq!(book.contacts()[0].field("email"), "john@example.com");
+    let output = book.run("VA\n");
+    assert!(output.contains("All contacts pass validation."));
+}
+
+#[test]
+fn plain_listing_follows_the_default_sort() {
+    let book = Book::new();
+    book.add(&[
+        ("Zed", "Young", "5551234567", ""),
+        ("Amy", "Adams", "5559876543", ""),
+        ("Bob", "Miller", "5550001111", ""),
+    ]);
+    let position = |output: &str, name: &str| output.find(name).unwrap();
+    let output = book.run("L\n");
+    assert!(position(&output, "Young") < position(&output, "Adams"));
+    assert!(position(&output, "Adams") < position(&output, "Miller"));
+
+    let book = book.env("DEFAULT_SORT", "last_asc");
+    let output = book.run("L\n");
+    assert!(position(&output, "Adams") < position(&output, "Miller"));
+    assert!(position(&output, "Miller") < position(&output, "Young"));
+
+    let book = book.env("DEFAULT_SORT", "surname");
+    let output = book.run("");
+    assert!(output.contains("Invalid DEFAULT_SORT setting: 'surname'"));
+}
+
+#[test]
+fn compact_listing_prints_one_aligned_line_per_contact() {
+    let book = Book::new();
+    let ids = book.add(&[
+        ("John", "Doe", "1234567890", "john@x.com"),
+        ("Jo", "Li", "5559876543", "jo@example.com"),
+    ]);
+    let output = book.run_with_args(&["--compact"], "L\n");
+    assert!(!output.contains('╭'));
+    let lines: Vec<&str> = output
+        .lines()
+        .filter_map(|line| line.find('#').map(|start| &line[start..]))
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(&format!(
+        "#{}  John Doe  (123) 456-7890  john@x.com",
+        ids[0]
+    )));
+    assert!(lines[1].starts_with(&format!(
+        "#{}  Jo Li     (555) 987-6543  jo@example.com",
+        ids[1]
+    )));
+}
+
+#[cfg(unix)]
+#[test]
+fn ctrl_c_at_the_prompt_exits_through_the_handler() {
+    let book = Book::new();
+    let mut child = book
+        .command()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The handler is installed before the first prompt
+    let stdout = child.stdout.as_mut().unwrap();
+    let mut output = Vec::new();
+    let mut byte = [0; 1];
+    while !output.ends_with(b"Enter an operation or ? for help: ") {
+        stdout.read_exact(&mut byte).unwrap();
+        output.push(byte[0]);
+    }
+    let interrupted = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(interrupted.success());
+    let (status, rest, _) = common::wait_for(child);
+    assert_eq!(status.code(), Some(130));
+    assert_eq!(rest, "\nExiting...\n");
+}
+
+#[test]
+fn verbose_mode_logs_the_insert_of_a_created_contact() {
+    let book = Book::new();
+    let script = "C\nJohn\nDoe\n\n5551234567\n\n\n\n\n\n\n\n";
+    let (_, log) = book.run_program(&[], script);
+    assert!(!log.contains("[SQL]"));
+    let (output, log) = book.run_program(&["--verbose"], script);
+    assert!(output.contains("Contact created successfully!"));
+    assert!(log
+        .lines()
+        .any(|line| line.starts_with("[SQL] INSERT INTO `contacts`")));
+}
+
+#[test]
+fn saved_search_is_run_again_by_name() {
+    let book = Book::new();
+    book.add(&[
+        ("John", "Doe", "5551234567", "john@work.example"),
+        ("Jane", "Doe", "5559876543", "jane@home.example"),
+        ("Mary", "Major", "5550001111", "mary@work.example"),
+    ]);
+    let output = book.run("SS\nlast_name:Doe email:work\nDoe at work\n");
+    assert!(output.contains("'Doe at work'"));
+    let output = book.run("LS\n");
+    assert!(output.contains("last_name:Doe email:work"));
+    let output = book.run("RS\ndoe at work\nn\n");
+    let results = after(&output, "Running 'Doe at work': last_name:Doe email:work");
+    assert!(results.contains("John"));
+    assert!(!results.contains("Jane"));
+    assert!(!results.contains("Mary"));
+}
+
+#[test]
+fn configured_placeholder_fills_empty_cells() {
+    let book = Book::new();
+    book.add(&[("John", "Doe", "5551234567", "")]);
+    let output = book.run("L\n");
+    assert!(!output.contains('—'));
+    let book = book.env("EMPTY_PLACEHOLDER", "—");
+    let output = book.run("L\n");
+    let row = output.lines().find(|line| line.contains("John")).unwrap();
+    let cells: Vec<&str> = row
+        .split(['│', '┆'])
+        .map(str::trim)
+        .filter(|cell| !cell.is_empty())
+        .collect();
+    assert_eq!(cells[cells.len() - 3..], ["(555) 123-4567", "—", "—"]);
+}
+
+#[test]
+fn extension_is_kept_through_create_and_vcard_export() {
+    let book = Book::new();
+    let output = book.run("C\nJohn\nDoe\n\n5551234567\n12a\n123\n\n\n\n\n\n\n");
+    assert!(output.contains("Phone extension must only contain digits (12a)."));
+    assert!(output.contains("Contact created successfully!"));
+    let contact = &book.contacts()[0];
+    assert_eq!(contact.field("phone"), "(555) 123-4567");
+    assert_eq!(contact.field("extension"), "123");
+    let output = book.run("L\nV\n1\n");
+    assert!(output.contains("\r\nTEL;TYPE=VOICE;VALUE=uri:tel:+15551234567;ext=123\r\n"));
+}
+
+#[test]
+fn bad_email_is_explained_and_asked_again_during_create() {
+    let book = Book::new();
+    let output = book.run("C\nJohn\nDoe\n\n5551234567\n\njohn@\njohn@example.com\n\n\n\n\n\n");
+    assert!(output.contains("Invalid email (john@)."));
+    assert!(output.contains("Contact created successfully!"));
+    let contacts = book.contacts();
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(contacts[0].field("email"), "john@example.com");
+}
+
+#[test]
+fn windows_1252_csv_is_imported_with_its_accents() {
+    let book = Book::new().env("IMPORT_ENCODING", "windows-1252");
+    let file = write_file(
+        "csv",
+        b"first_name,last_name,email,address,phone\nJos\xe9,M\xfcller,,Stra\xdfe 1,5551234567\n",
+    );
+    let output = book.run(&format!("F\n{}\ny\n", file.path()));
+    assert!(output.contains("Contacts loaded successfully"));
+    let contacts = book.contacts();
+    assert_eq!(contacts[0].field("first_name"), "José");
+    assert_eq!(contacts[0].field("last_name"), "Müller");
+    assert_eq!(contacts[0].field("address"), "Straße 1");
+}
+
+#[test]
+fn listing_again_resumes_at_the_page_the_user_stopped_on() {
+    let book = Book::new().env("PAGE_SIZE", "2");
+    book.add(&[
+        ("Ann", "Adams", "5550000001", ""),
+        ("Bob", "Brown", "5550000002", ""),
+        ("Cid", "Clark", "5550000003", ""),
+        ("Dan", "Doyle", "5550000004", ""),
+    ]);
+    let output = book.run("L\nn\n\nL\n\n");
+    let second_listing = after(&output, "Resuming at page 2 of 2.");
+    let second_page = &second_listing[..second_listing.find("Page 2 of 2").unwrap()];
+    assert!(second_page.contains("Clark") && second_page.contains("Doyle"));
+    assert!(!second_page.contains("Adams") && !second_page.contains("Brown"));
+}
+
+#[test]
+fn database_is_backed_up_before_removing_duplicates() {
+    let book = Book::new();
+    book.add(&[
+        ("John", "Doe", "5551234567", ""),
+        ("Johnny", "Doe", "5551234567", "john@example.com"),
+    ]);
+    let output = book.run("DD\n1\n1\ny\n");
+    let backup = after(&output, "Backed up the phone book to ");
+    let backup = &backup[..backup.find(".\n").unwrap()];
+    let backup_size = fs::metadata(backup).map(|metadata| metadata.len());
+    let _ = fs::remove_file(backup);
+    assert!(backup_size.unwrap() > 0);
+    assert!(backup.contains("-before-deduplication-"));
+    assert!(output.find("Backed up").unwrap() < output.find("Removed 1 duplicate").unwrap());
+    assert_eq!(book.contacts().len(), 1);
+
+    let book = Book::new().env("AUTO_BACKUP", "off");
+    book.add(&[
+        ("John", "Doe", "5551234567", ""),
+        ("Johnny", "Doe", "5551234567", ""),
+    ]);
+    let output = book.run("DD\n1\n1\ny\n");
+    assert!(!output.contains("Backed up"));
+    assert!(output.contains("Removed 1 duplicate contact(s)."));
+}
+
+#[test]
+fn large_windows_1252_csv_is_streamed_into_the_book() {
+    let book = Book::new().env("IMPORT_ENCODING", "windows-1252");
+    let mut csv = b"first_name,last_name,email,address,phone\n".to_vec();
+    for number in 0..5000 {
+        csv.extend_from_slice(b"Jos\xe9,M\xfcller,,Stra\xdfe ");
+        csv.extend_from_slice(format!("{0},555{0:07}\n", number).as_bytes());
+    }
+    let file = write_file("csv", &csv);
+    let output = book.run(&format!("F\n{}\ny\n", file.path()));
+    assert!(output.contains("Import 5000 contacts? (y/n): "));
+    assert!(!output.contains("Warning"));
+    let contacts = book.contacts();
+    assert_eq!(contacts.len(), 5000);
+    assert!(contacts
+        .iter()
+        .all(|contact| contact.field("first_name") == "José"
+            && contact.field("last_name") == "Müller"));
+    assert_eq!(contacts[4999].field("address"), "Straße 4999");
+}
+
+#[test]
+fn configured_prefix_appears_before_every_prompt() {
+    let book = Book::new()
+        .env("PROMPT_PREFIX", ">>")
+        .env("PROMPT_COLOR", "red");
+    let output = book.run("");
+    assert!(output.contains(">> Enter an operation or ? for help: "));
+    assert!(output.contains(">> Are you sure you want to exit? (y/n): "));
+    // Piped output is never colored
+    assert!(!output.contains('\u{1b}'));
+
+    let output = book.env("PROMPT_COLOR", "mauve").run("");
+    assert!(output.contains("Invalid PROMPT_COLOR setting: unknown color 'mauve'"));
+}
+
+#[test]
+fn invalid_contact_index_is_explained_and_asked_again() {
+    let book = Book::new();
+    book.add(&[
+        ("John", "Doe", "5551234567", ""),
+        ("Jane", "Roe", "5559876543", ""),
+    ]);
+    let output = book.run("L\nD\nabc\n9\n2\nn\n");
+    let delete = after(&output, "Enter the index of the contact to delete: ");
+    assert!(output
+        .contains("'abc' is not a number: enter the # shown in the listing, or :q to cancel."));
+    assert!(output.contains("There is no contact #9: enter a number from 1 to 2, or :q to cancel."));
+    assert_eq!(
+        output
+            .matches("Enter the index of the contact to delete: ")
+            .count(),
+        3
+    );
+    assert!(delete.contains("Roe"));
+    assert!(delete.contains("Contact deletion cancelled."));
+    assert_eq!(book.contacts().len(), 2);
+}
+
+#[test]
+fn piped_delete_falls_back_to_a_single_indexed_contact() {
+    let book = Book::new();
+    book.add(&[
+        ("John", "Doe", "5551234567", ""),
+        ("Jane", "Roe", "5559876543", ""),
+        ("Mary", "Major", "5550001111", ""),
+    ]);
+    let output = book.run("L\nD\n2\ny\n");
+    assert!(!output.contains("Select the contacts to delete"));
+    assert!(output.contains("Contact at index 2 deleted successfully."));
+    let names: Vec<String> = book
+        .contacts()
+        .iter()
+        .map(|contact| contact.field("first_name").to_string())
+        .collect();
+    assert_eq!(names, ["John", "Mary"]);
+}
+
+#[test]
+fn import_rules_transform_the_stored_values() {
+    let rules = write_file(
+        "json",
+        br#"[
+            {"field": "address", "action": "uppercase"},
+            {"field": "phone", "action": "strip_prefix", "prefix": "+1 "}
+        ]"#,
+    );
+    let book = Book::new().env("IMPORT_RULES", rules.path());
+    let file = write_file(
+        "csv",
+        b"first_name,last_name,email,address,phone\nJohn,Doe,,1 Main St Springfield il,+1 555 123 4567\n",
+    );
+    let output = book.run(&format!("F\n{}\ny\n", file.path()));
+    assert!(output.contains("Applying 2 import rule(s)."));
+    let contacts = book.contacts();
+    assert_eq!(contacts[0].field("address"), "1 MAIN ST SPRINGFIELD IL");
+    assert_eq!(contacts[0].field("phone"), "555 123 4567");
+}
+
+#[test]
+fn placeholder_email_is_imported_as_empty() {
+    let book = Book::new();
+    let file = write_file(
+        "csv",
+        b"first_name,last_name,email,address,phone\nJohn,Doe,N/A,none,5551234567\nJane,Roe,noemail@none.com,1 Main St,5559876543\n",
+    );
+    let output = book.run(&format!("F\n{}\ny\n", file.path()));
+    assert!(output.contains("Contacts loaded successfully"));
+    let contacts = book.contacts();
+    assert_eq!(contacts.len(), 2);
+    assert_eq!(contacts[0].field("email"), "");
+    assert_eq!(contacts[0].field("address"), "");
+    assert_eq!(contacts[1].field("email"), "");
+    assert_eq!(contacts[1].field("address"), "1 Main St");
+}
+
+#[test]
+fn company_is_kept_through_create_listing_and_vcard_export() {
+    let book = Book::new();
+    let output = book.run("C\nJohn\nDoe\nAcme Corp\n5551234567\n\n\n\n\n\n\n\n");
+    assert!(output.contains("Contact created successfully!"));
+    let contact = &book.contacts()[0];
+    assert_eq!(contact.field("company"), "Acme Corp");
+
+    let output = book.run("L\n");
+    let listing = after(&output, "Company");
+    assert!(listing.contains("Acme Corp"));
+
+    let output = book.run("L\nV\n1\n");
+    let card = after(&output, "BEGIN:VCARD");
+    assert!(card.contains("\r\nORG:Acme Corp\r\n"));
+}
+
+#[test]
+fn skipped_import_rows_are_written_to_the_errors_file() {
+    let book = Book::new();
+    let file = write_file(
+        "csv",
+        b"first_name,last_name,email,address,phone,website\n\
+          John,Doe,john@example.com,1 Main St,5551234567,\n\
+          Jane,Roe,,,5559876543,example.com\n\
+          Bob,Ray\n",
+    );
+    let output = book.run(&format!("F\n{}\ny\n", file.path()));
+    assert!(output.contains("Skipped 2 row(s) that could not be imported."));
+    let errors_path = file.path().replace(".csv", ".errors.csv");
+    let errors = fs::read_to_string(&errors_path);
+    let _ = fs::remove_file(&errors_path);
+    let errors = errors.unwrap();
+    let rows: Vec<&str> = errors.lines().collect();
+    assert_eq!(rows.len(), 3, "{}", errors);
+    assert_eq!(
+        rows[0],
+        "line,reason,first_name,last_name,email,address,phone,website"
+    );
+    assert!(rows[1].starts_with("3,Website must start with http:// or https://"));
+    assert!(rows[1].ends_with(",Jane,Roe,,,5559876543,example.com"));
+    assert_eq!(rows[2], "4,\"expected 6 columns, found 2.\",Bob,Ray,,,,");
+    assert_eq!(book.contacts().len(), 1);
+}
+
+#[test]
+fn new_phone_book_is_seeded_once_from_the_seed_file() {
+    let seed = write_file(
+        "csv",
+        b"first_name,last_name,email,address,phone\nJohn,Doe,,,5551234567\nJane,Roe,,,5559876543\n",
+    );
+    let book = Book::new().env("SEED_FILE", seed.path());
+    let output = book.run("");
+    assert!(output.contains(&format!("Seeded 2 contact(s) from '{}'.", seed.path())));
+    let names: Vec<String> = book
+        .contacts()
+        .iter()
+        .map(|contact| contact.field("first_name").to_string())
+        .collect();
+    assert_eq!(names, ["John", "Jane"]);
+
+    // Emptying the phone book doesn't bring the seed contacts back
+    let output = book.run("L\nD\n1\ny\nL\nD\n1\ny\n");
+    assert!(!output.contains("Seeded"));
+    let output = book.run("");
+    assert!(!output.contains("Seeded"));
+    assert!(book.contacts().is_empty());
+}
+
+#[test]
+fn long_address_is_truncated_to_the_configured_width() {
+    let book = Book::new().env("COLUMN_WIDTHS", "address=12");
+    let ids = book.add(&[("John", "Doe", "5551234567", "")]);
+    {
+        let mut phone_book = book.open();
+        let mut contact = phone_book.get(ids[0]).unwrap().unwrap();
+        contact.set_field("address", "1234 Long Avenue, Springfield".to_string());
+        phone_book.update(ids[0], contact).unwrap();
+    }
+    let output = book.run("L\n");
+    assert!(output.contains("1234 Long A…"));
+    assert!(!output.contains("1234 Long Avenue"));
+}
+
+#[test]
+fn piped_listing_is_wrapped_to_the_fallback_width() {
+    let book = Book::new();
+    let ids = book.add(&[(
+        "John",
+        "Doe",
+        "5551234567",
+        "john.doe.with.a.long.address@example.com",
+    )]);
+    {
+        let mut phone_book = book.open();
+        let mut contact = phone_book.get(ids[0]).unwrap().unwrap();
+        contact.set_field("address", "Apartment 12, ".repeat(12));
+        phone_book.update(ids[0], contact).unwrap();
+    }
+    let output = book.run("L\n");
+    let table: Vec<&str> = output
+        .lines()
+        .filter(|line| line.starts_with(['╭', '│', '├', '╞', '╰']))
+        .collect();
+    // The address wraps over several lines instead of widening the table
+    assert!(table.len() > 6);
+    assert!(table.iter().all(|line| line.chars().count() <= 120));
+    assert!(output.contains("Apartment"));
+}
+
+#[test]
+fn names_and_phones_of_matches_are_printed_without_a_clipboard() {
+    let book = Book::new();
+    book.add(&[
+        ("John", "Doe", "5551234567", ""),
+        ("Jane", "Doe", "5559876543", ""),
+        ("Mary", "Major", "5550001111", ""),
+    ]);
+    let output = book.run("CP\nDoe\n");
+    let printed = after(
+        &output,
+        "Enter a search query (leave empty for every contact): ",
+    );
+    assert!(printed.contains("Here is the names and phone numbers of 2 contact(s):"));
+    assert!(printed.contains("John Doe: (555) 123-4567\nJane Doe: (555) 987-6543\n"));
+    assert!(!printed.contains("Mary"));
+}
+
+#[test]
+fn duplicate_email_columns_are_reported_and_one_is_chosen() {
+    let csv = b"first_name,last_name,email,address,phone,email\n\
+                John,Doe,old@example.com,,5551234567,new@example.com\n";
+    let file = write_file("csv", csv);
+    let book = Book::new();
+    let output = book.run(&format!("F\n{}\n2\n6\ny\n", file.path()));
+    assert!(output.contains("Warning: 2 columns are named 'email' (columns 3, 6)."));
+    assert!(output.contains("Enter one of columns 3, 6."));
+    assert_eq!(
+        output
+            .matches("Which column should be imported as 'email'? [3]: ")
+            .count(),
+        2
+    );
+    assert_eq!(book.contacts()[0].field("email"), "new@example.com");
+
+    // Leaving the answer empty keeps the first column
+    let book = Book::new();
+    book.run(&format!("F\n{}\n\ny\n", file.path()));
+    assert_eq!(book.contacts()[0].field("email"), "old@example.com");
+}
+
+#[test]
+fn create_prompts_follow_the_configured_order() {
+    let book = Book::new().env("PROMPT_ORDER", "phone, first_name, email");
+    let output = book.run("C\n5551234567\nJohn\njohn@example.com\n\n\n");
+    let prompts: Vec<usize> = [
+        "Enter phone number (required): ",
+        "Enter first name (required): ",
+        "Enter email (optional): ",
+    ]
+    .iter()
+    .map(|prompt| output.find(prompt).expect(prompt))
+    .collect();
+    assert!(prompts.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(!output.contains("Enter last name"));
+    assert!(output.contains("Contact created successfully!"));
+    let contacts = book.contacts();
+    assert_eq!(contacts[0].field("first_name"), "John");
+    assert_eq!(contacts[0].field("email"), "john@example.com");
+    assert_eq!(contacts[0].field("last_name"), "");
+
+    let output = Book::new().env("PROMPT_ORDER", "last_name,email").run("");
+    assert!(
+        output.contains("Invalid PROMPT_ORDER setting: required field 'first_name' is missing.")
+    );
+}
+
+#[test]
+fn cloned_contact_shares_the_company_of_the_original() {
+    let book = Book::new();
+    book.run("C\nJohn\nDoe\nAcme\n5551234567\n\njohn@acme.com\n\n\n\n\n\n");
+    let output = book.run("L\nCL\n1\nJane\n\n\n\n\njane@acme.com\n\n\n\n");
+    assert!(output.contains("Enter company (optional, empty keeps 'Acme', - clears): "));
+    assert!(output.contains("Contact created successfully from a copy of John Doe!"));
+    let contacts = book.contacts();
+    assert_eq!(contacts.len(), 2);
+    let names: Vec<&str> = contacts
+        .iter()
+        .map(|contact| contact.field("first_name"))
+        .collect();
+    assert_eq!(names, ["John", "Jane"]);
+    assert!(contacts
+        .iter()
+        .all(|contact| contact.field("company") == "Acme" && contact.field("last_name") == "Doe"));
+    assert_eq!(contacts[1].field("email"), "jane@acme.com");
+}
+
+#[test]
+fn search_errors_are_reported_rather_than_shown_as_no_matches() {
+    let book = Book::new();
+    book.add(&[("John", "Doe", "5551234567", "john@example.com")]);
+    SqliteConnection::establish(book.path())
+        .unwrap()
+        .batch_execute("ALTER TABLE emails RENAME COLUMN address TO email_address;")
+        .unwrap();
+    // Punctuation only goes straight to the substring scan, which no longer finds the column
+    let output = book.run("Q\n@\n");
+    let result = after(&output, "Enter a search query: ");
+    assert!(result.starts_with("Error fetching contacts from the database: "));
+    assert!(result.contains("no such column"));
+    assert!(!result.contains("No contacts found"));
+}
+
+#[test]
+fn single_search_match_is_shown_in_detail_with_actions() {
+    let book = Book::new();
+    book.add(&[
+        ("John", "Doe", "5551234567", "john@example.com"),
+        ("Jane", "Doe", "5559876543", "jane@example.com"),
+        ("Mary", "Major", "5550001111", "mary@example.com"),
+    ]);
+    let output = book.run("Q\nMajor\n\n");
+    let result = after(&output, "Enter a search query: ");
+    assert!(result.contains("One contact found:"));
+    assert!(result.contains("mary@example.com"));
+    assert!(result.contains("Edit (e), delete (d), or press Enter to continue: "));
+    assert!(!result.contains("Copy the matching contacts"));
+
+    let output = book.run("Q\nDoe\nn\n");
+    let results = after(&output, "Enter a search query: ");
+    assert!(!results.contains("One contact found:"));
+    assert!(results.contains("Copy the matching contacts to the clipboard as CSV? (y/n): "));
+
+    let output = book.run("Q\nMajor\nd\ny\n");
+    assert!(output.contains("One contact found:"));
+    let names: Vec<String> = book
+        .contacts()
+        .iter()
+        .map(|contact| contact.field("first_name").to_string())
+        .collect();
+    assert_eq!(names, ["John", "Jane"]);
+}
+
+#[test]
+fn database_option_overrides_the_database_url() {
+    let env_book = Book::new();
+    env_book.add(&[("Ann", "Adams", "5550000001", "")]);
+    let flag_book = Book::new();
+    flag_book.add(&[("Bob", "Brown", "5550000002", "")]);
+
+    let output = env_book.run_with_args(&["--database", flag_book.path()], "L\n");
+    assert!(output.contains("Bob"));
+    assert!(!output.contains("Ann"));
+
+    // A new database is created and migrated
+    let new_book = Book::new();
+    let option = format!("--database={}", new_book.path());
+    let output = env_book.run_with_args(
+        &[option.as_str()],
+        "C\nCid\nClark\n\n5550000003\n\n\n\n\n\n\n\n",
+    );
+    assert!(output.contains("Contact created successfully!"));
+    let names = |book: &Book| -> Vec<String> {
+        book.contacts()
+            .iter()
+            .map(|contact| contact.field("first_name").to_string())
+            .collect()
+    };
+    assert_eq!(names(&new_book), ["Cid"]);
+    assert_eq!(names(&env_book), ["Ann"]);
+
+    let (_, stderr) = env_book.run_program(&["--database"], "");
+    assert!(stderr.contains("Missing path after --database"));
+}
+
+#[test]
+fn vcard_export_names_the_owner_as_sender() {
+    let book = Book::new();
+    book.add(&[("John", "Doe", "5551234567", "john@example.com")]);
+    let path = temp_path("vcf");
+    let output = book.run(&format!(
+        "SO\nAda\nLovelace\n\n5559876543\n\nada@example.com\n\n\n\nVC\n{}\n",
+        path.display()
+    ));
+    assert!(output.contains("Owner profile saved successfully!"));
+    let cards = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(cards.contains("\r\nFN:John Doe\r\n"));
+    assert!(cards
+        .contains("\r\nSOURCE:mailto:ada@example.com\r\nX-SENDER:Ada Lovelace\r\nEND:VCARD\r\n"));
+}
+
+#[test]
+fn imported_rows_with_an_invalid_email_are_skipped() {
+    let book = Book::new();
+    let file = write_file(
+        "csv",
+        b"first_name,last_name,email,address,phone\n\
+          John,Doe,john@example.com,,5551234567\n\
+          Jane,Roe,jane@,,5559876543\n",
+    );
+    let output = book.run(&format!("F\n{}\ny\n", file.path()));
+    assert!(output.contains("Skipped 1 row(s) that could not be imported."));
+    let errors_path = file.path().replace(".csv", ".errors.csv");
+    let errors = fs::read_to_string(&errors_path);
+    let _ = fs::remove_file(&errors_path);
+    assert!(errors
+        .unwrap()
+        .contains("3,Invalid email (jane@).,Jane,Roe,jane@,"));
+    let contacts = book.contacts();
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(contacts[0].field("email"), "john@example.com");
+}
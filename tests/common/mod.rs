@@ -0,0 +1,251 @@
+//! Helpers shared by the integration tests: temporary phone books, filled in through the library
+//! API and driven through the command-line program with scripted input.
+
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use phone_book_management_cli_rust::phone_book::{Contact, PhoneBook};
+
+/// Number of the next temporary file, so no two tests share one.
+static NEXT_FILE: AtomicUsize = AtomicUsize::new(0);
+
+/// How long the program may run before a test gives up on it, e.g. because the script didn't
+/// reach the exit prompt.
+const RUN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns a path in the temporary directory no other test uses, ending with `extension`.
+pub fn temp_path(extension: &str) -> PathBuf {
+    env::temp_dir().join(format!(
+        "phone-book-it-{}-{}.{}",
+        process::id(),
+        NEXT_FILE.fetch_add(1, Ordering::SeqCst),
+        extension
+    ))
+}
+
+/// A phone book stored in a temporary database, deleted when it is dropped.
+pub struct Book {
+    path: PathBuf,
+    env: Vec<(String, String)>,
+}
+
+impl Book {
+    /// Creates a phone book in a new temporary database.
+    pub fn new() -> Self {
+        Self {
+            path: temp_path("sqlite"),
+            env: Vec::new(),
+        }
+    }
+    /// Sets an environment variable, e.g. a setting, for every run of the program.
+    pub fn env(mut self, name: &str, value: &str) -> Self {
+        self.env.push((name.to_string(), value.to_string()));
+        self
+    }
+    /// Returns the path of the temporary database.
+    pub fn path(&self) -> &str {
+        self.path.to_str().expect("temporary paths are UTF-8")
+    }
+    /// Opens the phone book through the library API.
+    pub fn open(&self) -> PhoneBook {
+        PhoneBook::open(self.path()).expect("the test database opens")
+    }
+    /// Adds contacts through the library API.
+    ///
+    /// Each contact is given as its first name, last name, phone number and email.
+    ///
+    /// # Returns
+    ///
+    /// The ids of the new contacts.
+    pub fn add(&self, contacts: &[(&str, &str, &str, &str)]) -> Vec<i32> {
+        let mut phone_book = self.open();
+        contacts
+            .iter()
+            .map(|&(first_name, last_name, phone, email)| {
+                phone_book
+                    .add(contact(first_name, last_name, phone, email))
+                    .expect("the contact is added")
+            })
+            .collect()
+    }
+    /// Lists the contacts through the library API, in creation order.
+    pub fn contacts(&self) -> Vec<Contact> {
+        let phone_book = self.open();
+        phone_book.list(None).expect("the contacts are listed")
+    }
+    /// Runs the program on the phone book, typing `input` followed by the exit operation.
+    ///
+    /// # Returns
+    ///
+    /// Everything the program printed to standard output.
+    pub fn run(&self, input: &str) -> String {
+        self.run_with_args(&[], input)
+    }
+    /// Runs the program like `run`, with command-line arguments.
+    pub fn run_with_args(&self, args: &[&str], input: &str) -> String {
+        self.run_program(args, input).0
+    }
+    /// Runs the program like `run_with_args`.
+    ///
+    /// # Returns
+    ///
+    /// Everything the program printed to standard output and to standard error.
+    pub fn run_program(&self, args: &[&str], input: &str) -> (String, String) {
+        let mut command = self.command();
+        command.args(args);
+        run_program(command, &format!("{}E\ny\n", input))
+    }
+    /// Builds the command running the program on this phone book, without input or arguments.
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_phone-book-management-cli-rust"));
+        command
+            .env("DATABASE_URL", self.path())
+            .env("NO_COLOR", "1")
+            // Without a display, copies fall back to printing instead of taking the clipboard
+            .env_remove("DISPLAY")
+            .env_remove("WAYLAND_DISPLAY")
+            .envs(self.env.iter().map(|(name, value)| (name, value)));
+        command
+    }
+}
+
+impl Drop for Book {
+    fn drop(&mut self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let mut file = self.path.as_os_str().to_owned();
+            file.push(suffix);
+            let _ = fs::remove_file(file);
+        }
+    }
+}
+
+/// Runs a program, typing `input`, and waits for it to finish.
+///
+/// # Returns
+///
+/// Everything the program printed to standard output and to standard error.
+///
+/// # Panics
+///
+/// If the program doesn't finish within `RUN_TIMEOUT`, showing what it printed.
+pub fn run_program(mut command: Command, input: &str) -> (String, String) {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("the program starts");
+    let written = child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input.as_bytes());
+    // The program may exit before reading its input, e.g. when a setting is invalid
+    if let Err(err) = written {
+        assert_eq!(err.kind(), ErrorKind::BrokenPipe, "the input is written");
+    }
+    let (_, stdout, stderr) = wait_for(child);
+    (stdout, stderr)
+}
+
+/// Waits for a program started with piped output to finish, reading what is left of its output.
+///
+/// # Returns
+///
+/// The exit status, and everything the program printed to standard output and to standard error
+/// that wasn't read yet.
+///
+/// # Panics
+///
+/// If the program doesn't finish within `RUN_TIMEOUT`, showing what it printed.
+pub fn wait_for(mut child: Child) -> (ExitStatus, String, String) {
+    let stdout = read_in_background(child.stdout.take().expect("stdout is piped"));
+    let stderr = read_in_background(child.stderr.take().expect("stderr is piped"));
+    let started = Instant::now();
+    // Unlike `wait`, `try_wait` leaves stdin open, so the program doesn't read an end of input
+    let status = loop {
+        if let Some(status) = child.try_wait().expect("the program is running") {
+            break status;
+        }
+        if started.elapsed() > RUN_TIMEOUT {
+            let _ = child.kill();
+            let output = stdout.join().unwrap();
+            let skipped = output.chars().count().saturating_sub(2000);
+            let tail: String = output.chars().skip(skipped).collect();
+            panic!("the program didn't exit, it printed:\n{}", tail);
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    (status, stdout.join().unwrap(), stderr.join().unwrap())
+}
+
+/// Reads a stream to the end on another thread, so the program never blocks on a full pipe.
+fn read_in_background(mut stream: impl Read + Send + 'static) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        stream.read_to_end(&mut output).expect("the output is read");
+        String::from_utf8_lossy(&output).into_owned()
+    })
+}
+
+/// Builds a contact with the given names, phone number and email through `Contact::new`.
+pub fn contact(first_name: &str, last_name: &str, phone: &str, email: &str) -> Contact {
+    Contact::new(
+        first_name.to_string(),
+        last_name.to_string(),
+        email.to_string(),
+        String::new(),
+        phone.to_string(),
+    )
+}
+
+/// Writes a temporary file, deleted when the returned guard is dropped.
+pub fn write_file(extension: &str, contents: &[u8]) -> TempFile {
+    let path = temp_path(extension);
+    fs::write(&path, contents).expect("the file is written");
+    TempFile(path)
+}
+
+/// A temporary file, deleted when it is dropped along with the report of skipped rows an import
+/// writes next to it.
+pub struct TempFile(pub PathBuf);
+
+impl TempFile {
+    /// Returns the path of the file.
+    pub fn path(&self) -> &str {
+        self.0.to_str().expect("temporary paths are UTF-8")
+    }
+}
+
+impl AsRef<Path> for TempFile {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+        let _ = fs::remove_file(self.0.with_extension("errors.csv"));
+    }
+}
+
+/// Returns what the program printed after the last occurrence of `marker`, e.g. a prompt.
+///
+/// # Panics
+///
+/// If `marker` wasn't printed.
+pub fn after<'a>(output: &'a str, marker: &str) -> &'a str {
+    let start = output
+        .rfind(marker)
+        .unwrap_or_else(|| panic!("'{}' wasn't printed in:\n{}", marker, output));
+    &output[start + marker.len()..]
+}
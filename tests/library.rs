@@ -0,0 +1,226 @@
+//! Tests using the phone book as a library, without any input.
+
+use std::env;
+
+mod common;
+
+use common::{contact, Book};
+use phone_book_management_cli_rust::phone_book::{Error, PhoneBook, SortOrder};
+use phone_book_management_cli_rust::settings;
+
+#[test]
+fn contacts_are_added_updated_and_deleted_through_the_library() {
+    let book = Book::new();
+    let mut phone_book = book.open();
+
+    let id = phone_book
+        .add(contact("John", "Doe", "5551234567", "john@example.com"))
+        .expect("the contact is added");
+    let saved = phone_book.get(id).unwrap().expect("the contact is found");
+    assert_eq!(saved.field("first_name"), "John");
+    assert_eq!(saved.field("email"), "john@example.com");
+
+    let mut changed = saved.clone();
+    changed.set_field("email", "jdoe@example.com".to_string());
+    assert_eq!(phone_book.update(id, changed), Ok(true));
+    let updated = phone_book.get(id).unwrap().expect("the contact is found");
+    assert_eq!(updated.field("email"), "jdoe@example.com");
+    assert_eq!(updated.field("last_name"), "Doe");
+
+    assert!(phone_book.delete(id).unwrap());
+    assert!(phone_book.get(id).unwrap().is_none());
+    assert!(!phone_book.delete(id).unwrap());
+}
+
+#[test]
+fn each_phone_book_keeps_its_own_database() {
+    let first_book = Book::new();
+    let second_book = Book::new();
+    let mut first = first_book.open();
+    let mut second = second_book.open();
+
+    let ann = first
+        .add(contact("Ann", "Adams", "5550000001", ""))
+        .expect("the contact is added");
+    second
+        .add(contact("Bob", "Brown", "5550000002", ""))
+        .expect("the contact is added");
+    let names = |phone_book: &PhoneBook| -> Vec<String> {
+        phone_book
+            .list(None)
+            .unwrap()
+            .iter()
+            .map(|contact| contact.field("first_name").to_string())
+            .collect()
+    };
+    assert_eq!(names(&first), ["Ann"]);
+    assert_eq!(names(&second), ["Bob"]);
+    assert_eq!(first.get(ann).unwrap().unwrap().field("first_name"), "Ann");
+}
+
+#[test]
+fn library_errors_tell_what_went_wrong() {
+    let missing_directory = env::temp_dir().join("phone-book-missing-directory/contacts.sqlite");
+    assert!(matches!(
+        PhoneBook::open(missing_directory.to_str().unwrap()),
+        Err(Error::Connection(_))
+    ));
+
+    let book = Book::new();
+    let mut phone_book = book.open();
+    assert_eq!(
+        phone_book.add(contact("", "Doe", "5551234567", "")),
+        Err(Error::InvalidContact("First name is required.".to_string()))
+    );
+    assert_eq!(
+        Error::InvalidContact("First name is required.".to_string()).to_string(),
+        "First name is required."
+    );
+}
+
+#[test]
+fn invalid_contacts_are_rejected_without_being_saved() {
+    let book = Book::new();
+    let mut phone_book = book.open();
+
+    let mut invalid = contact("John", "Doe", "5551234567", "john@example.com");
+    invalid.set_field("website", "example.com".to_string());
+    assert!(phone_book.add(invalid).is_err());
+    for email in ["foo@", "a@@b"] {
+        let invalid = contact("John", "Doe", "5551234567", email);
+        assert_eq!(
+            phone_book.add(invalid).unwrap_err(),
+            Error::InvalidContact(format!("Invalid email ({}).", email))
+        );
+    }
+    let short = contact("John", "Doe", "12345", "");
+    assert!(phone_book.add(short).is_err());
+    assert!(phone_book.list(None).unwrap().is_empty());
+}
+
+#[test]
+fn contacts_cannot_be_updated_to_an_invalid_email() {
+    let book = Book::new();
+    let mut phone_book = book.open();
+    let saved = phone_book
+        .add(contact("John", "Doe", "5551234567", "john@example.com"))
+        .unwrap();
+    let mut changed = phone_book.get(saved).unwrap().unwrap();
+    changed.set_field("email", "john@".to_string());
+    assert!(phone_book.update(saved, changed).is_err());
+    assert_eq!(
+        phone_book.get(saved).unwrap().unwrap().field("email"),
+        "john@example.com"
+    );
+}
+
+#[test]
+fn contacts_are_searched_and_listed_through_the_library() {
+    let book = Book::new();
+    let mut phone_book = book.open();
+    for (first_name, last_name, phone) in [
+        ("Zoe", "Adams", "5550000003"),
+        ("Ann", "Doe", "5550000001"),
+        ("Bob", "Clark", "5550000002"),
+    ] {
+        phone_book
+            .add(contact(first_name, last_name, phone, ""))
+            .expect("the contact is added");
+    }
+
+    let found = phone_book.search("Doe").unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].field("first_name"), "Ann");
+
+    let names = |order| {
+        phone_book
+            .list(order)
+            .unwrap()
+            .iter()
+            .map(|contact| contact.field("first_name").to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(None), ["Zoe", "Ann", "Bob"]);
+    assert_eq!(names(Some(SortOrder::FirstNameAsc)), ["Ann", "Bob", "Zoe"]);
+    assert_eq!(names(Some(SortOrder::LastNameAsc)), ["Zoe", "Bob", "Ann"]);
+}
+
+#[test]
+fn name_search_returns_the_inserted_contacts_sorted_by_name() {
+    let book = Book::new();
+    let ids = book.add(&[
+        ("Joanne", "Dodd", "5550000001", "joanne@example.com"),
+        ("Bob", "Clark", "5550000002", ""),
+        ("Ann", "Doe", "5550000003", "ann@example.com"),
+    ]);
+    let phone_book = book.open();
+
+    let found = phone_book.search_by_name("ann").unwrap();
+    let summary = found
+        .iter()
+        .map(|contact| {
+            (
+                contact.id(),
+                contact.field("first_name").to_string(),
+                contact.field("phone").to_string(),
+                contact.field("email").to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            (
+                Some(ids[2]),
+                "Ann".into(),
+                "(555) 000-0003".into(),
+                "ann@example.com".into()
+            ),
+            (
+                Some(ids[0]),
+                "Joanne".into(),
+                "(555) 000-0001".into(),
+                "joanne@example.com".into()
+            ),
+        ]
+    );
+    let found = phone_book.search_by_name("ANN DOE").unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].id(), Some(ids[2]));
+    assert!(phone_book.search_by_name("zed").unwrap().is_empty());
+}
+
+#[test]
+fn phone_numbers_sort_by_their_digits_whatever_their_format() {
+    let book = Book::new();
+    let mut phone_book = book.open();
+    settings::store("STANDARDIZE_PHONE", Some("off")).unwrap();
+    settings::store("REQUIRED_FIELDS", Some("first_name")).unwrap();
+    for (first_name, phone) in [
+        ("Ann", "555.900.0001"),
+        ("Bob", ""),
+        ("Cid", "(555) 100-0002"),
+        ("Dan", "555-500-0003"),
+        ("Eve", "5551000001"),
+    ] {
+        phone_book
+            .add(contact(first_name, "Doe", phone, ""))
+            .expect("the contact is added");
+    }
+    let names = |order| {
+        phone_book
+            .list(Some(order))
+            .unwrap()
+            .iter()
+            .map(|contact| contact.field("first_name").to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        names(SortOrder::PhoneAsc),
+        ["Eve", "Cid", "Dan", "Ann", "Bob"]
+    );
+    assert_eq!(
+        names(SortOrder::PhoneDesc),
+        ["Ann", "Dan", "Cid", "Eve", "Bob"]
+    );
+}